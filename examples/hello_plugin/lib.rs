@@ -0,0 +1,93 @@
+// 👋 Hello Plugin - Minimal agent plugin that echoes every command back
+//
+// Build with `cargo build --example hello_plugin`, then point
+// `CONDUCTOR_PLUGIN_DIR` at the directory holding the resulting library and
+// spawn it with agent type `plugin:hello`.
+use anyhow::Result;
+use async_trait::async_trait;
+use conductor_max::orchestrator::AgentConfig;
+use conductor_max::plugin::{AgentPlugin, AgentProcessTrait, PluginFeatures};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
+
+struct HelloPlugin;
+
+impl AgentPlugin for HelloPlugin {
+    fn name(&self) -> &str {
+        "hello"
+    }
+
+    fn spawn(&self, config: &AgentConfig) -> Result<Box<dyn AgentProcessTrait>> {
+        let (sender, receiver) = mpsc::channel(100);
+        Ok(Box::new(EchoAgent {
+            id: config.agent_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
+            sender,
+            receiver: Mutex::new(receiver),
+            running: AtomicBool::new(true),
+            commands_sent: AtomicUsize::new(0),
+        }))
+    }
+
+    fn supported_features(&self) -> PluginFeatures {
+        PluginFeatures {
+            raw_input: true,
+            resize: false,
+            streaming_output: true,
+        }
+    }
+}
+
+struct EchoAgent {
+    id: String,
+    sender: mpsc::Sender<Vec<u8>>,
+    receiver: Mutex<mpsc::Receiver<Vec<u8>>>,
+    running: AtomicBool,
+    commands_sent: AtomicUsize,
+}
+
+#[async_trait]
+impl AgentProcessTrait for EchoAgent {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn send_command(&self, command: &str) -> Result<()> {
+        self.commands_sent.fetch_add(1, Ordering::Relaxed);
+        self.sender.send(format!("{}\r\n", command).into_bytes()).await?;
+        Ok(())
+    }
+
+    async fn send_raw(&self, data: &[u8]) -> Result<()> {
+        self.sender.send(data.to_vec()).await?;
+        Ok(())
+    }
+
+    async fn resize(&self, _rows: u16, _cols: u16) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_output(&self) -> Option<Vec<u8>> {
+        self.receiver.lock().await.recv().await
+    }
+
+    async fn kill(&self) -> Result<()> {
+        self.running.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn get_status(&self) -> serde_json::Value {
+        json!({
+            "id": self.id,
+            "type": "plugin:hello",
+            "running": self.running.load(Ordering::Relaxed),
+            "commands_sent": self.commands_sent.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[no_mangle]
+pub fn conductor_plugin_init() -> Box<dyn AgentPlugin> {
+    Box::new(HelloPlugin)
+}
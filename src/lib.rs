@@ -0,0 +1,3 @@
+// 🔱 Conductor Max - Orchestration core shared by the app and agent plugins
+pub mod orchestrator;
+pub mod plugin;
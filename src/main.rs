@@ -1,151 +1,1404 @@
 // 🔱 Conductor Max - AI Orchestration Platform
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::Arc;
-use tauri::{Manager, WebviewWindowBuilder};
-use tracing::info;
+mod window_layout;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager, WebviewWindowBuilder};
+use tracing::{error, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod orchestrator;
-use orchestrator::{AgentOrchestrator, AgentConfig, AgentType};
+use conductor_max::orchestrator::{
+    AgentOrchestrator, AgentConfig, AgentManager, AgentType, ColorScheme, ColorizedChunk, ElectionStrategy,
+    ErrorNotifier, ExitStatus, MessageType, OrchestratorConfig, Schedule, ScheduledCommand, TerminalSnapshot,
+    TranscriptFormat, WorkflowSpec,
+};
+
+use window_layout::{Layout, Rect, AGENT_WINDOW_SIZE};
 
 #[derive(Clone)]
 struct AppState {
     orchestrator: Arc<AgentOrchestrator>,
+    /// The task started by `watch_resources`, if any.
+    resource_watch: Arc<Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    /// Tasks started by `subscribe_agent_json_stream`, by agent id.
+    json_streams: Arc<Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>>,
+}
+
+/// Shortest interval accepted by `watch_resources`.
+const MIN_RESOURCE_WATCH_MS: u64 = 250;
+
+/// The primary monitor's area in logical pixels.
+fn primary_screen(app: &tauri::AppHandle) -> Option<Rect> {
+    let monitor = app.primary_monitor().ok()??;
+    let scale = monitor.scale_factor();
+    Some(Rect {
+        x: monitor.position().x as f64 / scale,
+        y: monitor.position().y as f64 / scale,
+        width: monitor.size().width as f64 / scale,
+        height: monitor.size().height as f64 / scale,
+    })
+}
+
+fn parse_agent_type(agent_type: &str) -> Result<AgentType, String> {
+    let parsed = agent_type.parse::<AgentType>();
+    match &parsed {
+        Ok(agent_type) => info!("Parsed agent type as {}", agent_type),
+        Err(error) => info!("Error: {}", error),
+    }
+    parsed
+}
+
+#[tauri::command]
+async fn spawn_agent(
+    state: tauri::State<'_, AppState>,
+    agent_type: String,
+    api_key: String,
+    agent_id: Option<String>,
+    workspace_path: Option<String>,
+    command_prefix: Option<String>,
+    command_suffix: Option<String>,
+) -> Result<String, String> {
+    info!("🚀 spawn_agent command called with type: {}, id: {:?}", agent_type, agent_id);
+    
+    let agent_type = parse_agent_type(&agent_type)?;
+
+    let config = AgentConfig {
+        api_key,
+        agent_id: agent_id.clone(),
+        workspace_path,
+        command_prefix,
+        command_suffix,
+        ..AgentConfig::new(agent_type)
+    };
+    
+    info!("Calling orchestrator.spawn_agent with config...");
+
+    match state.orchestrator.spawn_agent(config).await {
+        Ok(id) => {
+            info!("✅ Successfully spawned agent with ID: {}", id);
+            Ok(id)
+        }
+        Err(e) => {
+            let error_str = e.to_string();
+            info!("❌ Failed to spawn agent: {}", error_str);
+            Err(error_str)
+        }
+    }
+}
+
+#[tauri::command]
+async fn spawn_agent_from_config(
+    state: tauri::State<'_, AppState>,
+    config: serde_json::Value,
+) -> Result<String, String> {
+    let config: AgentConfig = serde_json::from_value(config)
+        .map_err(|e| format!("Invalid agent config: {}", e))?;
+
+    state.orchestrator
+        .spawn_agent(config)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn dry_run_spawn_agent(
+    state: tauri::State<'_, AppState>,
+    config: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let config: AgentConfig = serde_json::from_value(config)
+        .map_err(|e| format!("Invalid agent config: {}", e))?;
+
+    let dry_run = state.orchestrator.dry_run();
+    let agent_id = dry_run.spawn_agent(config).await
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "agent_id": agent_id,
+        "actions": dry_run.report(),
+    }))
+}
+
+#[tauri::command]
+async fn dry_run_agent_command(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    command: String,
+) -> Result<serde_json::Value, String> {
+    let result = state.orchestrator
+        .command_dry_run(&agent_id, command)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(result).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn spawn_agent_with_context(
+    state: tauri::State<'_, AppState>,
+    config: serde_json::Value,
+    context_files: Vec<String>,
+) -> Result<String, String> {
+    let config: AgentConfig = serde_json::from_value(config)
+        .map_err(|e| format!("Invalid agent config: {}", e))?;
+
+    state.orchestrator
+        .spawn_agent_with_context(config, context_files.into_iter().map(Into::into).collect())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn swap_agent(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    old_id: String,
+    new_config: serde_json::Value,
+) -> Result<String, String> {
+    let new_config: AgentConfig = serde_json::from_value(new_config)
+        .map_err(|e| format!("Invalid agent config: {}", e))?;
+    let agent_type = new_config.agent_type.to_string();
+
+    let new_id = state.orchestrator
+        .swap_agent(&old_id, new_config)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    // The old agent's window now belongs to the new one; point it there
+    if let Some(window) = state.orchestrator.window_label_for(&new_id)
+        .and_then(|label| app.get_webview_window(&label))
+    {
+        let mut url = window.url().map_err(|e| e.to_string())?;
+        url.query_pairs_mut().clear()
+            .append_pair("id", &new_id)
+            .append_pair("type", &agent_type);
+        window.navigate(url).map_err(|e| e.to_string())?;
+        window.set_title(&agent_window_title(&new_id, &agent_type)).map_err(|e| e.to_string())?;
+    }
+    Ok(new_id)
+}
+
+#[tauri::command]
+async fn reset_agent(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .reset_agent(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn add_agent_to_group(
+    state: tauri::State<'_, AppState>,
+    group: String,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .add_to_group(&group, &agent_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_agent_from_group(
+    state: tauri::State<'_, AppState>,
+    group: String,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator.remove_from_group(&group, &agent_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_agent_groups(
+    state: tauri::State<'_, AppState>,
+) -> Result<HashMap<String, Vec<String>>, String> {
+    Ok(state.orchestrator.list_groups())
+}
+
+#[tauri::command]
+async fn set_agent_alias(
+    state: tauri::State<'_, AppState>,
+    alias: String,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .set_alias(&alias, &agent_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn remove_agent_alias(
+    state: tauri::State<'_, AppState>,
+    alias: String,
+) -> Result<(), String> {
+    state.orchestrator.remove_alias(&alias);
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_agent_aliases(
+    state: tauri::State<'_, AppState>,
+) -> Result<HashMap<String, String>, String> {
+    Ok(state.orchestrator.list_aliases())
+}
+
+#[tauri::command]
+async fn validate_agent_config(
+    state: tauri::State<'_, AppState>,
+    agent_type: String,
+    workspace_path: Option<String>,
+) -> Result<(), String> {
+    let config = AgentConfig {
+        workspace_path,
+        ..AgentConfig::new(parse_agent_type(&agent_type)?)
+    };
+
+    state.orchestrator
+        .validate_agent(&config)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn send_to_agent(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    command: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .send_command(&agent_id, &command)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn send_command_ack(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    command: String,
+    collect_ack_ms: Option<u64>,
+    operation_id: Option<String>,
+) -> Result<String, String> {
+    let operation = state.orchestrator
+        .begin_operation(operation_id)
+        .map_err(|e| e.to_string())?;
+    state.orchestrator
+        .send_command_ack(&agent_id, &command, collect_ack_ms, &operation)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Blocks until the agent prints its prompt again and returns the output
+/// since the previous one. Call after `send_command` for a prompt-response
+/// cycle.
+#[tauri::command]
+async fn await_agent_response(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    timeout_ms: u64,
+) -> Result<String, String> {
+    state.orchestrator
+        .await_agent_response(&agent_id, timeout_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn queue_command(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    command: String,
+) -> Result<usize, String> {
+    state.orchestrator
+        .queue_command(&agent_id, command)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn send_next_queued(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<bool, String> {
+    state.orchestrator
+        .send_next_queued(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_queue_depth(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<usize, String> {
+    state.orchestrator
+        .get_pending_queue_depth(&agent_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn flush_agent_queue(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<usize, String> {
+    state.orchestrator
+        .flush_queue(&agent_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn schedule_command(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    command: String,
+    schedule: Schedule,
+) -> Result<String, String> {
+    state.orchestrator
+        .schedule_command(&agent_id, command, schedule)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_scheduled_commands(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ScheduledCommand>, String> {
+    Ok(state.orchestrator.list_scheduled_commands())
+}
+
+#[tauri::command]
+async fn cancel_scheduled_command(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .cancel_scheduled_command(&id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn send_file_as_context(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    path: String,
+    message: Option<String>,
+) -> Result<(), String> {
+    state.orchestrator
+        .send_file_contents(&agent_id, path.into(), message)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn send_raw_to_agent(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    data: Vec<u8>,
+) -> Result<(), String> {
+    state.orchestrator
+        .send_raw(&agent_id, &data)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn repeat_input(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    data: Vec<u8>,
+    count: usize,
+    delay_ms: u64,
+) -> Result<(), String> {
+    state.orchestrator
+        .repeat_input(&agent_id, data, count, delay_ms)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_output(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<Vec<u8>, String> {
+    let output = state.orchestrator
+        .get_agent_output(&agent_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(output.unwrap_or_default())
+}
+
+#[tauri::command]
+async fn flush_agent_output(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .flush_agent_output(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_agent_color_scheme(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    scheme: serde_json::Value,
+) -> Result<(), String> {
+    let scheme: Option<ColorScheme> = serde_json::from_value(scheme)
+        .map_err(|e| format!("Invalid color scheme: {}", e))?;
+
+    state.orchestrator
+        .set_color_scheme(&agent_id, scheme)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_colorized_output(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<Vec<ColorizedChunk>, String> {
+    state.orchestrator
+        .get_colorized_output(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_scrollback(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    lines: Option<usize>,
+) -> Result<Vec<String>, String> {
+    state.orchestrator
+        .get_scrollback(&agent_id, lines)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_screen(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<String, String> {
+    state.orchestrator
+        .get_screen(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn snapshot_terminal(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<TerminalSnapshot, String> {
+    state.orchestrator
+        .snapshot_terminal(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn restore_terminal_view(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    snapshot: TerminalSnapshot,
+) -> Result<(), String> {
+    state.orchestrator
+        .restore_terminal_view(&agent_id, &snapshot)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_output_range(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    start: usize,
+    end: usize,
+) -> Result<serde_json::Value, String> {
+    state.orchestrator
+        .get_output_range(&agent_id, start, end)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_output_stats(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<serde_json::Value, String> {
+    state.orchestrator
+        .get_output_stats(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_binary_outputs(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    since_index: usize,
+) -> Result<Vec<serde_json::Value>, String> {
+    state.orchestrator
+        .get_binary_outputs(&agent_id, since_index)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_agent_collapse_cr(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    state.orchestrator
+        .set_collapse_cr_updates(&agent_id, enabled)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn resize_agent_terminal(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    rows: u16,
+    cols: u16,
+) -> Result<(), String> {
+    state.orchestrator
+        .resize(&agent_id, rows, cols)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_agent_cpu_affinity(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    cpus: Vec<usize>,
+) -> Result<(), String> {
+    state.orchestrator
+        .set_agent_cpu_affinity(&agent_id, cpus)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_agent_env(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    key: String,
+    value: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .set_agent_env(&agent_id, key, value)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_agent_command_wrapper(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    prefix: Option<String>,
+    suffix: Option<String>,
+) -> Result<(), String> {
+    state.orchestrator
+        .set_command_wrapper(&agent_id, prefix, suffix)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Emits `workspace-changed` events as files in the agent's workspace are
+/// created, modified or deleted.
+#[tauri::command]
+async fn watch_agent_workspace(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .watch_agent_workspace(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The agent's workspace files, from `path` (relative to the workspace)
+/// or its root, as `entries` and whether the listing was `truncated`.
+#[tauri::command]
+async fn get_agent_workspace_tree(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    path: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let tree = state.orchestrator
+        .list_agent_workspace(&agent_id, path.as_deref().unwrap_or(""))
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(tree).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn unwatch_agent_workspace(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator.unwatch_agent_workspace(&agent_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn interrupt_agent(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .interrupt_agent(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn kill_agent(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<(), String> {
+    let label = state.orchestrator.window_label_for(&agent_id);
+    state.orchestrator
+        .kill_agent(&agent_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    // The killed agent's window has nothing left to show
+    if let Some(window) = label.and_then(|label| app.get_webview_window(&label)) {
+        window.close().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn wait_for_all_agents(
+    state: tauri::State<'_, AppState>,
+    timeout_secs: u64,
+    operation_id: Option<String>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let operation = state.orchestrator
+        .begin_operation(operation_id)
+        .map_err(|e| e.to_string())?;
+    let results = state.orchestrator
+        .wait_for_all(std::time::Duration::from_secs(timeout_secs), &operation)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(results
+        .into_iter()
+        .map(|(agent_id, status)| {
+            let exit_code = match status {
+                ExitStatus::Exited { code } => Some(code),
+                ExitStatus::Timeout | ExitStatus::Unsupported => None,
+            };
+            serde_json::json!({
+                "agent_id": agent_id,
+                "exit_code": exit_code,
+                "timed_out": status == ExitStatus::Timeout,
+                "unsupported": status == ExitStatus::Unsupported,
+            })
+        })
+        .collect())
+}
+
+/// Cancels a running `send_command_ack`, `wait_for_all_agents` or
+/// `tail_agent_output` call by the `operation_id` it was started with.
+#[tauri::command]
+async fn cancel_operation(
+    state: tauri::State<'_, AppState>,
+    operation_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .cancel_operation(&operation_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Corrects the session's command counters from the live agents' own
+/// counts and reports what changed.
+#[tauri::command]
+async fn reconcile_command_counts(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    Ok(state.orchestrator.reconcile_command_counts().await)
+}
+
+/// Runs a `WorkflowSpec` given as JSON and returns the `WorkflowRun`.
+/// Pass `run_id` to be able to stop the run with `cancel_workflow`.
+#[tauri::command]
+async fn run_workflow(
+    state: tauri::State<'_, AppState>,
+    spec_json: String,
+    run_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let spec: WorkflowSpec = serde_json::from_str(&spec_json)
+        .map_err(|e| format!("Invalid workflow spec: {}", e))?;
+    let operation = state.orchestrator
+        .begin_operation(run_id)
+        .map_err(|e| e.to_string())?;
+    let run = state.orchestrator
+        .run_workflow(spec, &operation)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(run).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn cancel_workflow(
+    state: tauri::State<'_, AppState>,
+    run_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .cancel_operation(&run_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Compares two agents' responses to `prompts`, judged by a third, and
+/// returns the `AbTestResult`. Pass `run_id` to be able to stop the test
+/// with `cancel_ab_test`.
+#[tauri::command]
+async fn run_ab_test(
+    state: tauri::State<'_, AppState>,
+    agent_a_id: String,
+    agent_b_id: String,
+    prompts: Vec<String>,
+    judge_agent_id: String,
+    run_id: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let operation = state.orchestrator
+        .begin_operation(run_id)
+        .map_err(|e| e.to_string())?;
+    let result = state.orchestrator
+        .run_ab_test(&agent_a_id, &agent_b_id, prompts, judge_agent_id, &operation)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(result).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn cancel_ab_test(
+    state: tauri::State<'_, AppState>,
+    run_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .cancel_operation(&run_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Asks the agents `question` at once and returns the `ElectionResult`.
+#[tauri::command]
+async fn run_agent_election(
+    state: tauri::State<'_, AppState>,
+    agent_ids: Vec<String>,
+    question: String,
+    strategy: ElectionStrategy,
+) -> Result<serde_json::Value, String> {
+    let result = state.orchestrator
+        .hold_election(agent_ids, question, strategy)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(result).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_agent_output_filter(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    include_pattern: Option<String>,
+    exclude_pattern: Option<String>,
+) -> Result<(), String> {
+    state.orchestrator
+        .set_agent_output_filter(&agent_id, include_pattern, exclude_pattern)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Applies an agent's output filter to the retained scrollback as well when
+/// `reprocess_existing` is set. Returns how many lines were redacted.
+#[tauri::command]
+async fn reapply_agent_filters(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    reprocess_existing: bool,
+) -> Result<usize, String> {
+    state.orchestrator
+        .reapply_filters(&agent_id, reprocess_existing)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Drops session history records beyond the `keep_last` most recent and/or
+/// older than `before` (RFC 3339). Returns how many were removed.
+#[tauri::command]
+async fn prune_session_history(
+    state: tauri::State<'_, AppState>,
+    keep_last: Option<usize>,
+    before: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<usize, String> {
+    Ok(state.orchestrator.prune_session_history(keep_last, before).await)
+}
+
+#[tauri::command]
+async fn reorder_task_history(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    new_order: Vec<String>,
+) -> Result<(), String> {
+    state.orchestrator
+        .reorder_task_history(&agent_id, new_order)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_task_record(
+    state: tauri::State<'_, AppState>,
+    record_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .delete_task_record(&record_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn replay_commands(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    target_id: Option<String>,
+) -> Result<usize, String> {
+    state.orchestrator
+        .replay_commands(&agent_id, target_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Returns the transcript, or the path it was written to when `path` is set.
+/// Command echoes are left out unless `keep_echo` is set.
+#[tauri::command]
+async fn export_agent_transcript(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    format: TranscriptFormat,
+    path: Option<String>,
+    keep_echo: Option<bool>,
+) -> Result<String, String> {
+    let suppress_echo = !keep_echo.unwrap_or(false);
+    state.orchestrator
+        .export_agent_transcript(&agent_id, format, path.map(std::path::PathBuf::from), suppress_echo)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_sandbox_support() -> Result<serde_json::Value, String> {
+    Ok(AgentManager::sandbox_support())
+}
+
+#[tauri::command]
+async fn get_ipc_batch_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    Ok(state.orchestrator.ipc_batch_stats())
+}
+
+/// Messages sent over the IPC bridge, by type, and how many the frontend
+/// relay skipped for lagging.
+#[tauri::command]
+async fn get_ipc_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    serde_json::to_value(state.orchestrator.ipc_stats())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_resource_snapshot(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    serde_json::to_value(state.orchestrator.get_resource_snapshot())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn auto_detect_agent_prompt(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<String, String> {
+    state.orchestrator
+        .detect_agent_prompt(&agent_id, None)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Sends `data` as is to `agent_ids`, or to every agent without them.
+#[tauri::command]
+async fn broadcast_raw_to_agents(
+    state: tauri::State<'_, AppState>,
+    data: Vec<u8>,
+    agent_ids: Option<Vec<String>>,
+) -> Result<serde_json::Value, String> {
+    let report = match agent_ids {
+        Some(agent_ids) => state.orchestrator.broadcast_raw_to(agent_ids, &data).await,
+        None => state.orchestrator.broadcast_raw(data).await.map_err(|e| e.to_string())?,
+    };
+    serde_json::to_value(report).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_agent_watermark(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<String, String> {
+    state.orchestrator
+        .create_watermark(&agent_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn await_watermark(
+    state: tauri::State<'_, AppState>,
+    watermark_id: String,
+    timeout_ms: u64,
+) -> Result<(), String> {
+    state.orchestrator
+        .await_watermark(&watermark_id, std::time::Duration::from_millis(timeout_ms))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_agent_conversation(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    limit: usize,
+) -> Result<Vec<serde_json::Value>, String> {
+    let turns = state.orchestrator
+        .get_agent_conversation_history(&agent_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let skip = turns.len().saturating_sub(limit);
+    Ok(turns.into_iter().skip(skip).map(|turn| serde_json::json!({
+        "command": turn.record.command,
+        "response": turn.response,
+    })).collect())
+}
+
+#[tauri::command]
+async fn simulate_workflow(
+    state: tauri::State<'_, AppState>,
+    commands: Vec<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    // Each step is `{"agent_id": ..., "command": ...}` or `[agent_id, command]`
+    let commands = commands.iter().enumerate().map(|(index, step)| {
+        let (agent_id, command) = match step {
+            serde_json::Value::Array(pair) if pair.len() == 2 => (&pair[0], &pair[1]),
+            _ => (&step["agent_id"], &step["command"]),
+        };
+        match (agent_id.as_str(), command.as_str()) {
+            (Some(agent_id), Some(command)) => Ok((agent_id.to_string(), command.to_string())),
+            _ => Err(format!("Step {} needs an agent_id and a command", index)),
+        }
+    }).collect::<Result<Vec<_>, String>>()?;
+    
+    #[cfg(feature = "simulation")]
+    {
+        let result = state.orchestrator
+            .simulate(commands)
+            .await
+            .map_err(|e| e.to_string())?;
+        serde_json::to_value(result).map_err(|e| e.to_string())
+    }
+    #[cfg(not(feature = "simulation"))]
+    {
+        let _ = (state, commands);
+        Err("Built without the simulation feature".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_agent_resources(
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<Option<serde_json::Value>, String> {
+    state.orchestrator
+        .get_agent_resources(&agent_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|usage| serde_json::to_value(usage).map_err(|e| e.to_string()))
+        .transpose()
 }
 
+/// Emits a `resource-snapshot` event every `interval_ms`, replacing any
+/// earlier watch. An interval of 0 stops watching.
 #[tauri::command]
-async fn spawn_agent(
+async fn watch_resources(
+    app: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
-    agent_type: String,
-    api_key: String,
-    agent_id: Option<String>,
-    workspace_path: Option<String>,
-) -> Result<String, String> {
-    info!("🚀 spawn_agent command called with type: {}, id: {:?}", agent_type, agent_id);
+    interval_ms: u64,
+) -> Result<(), String> {
+    let mut watch = state.resource_watch.lock().unwrap();
+    if let Some(previous) = watch.take() {
+        previous.abort();
+    }
+    if interval_ms == 0 {
+        return Ok(());
+    }
     
-    let agent_type = match agent_type.as_str() {
-        "claude" => {
-            info!("Parsed agent type as Claude");
-            AgentType::Claude
-        },
-        "gemini" => {
-            info!("Parsed agent type as Gemini");
-            AgentType::Gemini
-        },
-        "bash" => {
-            info!("Parsed agent type as Bash");
-            AgentType::Bash
-        },
-        _ => {
-            let error = format!("Unknown agent type: {}", agent_type);
-            info!("Error: {}", error);
-            return Err(error);
-        },
-    };
+    let interval = std::time::Duration::from_millis(interval_ms.max(MIN_RESOURCE_WATCH_MS));
+    let orchestrator = state.orchestrator.clone();
+    *watch = Some(tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let snapshot = orchestrator.get_resource_snapshot();
+            if let Err(e) = app.emit("resource-snapshot", snapshot) {
+                error!("Failed to emit resource snapshot: {}", e);
+            }
+        }
+    }));
+    Ok(())
+}
 
-    let config = AgentConfig {
-        agent_type,
-        api_key,
-        agent_id: agent_id.clone(),
-        workspace_path,
-    };
+/// Emits an `agent-json-output` event for each JSON object the agent
+/// prints under `jsonl` output framing, replacing any earlier subscription
+/// for it.
+#[tauri::command]
+async fn subscribe_agent_json_stream(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+) -> Result<(), String> {
+    if let Some(previous) = state.json_streams.lock().unwrap().remove(&agent_id) {
+        previous.abort();
+    }
+    let mut objects = state.orchestrator
+        .json_stream(&agent_id)
+        .await
+        .map_err(|e| e.to_string())?;
     
-    info!("Calling orchestrator.spawn_agent with config...");
-
-    match state.orchestrator.spawn_agent(config).await {
-        Ok(id) => {
-            info!("✅ Successfully spawned agent with ID: {}", id);
-            Ok(id)
+    let task_agent_id = agent_id.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        while let Some(object) = futures::StreamExt::next(&mut objects).await {
+            let event = serde_json::json!({ "agent_id": task_agent_id, "object": object });
+            if let Err(e) = app.emit("agent-json-output", event) {
+                error!("Failed to emit JSON output of agent {}: {}", task_agent_id, e);
+            }
         }
-        Err(e) => {
-            let error_str = e.to_string();
-            info!("❌ Failed to spawn agent: {}", error_str);
-            Err(error_str)
+    });
+    state.json_streams.lock().unwrap().insert(agent_id, task);
+    Ok(())
+}
+
+/// Emits `agent-tail-line` events with the agent's last `n` scrollback
+/// lines, then with `follow` each new line until the agent exits or the
+/// operation is cancelled. Following requires an `operation_id`, as the
+/// command only returns once the tail ends.
+#[tauri::command]
+async fn tail_agent_output(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    agent_id: String,
+    n: usize,
+    follow: bool,
+    operation_id: Option<String>,
+) -> Result<(), String> {
+    if follow && operation_id.is_none() {
+        return Err("Following a tail requires an operation_id to cancel it with".to_string());
+    }
+    let operation = state.orchestrator
+        .begin_operation(operation_id)
+        .map_err(|e| e.to_string())?;
+    let mut lines = state.orchestrator
+        .tail(&agent_id, n, follow)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        let line = tokio::select! {
+            line = futures::StreamExt::next(&mut lines) => line,
+            _ = operation.token().cancelled() => None,
+        };
+        let Some(line) = line else {
+            return Ok(());
+        };
+        let event = serde_json::json!({ "agent_id": agent_id, "line": line });
+        if let Err(e) = app.emit("agent-tail-line", event) {
+            error!("Failed to emit tail of agent {}: {}", agent_id, e);
         }
     }
 }
 
 #[tauri::command]
-async fn send_to_agent(
+async fn get_agent_status(
     state: tauri::State<'_, AppState>,
     agent_id: String,
-    command: String,
-) -> Result<(), String> {
+) -> Result<serde_json::Value, String> {
     state.orchestrator
-        .send_command(&agent_id, &command)
+        .get_agent_status(&agent_id)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn send_raw_to_agent(
+async fn clear_agent_error(
     state: tauri::State<'_, AppState>,
     agent_id: String,
-    data: Vec<u8>,
 ) -> Result<(), String> {
-    let agent = state.orchestrator.agents.get(&agent_id)
-        .ok_or_else(|| format!("Agent {} not found", agent_id))?;
-    
-    agent.send_raw(&data).await
+    state.orchestrator
+        .clear_agent_error(&agent_id)
+        .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn get_agent_output(
+async fn list_agents(
     state: tauri::State<'_, AppState>,
-    agent_id: String,
-) -> Result<Vec<u8>, String> {
-    let agent = state.orchestrator.agents.get(&agent_id)
-        .ok_or_else(|| format!("Agent {} not found", agent_id))?;
-    
-    match agent.get_output().await {
-        Some(data) => Ok(data),
-        None => Ok(Vec::new()),
-    }
+) -> Result<Vec<serde_json::Value>, String> {
+    Ok(state.orchestrator.list_agents().await)
 }
 
 #[tauri::command]
-async fn resize_agent_terminal(
+async fn set_agent_history_limit(
     state: tauri::State<'_, AppState>,
     agent_id: String,
-    rows: u16,
-    cols: u16,
+    max: usize,
 ) -> Result<(), String> {
-    let agent = state.orchestrator.agents.get(&agent_id)
-        .ok_or_else(|| format!("Agent {} not found", agent_id))?;
-    
-    agent.resize(rows, cols).await
+    state.orchestrator
+        .set_agent_history_limit(&agent_id, max)
+        .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn kill_agent(
+async fn get_command_analytics(
     state: tauri::State<'_, AppState>,
-    agent_id: String,
+    agent_id: Option<String>,
+    top_n: usize,
+) -> Result<Vec<serde_json::Value>, String> {
+    let commands = state.orchestrator.get_top_commands(agent_id, top_n).await;
+    Ok(commands
+        .into_iter()
+        .map(|(command, count)| serde_json::json!({ "command": command, "count": count }))
+        .collect())
+}
+
+#[tauri::command]
+async fn get_stats_window(
+    state: tauri::State<'_, AppState>,
+    seconds: u64,
+) -> Result<serde_json::Value, String> {
+    let since = i64::try_from(seconds).ok()
+        .and_then(chrono::TimeDelta::try_seconds)
+        .and_then(|window| chrono::Utc::now().checked_sub_signed(window))
+        .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC);
+    let stats = state.orchestrator.stats_since(since).await;
+    serde_json::to_value(stats).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_session_timeline(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<serde_json::Value>, String> {
+    state.orchestrator
+        .get_session_timeline()
+        .await
+        .into_iter()
+        .map(|event| serde_json::to_value(event).map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[tauri::command]
+async fn route_agent_message(
+    state: tauri::State<'_, AppState>,
+    from_id: String,
+    to_id: String,
+    message: String,
 ) -> Result<(), String> {
     state.orchestrator
-        .kill_agent(&agent_id)
+        .route_message(&from_id, &to_id, &message)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Queues `from_id`'s output lines matching `filter` as commands for
+/// `to_id`. Returns the pipe id for `remove_agent_pipe`.
 #[tauri::command]
-async fn get_agent_status(
+async fn create_agent_pipe(
+    state: tauri::State<'_, AppState>,
+    from_id: String,
+    to_id: String,
+    filter: Option<String>,
+) -> Result<String, String> {
+    let filter = filter
+        .map(|pattern| regex::Regex::new(&pattern).map_err(|e| format!("Invalid pipe filter: {}", e)))
+        .transpose()?;
+    let pipe = state.orchestrator
+        .pipe(&from_id, &to_id, filter)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(pipe.id)
+}
+
+#[tauri::command]
+async fn remove_agent_pipe(
+    state: tauri::State<'_, AppState>,
+    pipe_id: String,
+) -> Result<(), String> {
+    state.orchestrator
+        .remove_pipe(&pipe_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_routing_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let counts: serde_json::Map<String, serde_json::Value> = state.orchestrator
+        .get_inter_agent_message_count()
+        .into_iter()
+        .map(|((from, to), count)| (format!("{}->{}", from, to), count.into()))
+        .collect();
+    Ok(serde_json::Value::Object(counts))
+}
+
+#[tauri::command]
+async fn reset_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    state.orchestrator.reset_stats();
+    Ok(())
+}
+
+#[tauri::command]
+async fn export_agent_fleet(
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let configs = state.orchestrator
+        .export_agents_config()
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::to_string_pretty(&configs).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn import_agent_fleet(
+    state: tauri::State<'_, AppState>,
+    json: String,
+) -> Result<Vec<String>, String> {
+    let configs: Vec<AgentConfig> = serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid agent fleet: {}", e))?;
+
+    state.orchestrator
+        .import_agents_config(configs)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn export_session(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    Ok(state.orchestrator.export_session().await)
+}
+
+#[tauri::command]
+async fn get_session_summary(
     state: tauri::State<'_, AppState>,
-    agent_id: String,
 ) -> Result<serde_json::Value, String> {
+    Ok(state.orchestrator.get_session_summary().await)
+}
+
+#[tauri::command]
+async fn save_session(
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
     state.orchestrator
-        .get_agent_status(&agent_id)
+        .save_session()
         .await
+        .map(|path| path.display().to_string())
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn list_agents(
+async fn save_session_template(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> Result<String, String> {
+    state.orchestrator
+        .save_session_template(&name)
+        .await
+        .map(|path| path.display().to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn load_session_template(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> Result<serde_json::Value, String> {
+    let template = state.orchestrator
+        .load_session_template(&name)
+        .await
+        .map_err(|e| e.to_string())?;
+    serde_json::to_value(template).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_templates(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    state.orchestrator
+        .list_templates()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn spawn_from_template(
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> Result<Vec<String>, String> {
+    let template = state.orchestrator
+        .load_session_template(&name)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    state.orchestrator
+        .spawn_from_template(template)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn import_session(
+    state: tauri::State<'_, AppState>,
+    json: String,
+) -> Result<String, String> {
+    state.orchestrator
+        .import_session(&json)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn orchestrator_health(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    Ok(state.orchestrator.health())
+}
+
+#[tauri::command]
+async fn list_plugins(
     state: tauri::State<'_, AppState>,
 ) -> Result<Vec<serde_json::Value>, String> {
-    Ok(state.orchestrator.list_agents().await)
+    Ok(state.orchestrator.list_plugins())
 }
 
 #[tauri::command]
@@ -177,33 +1430,149 @@ async fn open_strategy_window(
 #[tauri::command]
 async fn open_agent_window(
     app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
     agent_id: String,
     agent_type: String,
+    tag: Option<String>,
 ) -> Result<(), String> {
-    let window_id = format!("agent_{}", agent_id);
-    
-    // Check if window already exists
-    if app.get_webview_window(&window_id).is_some() {
-        // Focus existing window
-        if let Some(window) = app.get_webview_window(&window_id) {
+    create_agent_window(&app, &state, &agent_id, &agent_type, tag)
+}
+
+/// Spawns an agent and opens its window, unless the config marks it
+/// `headless`. Returns the agent id.
+#[tauri::command]
+async fn spawn_and_open(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    config: serde_json::Value,
+    tag: Option<String>,
+) -> Result<String, String> {
+    let config: AgentConfig = serde_json::from_value(config)
+        .map_err(|e| format!("Invalid agent config: {}", e))?;
+    let (agent_type, headless) = (config.agent_type.to_string(), config.headless);
+
+    let agent_id = state.orchestrator
+        .spawn_agent(config)
+        .await
+        .map_err(|e| e.to_string())?;
+    if headless {
+        info!("Agent {} is headless; not opening a window", agent_id);
+    } else {
+        create_agent_window(&app, &state, &agent_id, &agent_type, tag)?;
+    }
+    Ok(agent_id)
+}
+
+#[tauri::command]
+async fn list_headless_agents(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<serde_json::Value>, String> {
+    Ok(state.orchestrator.list_headless_agents().await)
+}
+
+fn agent_window_title(agent_id: &str, agent_type: &str) -> String {
+    let short_id: String = agent_id.chars().take(8).collect();
+    format!("🤖 {} Agent - {}", agent_type.to_uppercase(), short_id)
+}
+
+fn create_agent_window(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    agent_id: &str,
+    agent_type: &str,
+    tag: Option<String>,
+) -> Result<(), String> {
+    // Focus the agent's window if it already has one
+    if let Some(label) = state.orchestrator.window_label_for(agent_id) {
+        if let Some(window) = app.get_webview_window(&label) {
             window.set_focus().map_err(|e| e.to_string())?;
+            return Ok(());
         }
-        return Ok(());
+        state.orchestrator.deregister_window(&label);
     }
+    let open_windows = open_agent_windows(app, state).len();
+    let window_id = state.orchestrator.assign_window_label(agent_id, tag);
+    
+    // Cascade new windows so a batch doesn't open stacked on one spot
+    let slot = primary_screen(app)
+        .map(|screen| window_layout::cascade_slot(open_windows, screen));
     
     // Create new agent window
     let url = format!("agent.html?id={}&type={}", agent_id, agent_type);
-    let _window = WebviewWindowBuilder::new(&app, &window_id, 
+    let mut builder = WebviewWindowBuilder::new(app, &window_id, 
         tauri::WebviewUrl::App(url.into()))
-        .title(format!("🤖 {} Agent - {}", agent_type.to_uppercase(), &agent_id[..8]))
-        .inner_size(1024.0, 768.0)
-        .resizable(true)
-        .build()
-        .map_err(|e| e.to_string())?;
+        .title(agent_window_title(agent_id, agent_type))
+        .inner_size(AGENT_WINDOW_SIZE.0, AGENT_WINDOW_SIZE.1)
+        .resizable(true);
+    if let Some(slot) = slot {
+        builder = builder.position(slot.x, slot.y);
+    }
+    let window = builder.build().map_err(|e| {
+        state.orchestrator.deregister_window(&window_id);
+        e.to_string()
+    })?;
     
+    let orchestrator = state.orchestrator.clone();
+    let label = window_id.clone();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::CloseRequested { .. } = event {
+            orchestrator.deregister_window(&label);
+        }
+    });
     Ok(())
 }
 
+/// The open agent windows with their tags, in opening order. Windows that
+/// have gone away without a `CloseRequested` event are deregistered.
+fn open_agent_windows(app: &tauri::AppHandle, state: &AppState) -> Vec<(Option<String>, tauri::WebviewWindow)> {
+    state.orchestrator.open_windows()
+        .into_iter()
+        .filter_map(|(label, tag)| match app.get_webview_window(&label) {
+            Some(window) => Some((tag, window)),
+            None => {
+                state.orchestrator.deregister_window(&label);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Window label of every agent with an open window, by agent id.
+#[tauri::command]
+async fn get_agent_window_labels(
+    state: tauri::State<'_, AppState>,
+) -> Result<HashMap<String, String>, String> {
+    Ok(state.orchestrator.window_labels())
+}
+
+/// Tiles or cascades the open agent windows over the primary monitor,
+/// keeping windows with the same tag next to each other. Returns how many
+/// windows were placed.
+#[tauri::command]
+async fn arrange_agent_windows(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    layout: Layout,
+) -> Result<usize, String> {
+    let screen = primary_screen(&app)
+        .ok_or_else(|| "No monitor available".to_string())?;
+    
+    let mut windows = open_agent_windows(&app, &state);
+    // Stable, so windows keep their opening order within a tag
+    windows.sort_by(|a, b| a.0.cmp(&b.0));
+    
+    let rects = window_layout::arrange(layout, windows.len(), screen);
+    for ((_, window), rect) in windows.iter().zip(&rects) {
+        window.set_position(tauri::LogicalPosition::new(rect.x, rect.y))
+            .map_err(|e| e.to_string())?;
+        window.set_size(tauri::LogicalSize::new(rect.width, rect.height))
+            .map_err(|e| e.to_string())?;
+    }
+    
+    info!("🪟 Arranged {} agent windows", rects.len());
+    Ok(rects.len())
+}
+
 fn main() {
     // Initialize tracing
     tracing_subscriber::registry()
@@ -216,25 +1585,174 @@ fn main() {
 
     info!("🔱 Starting Conductor Max...");
 
-    let orchestrator = Arc::new(AgentOrchestrator::new());
-    let app_state = AppState { orchestrator };
+    let config = OrchestratorConfig::load().unwrap_or_else(|e| {
+        error!("Failed to load settings, using defaults: {:#}", e);
+        OrchestratorConfig::default()
+    });
+    let error_notifications = config.error_notifications;
+    let orchestrator = Arc::new(AgentOrchestrator::with_config(config));
+    if error_notifications {
+        orchestrator.add_output_observer(Arc::new(ErrorNotifier::new()));
+    }
+    let heartbeat = orchestrator.heartbeat_task();
+    let ipc_orchestrator = orchestrator.clone();
+    let app_state = AppState {
+        orchestrator,
+        resource_watch: Arc::new(Mutex::new(None)),
+        json_streams: Arc::new(Mutex::new(HashMap::new())),
+    };
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             spawn_agent,
+            spawn_agent_from_config,
+            spawn_agent_with_context,
+            dry_run_spawn_agent,
+            dry_run_agent_command,
+            swap_agent,
+            reset_agent,
+            add_agent_to_group,
+            remove_agent_from_group,
+            list_agent_groups,
+            set_agent_alias,
+            remove_agent_alias,
+            list_agent_aliases,
+            validate_agent_config,
             send_to_agent,
+            send_command_ack,
+            await_agent_response,
+            queue_command,
+            send_next_queued,
+            get_agent_queue_depth,
+            flush_agent_queue,
+            schedule_command,
+            list_scheduled_commands,
+            cancel_scheduled_command,
+            send_file_as_context,
             send_raw_to_agent,
+            repeat_input,
             get_agent_output,
+            flush_agent_output,
+            set_agent_color_scheme,
+            get_agent_colorized_output,
+            get_agent_scrollback,
+            get_agent_screen,
+            snapshot_terminal,
+            restore_terminal_view,
+            get_agent_output_range,
+            get_agent_output_stats,
+            get_agent_binary_outputs,
+            set_agent_collapse_cr,
             resize_agent_terminal,
+            set_agent_cpu_affinity,
+            set_agent_env,
+            set_agent_command_wrapper,
+            watch_agent_workspace,
+            unwatch_agent_workspace,
+            get_agent_workspace_tree,
+            interrupt_agent,
             kill_agent,
+            wait_for_all_agents,
+            cancel_operation,
+            run_workflow,
+            cancel_workflow,
+            run_ab_test,
+            cancel_ab_test,
+            run_agent_election,
+            reconcile_command_counts,
+            set_agent_output_filter,
+            reapply_agent_filters,
+            prune_session_history,
+            reorder_task_history,
+            delete_task_record,
+            replay_commands,
+            export_agent_transcript,
+            get_sandbox_support,
+            get_ipc_batch_stats,
+            get_ipc_stats,
+            spawn_and_open,
+            list_headless_agents,
+            get_resource_snapshot,
+            get_agent_resources,
+            auto_detect_agent_prompt,
+            broadcast_raw_to_agents,
+            create_agent_watermark,
+            await_watermark,
+            simulate_workflow,
+            get_agent_conversation,
+            watch_resources,
+            subscribe_agent_json_stream,
             get_agent_status,
+            tail_agent_output,
+            clear_agent_error,
             list_agents,
+            set_agent_history_limit,
+            get_command_analytics,
+            get_stats_window,
+            get_session_timeline,
+            route_agent_message,
+            get_routing_stats,
+            create_agent_pipe,
+            remove_agent_pipe,
+            reset_stats,
+            export_agent_fleet,
+            import_agent_fleet,
+            export_session,
+            get_session_summary,
+            save_session,
+            save_session_template,
+            load_session_template,
+            list_templates,
+            spawn_from_template,
+            import_session,
+            orchestrator_health,
+            list_plugins,
             open_strategy_window,
             open_agent_window,
+            get_agent_window_labels,
+            arrange_agent_windows,
         ])
         .setup(|app| {
+            tauri::async_runtime::spawn(heartbeat);
+            
+            // Relay the IPC bridge, and with it typed agent events, to the
+            // frontend as `ipc-message`. Output chunks stay off it; the
+            // frontend reads output through `get_agent_output`
+            let ipc_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut messages = ipc_orchestrator.subscribe_ipc().await;
+                loop {
+                    match messages.recv().await {
+                        Ok(message) if message.message_type == MessageType::Output => {}
+                        Ok(message) => {
+                            // Watched workspaces also get an event of their own
+                            if message.payload["event"] == "workspace_changed" {
+                                let event = serde_json::json!({
+                                    "agent_id": message.agent_id,
+                                    "root": message.payload["root"],
+                                    "created": message.payload["created"],
+                                    "modified": message.payload["modified"],
+                                    "deleted": message.payload["deleted"],
+                                });
+                                if let Err(e) = ipc_handle.emit("workspace-changed", event) {
+                                    error!("Failed to relay workspace change: {}", e);
+                                }
+                            }
+                            if let Err(e) = ipc_handle.emit("ipc-message", message) {
+                                error!("Failed to relay IPC message: {}", e);
+                            }
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                            error!("IPC relay skipped {} messages", skipped);
+                            ipc_orchestrator.record_ipc_lag(skipped);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+            
             let window = app.get_webview_window("main").unwrap();
             
             // Set up window event handlers
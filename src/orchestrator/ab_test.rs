@@ -0,0 +1,110 @@
+// A/B Tests - Comparing two agents' responses with a third agent as judge
+use serde::{Deserialize, Serialize};
+
+/// How long each agent has to answer a prompt, and the judge to decide, as
+/// for workflow steps.
+pub const AB_TEST_TIMEOUT_MS: u64 = super::workflow::DEFAULT_STEP_TIMEOUT_MS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbVerdict {
+    A,
+    B,
+    Tie,
+}
+
+/// One prompt of an A/B test, with both responses and the judge's.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbTestRound {
+    pub prompt: String,
+    pub response_a: String,
+    pub response_b: String,
+    /// Everything the judge answered, escape sequences removed.
+    pub judgement: String,
+    pub verdict: AbVerdict,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AbTestResult {
+    pub a_wins: usize,
+    pub b_wins: usize,
+    pub ties: usize,
+    pub per_prompt: Vec<AbTestRound>,
+}
+
+impl AbTestResult {
+    pub(crate) fn record(&mut self, round: AbTestRound) {
+        match round.verdict {
+            AbVerdict::A => self.a_wins += 1,
+            AbVerdict::B => self.b_wins += 1,
+            AbVerdict::Tie => self.ties += 1,
+        }
+        self.per_prompt.push(round);
+    }
+}
+
+/// The question the judge is asked about one pair of responses, asking for
+/// the answer format `AbVerdict::parse` looks for first.
+pub(crate) fn judge_prompt(response_a: &str, response_b: &str) -> String {
+    format!(
+        "A: {}\nB: {}\nWhich is better? End your answer with a line VERDICT: A, VERDICT: B or VERDICT: tie.",
+        response_a, response_b,
+    )
+}
+
+/// The words of `line`, split at anything not alphanumeric.
+fn words(line: &str) -> Vec<&str> {
+    line.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).collect()
+}
+
+/// The choice `word` names: `A`, `B` or `tie`, ignoring case.
+fn choice(word: &str) -> Option<AbVerdict> {
+    match word.to_ascii_lowercase().as_str() {
+        "a" => Some(AbVerdict::A),
+        "b" => Some(AbVerdict::B),
+        "tie" => Some(AbVerdict::Tie),
+        _ => None,
+    }
+}
+
+impl AbVerdict {
+    /// Reads the judge's verdict from its last `VERDICT:` line, as
+    /// `judge_prompt` asks for. Otherwise it takes the first choice stated
+    /// on the last line that states one, so "A is better than B" is `A`.
+    /// Loose words only count as `A` or `B` in capitals, to tell them from
+    /// the article "a", unless they are the whole answer. Lines echoing
+    /// `prompt` are skipped; a judgement without a verdict counts as a tie.
+    pub fn parse(judgement: &str, prompt: &str) -> Self {
+        let asked: Vec<&str> = prompt.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        let answered: Vec<&str> = judgement.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !asked.iter().any(|asked| line.ends_with(asked)))
+            .collect();
+
+        for line in answered.iter().rev() {
+            let words = words(line);
+            if words.first().is_some_and(|word| word.eq_ignore_ascii_case("verdict")) {
+                if let Some(verdict) = words.get(1).and_then(|&word| choice(word)) {
+                    return verdict;
+                }
+            }
+        }
+        for line in answered.iter().rev() {
+            let words = words(line);
+            if let [word] = words.as_slice() {
+                if let Some(verdict) = choice(word) {
+                    return verdict;
+                }
+            }
+            let stated = words.iter().find_map(|&word| match word {
+                "A" | "B" => choice(word),
+                _ if word.eq_ignore_ascii_case("tie") => Some(AbVerdict::Tie),
+                _ => None,
+            });
+            if let Some(verdict) = stated {
+                return verdict;
+            }
+        }
+        AbVerdict::Tie
+    }
+}
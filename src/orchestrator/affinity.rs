@@ -0,0 +1,120 @@
+// CPU Affinity - Pinning agent processes to logical CPUs
+use anyhow::Result;
+use std::io;
+
+use super::error::AgentError;
+
+/// Logical CPUs this process may run on, which agents are pinned within:
+/// its own affinity mask, which may leave gaps, on Linux.
+#[cfg(target_os = "linux")]
+pub(crate) fn available_cpus() -> Vec<usize> {
+    use nix::sched::{sched_getaffinity, CpuSet};
+    use nix::unistd::Pid;
+
+    match sched_getaffinity(Pid::from_raw(0)) {
+        Ok(set) => (0..CpuSet::count()).filter(|&cpu| set.is_set(cpu).unwrap_or(false)).collect(),
+        Err(_) => (0..fallback_cpu_count()).collect(),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn available_cpus() -> Vec<usize> {
+    (0..fallback_cpu_count()).collect()
+}
+
+fn fallback_cpu_count() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Checks `cpus` is a non-empty list of `available_cpus`.
+pub(crate) fn validate(cpus: &[usize]) -> Result<(), AgentError> {
+    let available = available_cpus();
+    if cpus.is_empty() || cpus.iter().any(|cpu| !available.contains(cpu)) {
+        return Err(AgentError::InvalidAffinityMask { cpus: cpus.to_vec(), available });
+    }
+    Ok(())
+}
+
+/// Pins an agent's process to `cpus`, which `validate` has accepted.
+/// Platforms without process affinity, and agents without a known pid,
+/// report `AgentError::NotSupported`.
+pub(crate) fn apply(agent_id: &str, pid: Option<u32>, cpus: &[usize]) -> Result<()> {
+    let not_supported = || AgentError::NotSupported {
+        agent_id: agent_id.to_string(),
+        operation: "CPU affinity".to_string(),
+    };
+    let pid = pid.ok_or_else(not_supported)?;
+    match set_process_affinity(pid, cpus) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => Err(not_supported().into()),
+        Err(e) => Err(anyhow::anyhow!("Failed to set CPU affinity of agent {}: {}", agent_id, e)),
+    }
+}
+
+/// Restricts every thread of process `pid` to `cpus`; the mask is per
+/// thread on Linux. Threads and children it starts afterwards inherit it,
+/// but children already running keep theirs.
+#[cfg(target_os = "linux")]
+fn set_process_affinity(pid: u32, cpus: &[usize]) -> io::Result<()> {
+    use nix::errno::Errno;
+    use nix::sched::{sched_setaffinity, CpuSet};
+    use nix::unistd::Pid;
+
+    let mut set = CpuSet::new();
+    for &cpu in cpus {
+        set.set(cpu).map_err(io::Error::from)?;
+    }
+    sched_setaffinity(Pid::from_raw(pid as i32), &set).map_err(io::Error::from)?;
+    for entry in std::fs::read_dir(format!("/proc/{}/task", pid))? {
+        let Some(tid) = entry?.file_name().to_str().and_then(|name| name.parse::<i32>().ok()) else {
+            continue;
+        };
+        match sched_setaffinity(Pid::from_raw(tid), &set) {
+            // The thread ended in the meantime
+            Ok(()) | Err(Errno::ESRCH) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// macOS has no process-wide affinity; its thread affinity tags are only
+/// hints a thread sets for itself, so another process can't be pinned.
+#[cfg(target_os = "macos")]
+fn set_process_affinity(_pid: u32, _cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "macOS cannot pin another process to CPUs"))
+}
+
+#[cfg(windows)]
+fn set_process_affinity(pid: u32, cpus: &[usize]) -> io::Result<()> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, SetProcessAffinityMask, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_INFORMATION,
+    };
+
+    // The mask covers the process's processor group only
+    if cpus.iter().any(|&cpu| cpu >= usize::BITS as usize) {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "CPUs beyond the first processor group"));
+    }
+    let mask = cpus.iter().fold(0usize, |mask, &cpu| mask | 1 << cpu);
+
+    // SAFETY: the handle is checked before use and closed once
+    unsafe {
+        let handle = OpenProcess(PROCESS_SET_INFORMATION | PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let ok = SetProcessAffinityMask(handle, mask) != 0;
+        let error = io::Error::last_os_error();
+        CloseHandle(handle);
+        if !ok {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn set_process_affinity(_pid: u32, _cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "CPU affinity is not available on this platform"))
+}
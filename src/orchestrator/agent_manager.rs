@@ -1,21 +1,58 @@
 // Agent Manager - Real PTY terminal spawning and management
-use anyhow::Result;
-use portable_pty::{CommandBuilder, PtySize, native_pty_system, PtyPair};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::stream::{self, Stream};
+use portable_pty::{Child, CommandBuilder, PtySize, native_pty_system, PtyPair};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::ffi::OsStr;
 use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::io::{Read, Write};
-use tokio::sync::{Mutex, RwLock, mpsc};
+use std::pin::Pin;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, RwLock, broadcast, mpsc, watch};
 use tokio::task;
 use tracing::{info, error, debug};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::affinity;
+use super::binary_output::{BinaryOutput, BinaryOutputs, BinarySplitter};
+use super::checks;
+use super::colorize::{plain_text, ColorScheme, ColorizedChunk, SgrParser};
+use super::error::AgentError;
+use super::events::AgentStatusDto;
+use super::framing::{JsonlFramer, OutputFraming};
+use super::interrupt;
+use super::output_buffer::{
+    OutputBuffer, OutputRange, OutputStats, TerminalSnapshot, DEFAULT_SCROLLBACK_LINES,
+};
+use super::output_filter::{FilterStage, LinePredicate};
+use super::output_meter::OutputMeter;
+use super::observer::OutputObservers;
+use super::patterns::{AgentPatterns, CompiledPatterns, ErrorMatcher, LineSplitter, PendingEchoes};
+use super::resources::{ProcessResources, ResourceSampler};
+use super::sandbox::{self, SandboxConfig};
+use super::shutdown::ShutdownStep;
+use super::watermark::{self, WatermarkHandle, Watermarks};
+use super::workspace_path;
+use crate::plugin::AgentProcessTrait;
+
+/// Serialized as its display string (`claude`, `plugin:<name>`, ...), the
+/// same form the frontend passes to `spawn_agent`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub enum AgentType {
     Claude,
     Gemini,
     Bash,
+    /// An agent provided by a loaded plugin, identified by plugin name.
+    Plugin(String),
 }
 
 impl fmt::Display for AgentType {
@@ -24,25 +61,334 @@ impl fmt::Display for AgentType {
             AgentType::Claude => write!(f, "claude"),
             AgentType::Gemini => write!(f, "gemini"),
             AgentType::Bash => write!(f, "bash"),
+            AgentType::Plugin(name) => write!(f, "plugin:{}", name),
+        }
+    }
+}
+
+impl FromStr for AgentType {
+    type Err = String;
+    
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "claude" => Ok(AgentType::Claude),
+            "gemini" => Ok(AgentType::Gemini),
+            "bash" => Ok(AgentType::Bash),
+            other => match other.strip_prefix("plugin:") {
+                Some(name) if !name.is_empty() => Ok(AgentType::Plugin(name.to_string())),
+                _ => Err(format!("Unknown agent type: {}", other)),
+            },
+        }
+    }
+}
+
+impl From<AgentType> for String {
+    fn from(agent_type: AgentType) -> Self {
+        agent_type.to_string()
+    }
+}
+
+impl TryFrom<String> for AgentType {
+    type Error = String;
+    
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// How an agent's stdio is attached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtyType {
+    /// An interactive pseudo-terminal, for full CLIs and TUIs.
+    #[default]
+    Pty,
+    /// Plain stdin/stdout/stderr pipes, for batch-mode tools: no ANSI
+    /// negotiation and no terminal size. Cannot be resized.
+    Pipe,
+    /// No stdio at all; input is discarded and no output is produced.
+    Null,
+}
+
+impl fmt::Display for TtyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TtyType::Pty => write!(f, "pty"),
+            TtyType::Pipe => write!(f, "pipe"),
+            TtyType::Null => write!(f, "null"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AgentConfig {
     pub agent_type: AgentType,
+    #[serde(default, skip_serializing)]
     pub api_key: String, // Not used - relies on existing CLI auth
+    #[serde(default)]
     pub agent_id: Option<String>,
+    /// Working directory, as a template expanded at spawn: a leading `~`,
+    /// `$VAR`/`${VAR}`, and the placeholders `{agent_id}`, `{agent_type}`
+    /// and `{date}` (`YYYY-MM-DD`). The expansion must be an existing
+    /// directory; the agent's config holds the canonical result.
+    #[serde(default)]
     pub workspace_path: Option<String>,
+    /// Prepended verbatim to every `send_command` (not `send_raw`).
+    #[serde(default)]
+    pub command_prefix: Option<String>,
+    /// Appended verbatim to every `send_command` (not `send_raw`).
+    #[serde(default)]
+    pub command_suffix: Option<String>,
+    /// Store `\r`-driven progress updates as one scrollback line.
+    #[serde(default)]
+    pub collapse_cr_updates: bool,
+    /// Sent in order right after the agent is spawned.
+    #[serde(default)]
+    pub startup_commands: Vec<String>,
+    /// Scrollback lines to retain; the orchestrator fills in its default.
+    #[serde(default)]
+    pub scrollback_lines: Option<usize>,
+    /// Window for merging output chunks in `get_output`; the orchestrator
+    /// fills in its default.
+    #[serde(default)]
+    pub output_coalesce_ms: Option<u64>,
+    /// Largest file `send_file_contents` accepts; the orchestrator fills in
+    /// its default.
+    #[serde(default)]
+    pub max_context_bytes: Option<usize>,
+    /// Emit `SystemEvent` messages when files in the workspace change.
+    #[serde(default)]
+    pub watch_workspace: bool,
+    /// Session history records kept for this agent, which may grow to
+    /// twice as many between trims; 0 is unlimited.
+    #[serde(default)]
+    pub max_session_history_per_agent: usize,
+    /// Hint runtimes that buffer stdout when it isn't a terminal (currently
+    /// via `PYTHONUNBUFFERED`) to flush line by line.
+    #[serde(default)]
+    pub line_buffered: bool,
+    /// Send a newline right after spawn, for CLIs that only draw a prompt
+    /// once they receive input.
+    #[serde(default)]
+    pub nudge_prompt: bool,
+    /// Emit a `queue_deep` event when more commands than this are queued.
+    #[serde(default)]
+    pub queue_depth_warning_threshold: Option<usize>,
+    /// How the agent's stdio is attached; see `TtyType`.
+    #[serde(default)]
+    pub tty_type: TtyType,
+    /// Overrides for the agent type's `default_patterns`, per pattern.
+    #[serde(default)]
+    pub patterns: AgentPatterns,
+    /// Namespace isolation on Linux; ignored elsewhere.
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfig>,
+    /// A background agent that never gets a window of its own. Its output
+    /// is buffered and its events emitted as for any other agent, so it can
+    /// still be monitored through the orchestrator.
+    #[serde(default)]
+    pub headless: bool,
+    /// Run by `kill`; empty uses the agent type's default sequence.
+    #[serde(default)]
+    pub shutdown_sequence: Vec<ShutdownStep>,
+    /// Sniff each output chunk and keep binary ones (images, PDFs, ...) in
+    /// `binary_outputs` instead of the text scrollback.
+    #[serde(default)]
+    pub output_mime_type_detection: bool,
+    /// How output is divided into messages; see `OutputFraming`.
+    #[serde(default)]
+    pub output_framing: OutputFraming,
+    /// Emit a `response_timeout` event when a command gets no output
+    /// within this long. Given in milliseconds.
+    #[serde(default, with = "option_duration_millis")]
+    pub response_timeout: Option<Duration>,
+    /// Logical CPUs the agent's process is pinned to; `None` leaves it to
+    /// the scheduler. Not supported on macOS.
+    #[serde(default)]
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// Start the agent's `PATH` from the orchestrator's, with the usual
+    /// per-user tool directories (`~/.cargo/bin`, `~/.local/bin`, ...) in
+    /// front. When off, `path_prepend` is the whole `PATH`, so it must
+    /// include the directory of the agent's shell.
+    #[serde(default = "inherit_path_default")]
+    pub inherit_path: bool,
+    /// Directories put first on the agent's `PATH`, in order, ahead of
+    /// anything inherited.
+    #[serde(default)]
+    pub path_prepend: Vec<PathBuf>,
+}
+
+fn inherit_path_default() -> bool {
+    true
+}
+
+impl AgentConfig {
+    pub fn new(agent_type: AgentType) -> Self {
+        Self {
+            agent_type,
+            api_key: String::new(),
+            agent_id: None,
+            workspace_path: None,
+            command_prefix: None,
+            command_suffix: None,
+            collapse_cr_updates: false,
+            startup_commands: Vec::new(),
+            scrollback_lines: None,
+            output_coalesce_ms: None,
+            max_context_bytes: None,
+            watch_workspace: false,
+            max_session_history_per_agent: 0,
+            line_buffered: false,
+            nudge_prompt: false,
+            queue_depth_warning_threshold: None,
+            tty_type: TtyType::Pty,
+            patterns: AgentPatterns::default(),
+            sandbox: None,
+            headless: false,
+            shutdown_sequence: Vec::new(),
+            output_mime_type_detection: false,
+            output_framing: OutputFraming::Raw,
+            response_timeout: None,
+            cpu_affinity: None,
+            inherit_path: true,
+            path_prepend: Vec::new(),
+        }
+    }
+    
+    /// A copy safe to persist: no `api_key`, and sensitive flag and
+    /// variable values in the commands, shutdown commands included,
+    /// replaced with `<redacted>`.
+    pub fn redacted(&self) -> AgentConfig {
+        let redact = |command: &Option<String>| command.as_deref().map(AgentManager::redact_command);
+        AgentConfig {
+            api_key: String::new(),
+            command_prefix: redact(&self.command_prefix),
+            command_suffix: redact(&self.command_suffix),
+            startup_commands: self.startup_commands.iter()
+                .map(|command| AgentManager::redact_command(command))
+                .collect(),
+            shutdown_sequence: self.shutdown_sequence.iter()
+                .map(|step| match step {
+                    ShutdownStep::Command(command) => ShutdownStep::Command(AgentManager::redact_command(command)),
+                    step => step.clone(),
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+    
+    /// A copy without the commands `redacted` blanked values out of, which
+    /// would only replay the placeholder: their startup commands, shutdown
+    /// steps and prefix or suffix are dropped.
+    pub fn without_redacted_commands(&self) -> AgentConfig {
+        let keep = |command: &Option<String>| command.clone().filter(|command| !AgentManager::is_redacted(command));
+        AgentConfig {
+            command_prefix: keep(&self.command_prefix),
+            command_suffix: keep(&self.command_suffix),
+            startup_commands: self.startup_commands.iter()
+                .filter(|command| !AgentManager::is_redacted(command))
+                .cloned()
+                .collect(),
+            shutdown_sequence: self.shutdown_sequence.iter()
+                .filter(|step| !matches!(step, ShutdownStep::Command(command) if AgentManager::is_redacted(command)))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+    
+    /// The configured patterns, falling back to the agent type's defaults.
+    pub fn effective_patterns(&self) -> AgentPatterns {
+        self.patterns.clone().or(self.agent_type.default_patterns())
+    }
+    
+    /// The configured shutdown sequence, or the agent type's default.
+    pub fn effective_shutdown_sequence(&self) -> Vec<ShutdownStep> {
+        if self.shutdown_sequence.is_empty() {
+            self.agent_type.default_shutdown_sequence()
+        } else {
+            self.shutdown_sequence.clone()
+        }
+    }
+    
+    /// Applies the configured prefix/suffix to a command.
+    pub fn wrap_command(&self, command: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.command_prefix.as_deref().unwrap_or_default(),
+            command,
+            self.command_suffix.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Syntax family of the shell running inside an agent's PTY.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShellKind {
+    Posix,
+    Cmd,
+}
+
+impl ShellKind {
+    fn from_program(program: &str) -> Self {
+        let name = Path::new(program)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match name.as_str() {
+            "cmd" | "cmd.exe" => ShellKind::Cmd,
+            _ => ShellKind::Posix,
+        }
+    }
 }
 
 pub struct AgentProcess {
     pub id: String,
     pub agent_type: AgentType,
-    pty_pair: Arc<Mutex<PtyPair>>,
+    shell: ShellKind,
+    /// OS process id, if the platform reported one at spawn.
+    pid: Option<u32>,
+    /// CPU time at the previous `resource_usage` call.
+    resource_sampler: ResourceSampler,
+    /// The prompt may be replaced by `detect_prompt`.
+    patterns: std::sync::RwLock<CompiledPatterns>,
+    /// `None` unless the agent runs with `TtyType::Pty`.
+    pty_pair: Option<Arc<Mutex<PtyPair>>>,
+    child: Arc<Mutex<AgentChild>>,
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
     output_sender: mpsc::Sender<Vec<u8>>,
     output_receiver: Arc<Mutex<mpsc::Receiver<Vec<u8>>>>,
+    /// Objects parsed from output under `OutputFraming::Jsonl`.
+    json_output_receiver: Arc<Mutex<mpsc::Receiver<Value>>>,
+    /// Copy of every output chunk for listeners that must not consume the
+    /// `get_output` stream.
+    output_tap: broadcast::Sender<Vec<u8>>,
+    /// Output lines matching an error pattern, as the readers find them.
+    error_tap: broadcast::Sender<String>,
+    output_meter: Arc<OutputMeter>,
+    /// Applied by the reader tasks to each line before it is stored or sent.
+    output_filter: Arc<std::sync::RwLock<Option<LinePredicate>>>,
+    /// Pending `output_watermark` sentinels, removed by the readers.
+    watermarks: Watermarks,
+    /// Commands a terminal has yet to echo, so error patterns skip the
+    /// echo. `None` without a PTY.
+    pending_echoes: Option<PendingEchoes>,
+    coalesce_window: Duration,
+    /// Set while `get_output` is merging chunks; `flush_output` ends the
+    /// merge through `flush_requested` and waits for this to clear.
+    coalescing: watch::Sender<bool>,
+    flush_requested: Notify,
+    scrollback: Arc<Mutex<OutputBuffer>>,
+    /// Chunks diverted from the scrollback by MIME detection.
+    binary_outputs: Arc<Mutex<BinaryOutputs>>,
+    /// Scrollback line after the last prompt `get_last_response` detected;
+    /// the next response starts here.
+    last_prompt_pos: Mutex<usize>,
+    /// Active theme for `get_colorized_output`, with the parser state it
+    /// carries between reads.
+    color_scheme: Arc<RwLock<Option<ColorScheme>>>,
+    sgr_parser: Arc<Mutex<SgrParser>>,
+    config: Arc<RwLock<AgentConfig>>,
     status: Arc<RwLock<AgentStatus>>,
 }
 
@@ -55,91 +401,348 @@ struct AgentStatus {
     last_activity: chrono::DateTime<chrono::Utc>,
     commands_sent: usize,
     workspace: Option<String>,
+    /// The PTY and child process are owned by this orchestrator.
+    managed: bool,
+    /// The process outlived a previous orchestrator and was attached to
+    /// again rather than spawned (reserved for daemon mode).
+    reattached: bool,
+    /// Current PTY size, updated by `resize`.
+    rows: u16,
+    cols: u16,
+    headless: bool,
+    /// Commands that saw no output within `response_timeout`.
+    response_timeout_count: u64,
+    /// Latest output line matching an error pattern, until `clear_error`.
+    last_error: Option<String>,
+    error_count: u64,
+    /// Interrupts delivered by `send_interrupt`.
+    signals_sent: u64,
 }
 
+/// How an agent's process ended, as reported by `wait_for_all`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStatus {
+    Exited { code: u32 },
+    /// Still running when the wait gave up.
+    Timeout,
+    /// A plugin agent, whose process can't be waited for; left running.
+    Unsupported,
+}
+
+/// The agent's process, spawned on a PTY or with plain stdio.
+enum AgentChild {
+    Pty(Box<dyn Child + Send + Sync>),
+    Process(std::process::Child),
+}
+
+impl AgentChild {
+    /// The exit code if the process has exited. Processes killed by a
+    /// signal report 1, as portable-pty does.
+    fn try_wait(&mut self) -> Result<Option<u32>> {
+        Ok(match self {
+            AgentChild::Pty(child) => child.try_wait()?.map(|status| status.exit_code()),
+            AgentChild::Process(child) => child.try_wait()?
+                .map(|status| status.code().map_or(1, |code| code as u32)),
+        })
+    }
+
+    fn process_id(&self) -> Option<u32> {
+        match self {
+            AgentChild::Pty(child) => child.process_id(),
+            AgentChild::Process(child) => Some(child.id()),
+        }
+    }
+
+    /// Kills the process unless it has already exited.
+    fn kill(&mut self) -> Result<()> {
+        if self.try_wait()?.is_none() {
+            match self {
+                AgentChild::Pty(child) => child.kill()?,
+                AgentChild::Process(child) => child.kill()?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A spawned process with the handles `AgentProcess` is built from.
+struct SpawnedIo {
+    child: AgentChild,
+    writer: Box<dyn Write + Send>,
+    readers: Vec<Box<dyn Read + Send>>,
+    pty_pair: Option<PtyPair>,
+}
+
+/// Where an `AgentProcess::tail` stream is in the scrollback.
+struct TailState {
+    agent: Arc<AgentProcess>,
+    /// Signals new output while following.
+    output: Option<broadcast::Receiver<Vec<u8>>>,
+    /// Scrollback line number to read from next; `None` before the first
+    /// `n` lines are read.
+    next_line: Option<usize>,
+    pending: VecDeque<String>,
+    /// The agent has exited; its last output is being drained.
+    exited: bool,
+    done: bool,
+}
+
+/// Clears `AgentProcess::coalescing` when a merge ends, including when the
+/// `get_output` future is dropped mid-merge.
+struct CoalescingGuard<'a>(&'a watch::Sender<bool>);
+
+impl Drop for CoalescingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.send_replace(false);
+    }
+}
+
+/// Where an agent's output readers deliver each chunk.
+#[derive(Clone)]
+struct OutputSinks {
+    sender: mpsc::Sender<Vec<u8>>,
+    tap: broadcast::Sender<Vec<u8>>,
+    meter: Arc<OutputMeter>,
+    scrollback: Arc<Mutex<OutputBuffer>>,
+    filter: Arc<std::sync::RwLock<Option<LinePredicate>>>,
+    watermarks: Watermarks,
+    /// Set when `output_mime_type_detection` is on.
+    binary: Option<Arc<Mutex<BinaryOutputs>>>,
+    /// Set under `OutputFraming::Jsonl`.
+    json: Option<mpsc::Sender<Value>>,
+    error_patterns: Vec<Regex>,
+    echoes: Option<PendingEchoes>,
+    errors: broadcast::Sender<String>,
+    status: Arc<RwLock<AgentStatus>>,
+    agent_id: String,
+    observers: OutputObservers,
+}
+
+/// Chunks buffered per `subscribe_output` listener before it lags.
+const OUTPUT_TAP_CAPACITY: usize = 256;
+
+/// Error lines buffered per `subscribe_errors` listener before it lags.
+const ERROR_TAP_CAPACITY: usize = 64;
+
+/// JSON objects buffered for `json_stream`; further ones are dropped until
+/// it catches up.
+const JSON_OUTPUT_CAPACITY: usize = 1024;
+
+/// Newlines `AgentProcess::detect_prompt` sends at most.
+const MAX_PROMPT_PROBES: usize = 10;
+
+/// Quiet period after which `detect_prompt` takes the output as complete.
+const PROMPT_SETTLE: Duration = Duration::from_millis(300);
+
+/// Longest `detect_prompt` waits for output to go quiet, so a spinner
+/// can't hold it up.
+const PROMPT_SETTLE_LIMIT: Duration = Duration::from_secs(3);
+
+/// Lines `detect_prompt` samples unless told otherwise.
+pub const DEFAULT_PROMPT_SAMPLE_LINES: usize = 4;
+
+/// Poll interval for `AgentProcess::wait`.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub struct AgentManager;
 
+pub const DEFAULT_MAX_CONTEXT_BYTES: usize = 100 * 1024;
+
+pub const DEFAULT_PTY_ROWS: u16 = 24;
+pub const DEFAULT_PTY_COLS: u16 = 80;
+
+/// Flag fragments whose values must never reach the logs.
+const SENSITIVE_ARG_MARKERS: &[&str] = &["key", "token", "secret", "password"];
+
 impl AgentManager {
     pub async fn spawn(config: AgentConfig) -> Result<AgentProcess> {
+        Self::spawn_observed(config, OutputObservers::default()).await
+    }
+    
+    /// Like `spawn`, passing each line of output to `observers`.
+    pub async fn spawn_observed(config: AgentConfig, observers: OutputObservers) -> Result<AgentProcess> {
+        if let AgentType::Plugin(name) = &config.agent_type {
+            bail!("Plugin agent {} must be spawned through the PluginManager", name);
+        }
+        let patterns = config.effective_patterns().compile()?;
+        if let Some(cpus) = &config.cpu_affinity {
+            affinity::validate(cpus)?;
+        }
+        
         let agent_id = config.agent_id.clone()
             .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let config = AgentConfig {
+            workspace_path: config.workspace_path.as_deref()
+                .map(|template| workspace_path::expand(template, &agent_id, &config.agent_type))
+                .transpose()?,
+            ..config
+        };
+        
+        info!("Spawning {} agent (ID: {}) on {}", config.agent_type, agent_id, config.tty_type);
         
-        info!("Spawning {} agent (ID: {}) with real PTY", config.agent_type, agent_id);
+        let cmd = Self::build_command(&config);
+        let command_line = Self::describe_command(&cmd);
+        let shell = cmd.get_argv().first()
+            .map(|p| ShellKind::from_program(&p.to_string_lossy()))
+            .unwrap_or(ShellKind::Posix);
+        let pty_size = PtySize {
+            rows: DEFAULT_PTY_ROWS,
+            cols: DEFAULT_PTY_COLS,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        debug!(
+            "Spawn plan for agent {}: command=`{}` workspace={:?} pty={}x{}",
+            agent_id, command_line, config.workspace_path, pty_size.cols, pty_size.rows
+        );
+        debug!("PATH of agent {}: {:?}", agent_id, cmd.get_env("PATH").unwrap_or_default());
+        
+        let mut io = match config.tty_type {
+            TtyType::Pty => Self::spawn_pty(cmd, pty_size, &command_line)?,
+            TtyType::Pipe | TtyType::Null => Self::spawn_piped(&cmd, config.tty_type, &command_line)?,
+        };
+        info!("Successfully spawned {} process ({})", config.agent_type, config.tty_type);
+        let pid = match io.child.process_id() {
+            Some(launcher) => Some(sandbox::agent_pid(config.sandbox.as_ref(), launcher).await),
+            None => None,
+        };
+        if let Some(cpus) = &config.cpu_affinity {
+            if let Err(e) = affinity::apply(&agent_id, pid, cpus) {
+                if let Err(kill_error) = io.child.kill() {
+                    error!("Failed to kill unpinned agent {}: {}", agent_id, kill_error);
+                }
+                return Err(e);
+            }
+            info!("Pinned agent {} to CPUs {:?}", agent_id, cpus);
+        }
+        
+        // Create channel for output streaming
+        let (output_sender, output_receiver) = mpsc::channel::<Vec<u8>>(100);
+        let (output_tap, _) = broadcast::channel::<Vec<u8>>(OUTPUT_TAP_CAPACITY);
+        let (error_tap, _) = broadcast::channel::<String>(ERROR_TAP_CAPACITY);
+        let (json_output_channel, json_output_receiver) = mpsc::channel::<Value>(JSON_OUTPUT_CAPACITY);
+        info!("Created output channel");
         
+        let output_meter = Arc::new(OutputMeter::new());
+        let scrollback = Arc::new(Mutex::new(OutputBuffer::new(
+            config.scrollback_lines.unwrap_or(DEFAULT_SCROLLBACK_LINES),
+            config.collapse_cr_updates,
+        )));
+        let output_filter = Arc::new(std::sync::RwLock::new(None));
+        let watermarks = Watermarks::default();
+        let pending_echoes = (config.tty_type == TtyType::Pty).then(PendingEchoes::default);
+        let binary_outputs = Arc::new(Mutex::new(BinaryOutputs::default()));
+        let status = Arc::new(RwLock::new(AgentStatus {
+            id: agent_id.clone(),
+            agent_type: config.agent_type.to_string(),
+            running: true,
+            start_time: chrono::Utc::now(),
+            last_activity: chrono::Utc::now(),
+            commands_sent: 0,
+            workspace: config.workspace_path.clone(),
+            managed: true,
+            reattached: false,
+            rows: pty_size.rows,
+            cols: pty_size.cols,
+            headless: config.headless,
+            response_timeout_count: 0,
+            last_error: None,
+            error_count: 0,
+            signals_sent: 0,
+        }));
+        
+        let sinks = OutputSinks {
+            sender: output_sender.clone(),
+            tap: output_tap.clone(),
+            meter: output_meter.clone(),
+            scrollback: scrollback.clone(),
+            filter: output_filter.clone(),
+            watermarks: watermarks.clone(),
+            binary: config.output_mime_type_detection.then(|| binary_outputs.clone()),
+            json: (config.output_framing == OutputFraming::Jsonl).then_some(json_output_channel),
+            error_patterns: patterns.errors.clone(),
+            echoes: pending_echoes.clone(),
+            errors: error_tap.clone(),
+            status: status.clone(),
+            agent_id: agent_id.clone(),
+            observers,
+        };
+        for reader in io.readers {
+            Self::spawn_output_reader(
+                reader,
+                format!("{} agent {}", config.agent_type, agent_id),
+                sinks.clone(),
+            );
+        }
+        
+        let config = AgentConfig {
+            agent_id: Some(agent_id.clone()),
+            ..config
+        };
+        
+        Ok(AgentProcess {
+            id: agent_id,
+            agent_type: config.agent_type.clone(),
+            shell,
+            pid,
+            resource_sampler: ResourceSampler::default(),
+            patterns: std::sync::RwLock::new(patterns),
+            pty_pair: io.pty_pair.map(|pair| Arc::new(Mutex::new(pair))),
+            child: Arc::new(Mutex::new(io.child)),
+            writer: Arc::new(Mutex::new(io.writer)),
+            output_sender,
+            output_receiver: Arc::new(Mutex::new(output_receiver)),
+            json_output_receiver: Arc::new(Mutex::new(json_output_receiver)),
+            output_tap,
+            error_tap,
+            output_meter,
+            output_filter,
+            watermarks,
+            pending_echoes,
+            coalesce_window: Duration::from_millis(config.output_coalesce_ms.unwrap_or(0)),
+            coalescing: watch::Sender::new(false),
+            flush_requested: Notify::new(),
+            scrollback,
+            binary_outputs,
+            last_prompt_pos: Mutex::new(0),
+            color_scheme: Arc::new(RwLock::new(None)),
+            sgr_parser: Arc::new(Mutex::new(SgrParser::new())),
+            config: Arc::new(RwLock::new(config)),
+            status,
+        })
+    }
+
+    fn spawn_pty(cmd: CommandBuilder, pty_size: PtySize, command_line: &str) -> Result<SpawnedIo> {
         // Create PTY system
         let pty_system = native_pty_system();
         
         // Create PTY pair with size
-        let pty_pair = match pty_system.openpty(PtySize {
-            rows: 24,
-            cols: 80,
-            pixel_width: 0,
-            pixel_height: 0,
-        }) {
+        let pty_pair = match pty_system.openpty(pty_size) {
             Ok(pair) => {
                 info!("PTY pair created successfully");
                 pair
             }
             Err(e) => {
                 error!("Failed to create PTY pair: {}", e);
-                return Err(e.into());
+                return Err(AgentError::PtyAllocationFailed {
+                    reason: e.to_string(),
+                    open_agents: None,
+                }.into());
             }
         };
         
-        // Build command - start with basic shell for now
-        let mut cmd = match config.agent_type {
-            AgentType::Claude => CommandBuilder::new("bash"),
-            AgentType::Gemini => CommandBuilder::new("bash"),
-            AgentType::Bash => CommandBuilder::new("bash"),
-        };
-        
-        // Add workspace path if specified
-        if let Some(workspace) = &config.workspace_path {
-            cmd.cwd(workspace);
-        }
-        
-        // Set environment for full terminal compatibility
-        cmd.env("TERM", "xterm-256color");
-        cmd.env("COLORTERM", "truecolor");
-        cmd.env("LANG", std::env::var("LANG").unwrap_or_else(|_| "en_US.UTF-8".to_string()));
-        cmd.env("LC_ALL", std::env::var("LC_ALL").unwrap_or_else(|_| "en_US.UTF-8".to_string()));
-        
-        // Pass through important environment variables for full functionality
-        if let Ok(shell) = std::env::var("SHELL") {
-            cmd.env("SHELL", shell);
-        }
-        if let Ok(user) = std::env::var("USER") {
-            cmd.env("USER", user);
-        }
-        if let Ok(home) = std::env::var("HOME") {
-            cmd.env("HOME", home.clone());
-            
-            // Add common binary paths
-            let node_bin_path = format!("{}/.nvm/versions/node/v22.15.0/bin", home);
-            let cargo_bin = format!("{}/.cargo/bin", home);
-            let local_bin = format!("{}/.local/bin", home);
-            
-            let current_path = std::env::var("PATH").unwrap_or_default();
-            let enhanced_path = format!("{}:{}:{}:{}", node_bin_path, cargo_bin, local_bin, current_path);
-            cmd.env("PATH", enhanced_path);
-        } else {
-            // Just use existing PATH if HOME not available
-            if let Ok(path) = std::env::var("PATH") {
-                cmd.env("PATH", path);
-            }
-        }
-        
         // The CLIs handle their own auth - no API keys needed
         
         // Spawn the child process
-        let _child = match pty_pair.slave.spawn_command(cmd) {
-            Ok(child) => {
-                info!("Successfully spawned {} process", config.agent_type);
-                child
-            }
+        let child = match pty_pair.slave.spawn_command(cmd) {
+            Ok(child) => child,
             Err(e) => {
-                error!("Failed to spawn {} process: {}", config.agent_type, e);
-                return Err(e.into());
+                error!("Failed to spawn process `{}`: {}", command_line, e);
+                return Err(AgentError::SpawnFailed {
+                    command: command_line.to_string(),
+                    reason: e.to_string(),
+                }.into());
             }
         };
         
@@ -155,12 +758,7 @@ impl AgentManager {
             }
         };
         
-        // Create channel for output streaming
-        let (output_sender, output_receiver) = mpsc::channel::<Vec<u8>>(100);
-        info!("Created output channel");
-        
-        // Start reader task for PTY output
-        let mut reader = match pty_pair.master.try_clone_reader() {
+        let reader = match pty_pair.master.try_clone_reader() {
             Ok(r) => {
                 info!("PTY reader cloned");
                 r
@@ -170,65 +768,374 @@ impl AgentManager {
                 return Err(e.into());
             }
         };
-        let sender_clone = output_sender.clone();
-        let agent_type_str = config.agent_type.to_string();
-        let agent_id_clone = agent_id.clone();
         
+        Ok(SpawnedIo {
+            child: AgentChild::Pty(child),
+            writer,
+            readers: vec![reader],
+            pty_pair: Some(pty_pair),
+        })
+    }
+    
+    /// Runs the command without a terminal: piped stdio for `Pipe`, none
+    /// for `Null`. The environment and working directory are taken from the
+    /// same `CommandBuilder` a PTY spawn would use.
+    fn spawn_piped(cmd: &CommandBuilder, tty_type: TtyType, command_line: &str) -> Result<SpawnedIo> {
+        let argv = cmd.get_argv();
+        let mut command = std::process::Command::new(&argv[0]);
+        command.args(&argv[1..]);
+        command.envs(cmd.iter_extra_env_as_str());
+        if let Some(cwd) = cmd.get_cwd() {
+            command.current_dir(cwd);
+        }
+        let stdio = || match tty_type {
+            TtyType::Pipe => Stdio::piped(),
+            _ => Stdio::null(),
+        };
+        command.stdin(stdio()).stdout(stdio()).stderr(stdio());
+        // Its own process group, so `send_interrupt` reaches the agent's
+        // children without reaching us
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            use windows_sys::Win32::System::Threading::CREATE_NEW_PROCESS_GROUP;
+            command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+        }
+        
+        let mut child = command.spawn().map_err(|e| {
+            error!("Failed to spawn process `{}`: {}", command_line, e);
+            AgentError::SpawnFailed {
+                command: command_line.to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+        
+        let writer: Box<dyn Write + Send> = match child.stdin.take() {
+            Some(stdin) => Box::new(stdin),
+            None => Box::new(std::io::sink()),
+        };
+        let mut readers: Vec<Box<dyn Read + Send>> = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(Box::new(stdout));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(Box::new(stderr));
+        }
+        
+        Ok(SpawnedIo {
+            child: AgentChild::Process(child),
+            writer,
+            readers,
+            pty_pair: None,
+        })
+    }
+    
+    /// Forwards one output source into the agent's meter, scrollback, tap
+    /// and output channel until it closes.
+    fn spawn_output_reader(mut reader: Box<dyn Read + Send>, label: String, sinks: OutputSinks) {
         // Spawn blocking reader in separate task
         task::spawn_blocking(move || {
             let mut buffer = [0u8; 4096];
+            let mut stage = FilterStage::default();
+            let mut framer = JsonlFramer::default();
+            let mut errors = ErrorMatcher::new(sinks.error_patterns.clone(), sinks.echoes.clone());
+            let mut observed = LineSplitter::default();
+            let mut binary_splitter = BinarySplitter::default();
             loop {
                 match reader.read(&mut buffer) {
                     Ok(0) => {
-                        info!("PTY for {} agent {} closed", agent_type_str, agent_id_clone);
+                        info!("Output of {} closed", label);
                         break;
                     }
                     Ok(n) => {
-                        let data = buffer[..n].to_vec();
-                        if let Err(e) = sender_clone.blocking_send(data) {
-                            error!("Failed to send PTY output: {}", e);
+                        sinks.meter.record(n);
+                        let chunk = &buffer[..n];
+                        let text_len = sinks.binary.as_ref()
+                            .map_or(n, |binary| binary_splitter.split_off_binary(&mut binary.blocking_lock(), chunk).len());
+                        let (text, binary) = chunk.split_at(text_len);
+                        
+                        // JSON objects bypass the filter, scrollback and
+                        // byte stream
+                        let framed = sinks.json.as_ref().map(|json| {
+                            let (rest, objects) = framer.split(text);
+                            for object in objects {
+                                if let Err(e) = json.try_send(object) {
+                                    error!("Dropped JSON output of {}: {}", label, e);
+                                }
+                            }
+                            rest
+                        });
+                        let text = framed.as_deref().unwrap_or(text);
+                        
+                        let predicate = sinks.filter.read().unwrap_or_else(|e| e.into_inner()).clone();
+                        let predicate = sinks.watermarks.filter(predicate);
+                        let mut data = stage.apply(text, predicate.as_ref());
+                        if !data.is_empty() {
+                            sinks.scrollback.blocking_lock().push(&data);
+                        }
+                        for line in errors.matches(&data) {
+                            let mut status = sinks.status.blocking_write();
+                            status.last_error = Some(line.clone());
+                            status.error_count += 1;
+                            drop(status);
+                            let _ = sinks.errors.send(line);
+                        }
+                        if !sinks.observers.is_empty() {
+                            for line in observed.lines(&data) {
+                                sinks.observers.line(&sinks.agent_id, &line);
+                            }
+                        }
+                        // Binary is still streamed, but line filters and the
+                        // scrollback only make sense for text
+                        data.extend_from_slice(binary);
+                        if data.is_empty() {
+                            continue;
+                        }
+                        // No subscribers is the common case, not an error
+                        let _ = sinks.tap.send(data.clone());
+                        if let Err(e) = sinks.sender.blocking_send(data) {
+                            error!("Failed to send output of {}: {}", label, e);
                             break;
                         }
                     }
                     Err(e) => {
-                        error!("Error reading PTY: {}", e);
+                        error!("Error reading output of {}: {}", label, e);
                         break;
                     }
                 }
             }
         });
+    }
+    
+    /// Checks that `config` could be spawned without actually starting a
+    /// process: its patterns must compile, its CPU affinity must name
+    /// available CPUs, its workspace template must expand and the program
+    /// must resolve on the command's `PATH`.
+    pub fn validate(config: &AgentConfig) -> Result<()> {
+        if let AgentType::Plugin(name) = &config.agent_type {
+            bail!("Plugin agent {} must be validated through the PluginManager", name);
+        }
+        config.effective_patterns().compile()?;
+        if let Some(cpus) = &config.cpu_affinity {
+            affinity::validate(cpus)?;
+        }
         
-        let status = Arc::new(RwLock::new(AgentStatus {
-            id: agent_id.clone(),
-            agent_type: config.agent_type.to_string(),
-            running: true,
-            start_time: chrono::Utc::now(),
-            last_activity: chrono::Utc::now(),
-            commands_sent: 0,
-            workspace: config.workspace_path.clone(),
-        }));
+        let cmd = Self::build_command(config);
+        let command_line = Self::describe_command(&cmd);
+        debug!(
+            "Validating {} agent: command=`{}` workspace={:?} pty={}x{}",
+            config.agent_type, command_line, config.workspace_path, DEFAULT_PTY_COLS, DEFAULT_PTY_ROWS
+        );
         
-        Ok(AgentProcess {
-            id: agent_id,
-            agent_type: config.agent_type,
-            pty_pair: Arc::new(Mutex::new(pty_pair)),
-            writer: Arc::new(Mutex::new(writer)),
-            output_sender,
-            output_receiver: Arc::new(Mutex::new(output_receiver)),
-            status,
+        let fail = |reason: String| -> anyhow::Error {
+            AgentError::SpawnFailed { command: command_line.clone(), reason }.into()
+        };
+        
+        if let Some(template) = &config.workspace_path {
+            let agent_id = config.agent_id.clone()
+                .unwrap_or_else(|| Uuid::new_v4().to_string());
+            workspace_path::expand(template, &agent_id, &config.agent_type)
+                .map_err(|e| fail(e.to_string()))?;
+        }
+        
+        let program = cmd.get_argv().first()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let search_path = cmd.get_env("PATH")
+            .map(|p| p.to_os_string())
+            .unwrap_or_default();
+        if Self::resolve_program(&program, &search_path).is_none() {
+            return Err(fail(format!("{} not found on PATH", program)));
+        }
+        
+        Ok(())
+    }
+    
+    /// Builds the PTY command for `config`, including the terminal
+    /// environment every agent shell expects.
+    fn build_command(config: &AgentConfig) -> CommandBuilder {
+        // Start with basic shell for now
+        let program = match config.agent_type {
+            AgentType::Claude => "bash",
+            AgentType::Gemini => "bash",
+            AgentType::Bash => "bash",
+            // Rejected by `spawn`/`validate` before a command is built
+            AgentType::Plugin(_) => "bash",
+        };
+        let mut cmd = match &config.sandbox {
+            Some(sandbox) => sandbox::command(sandbox, program),
+            None => CommandBuilder::new(program),
+        };
+        
+        // Add workspace path if specified
+        if let Some(workspace) = &config.workspace_path {
+            cmd.cwd(workspace);
+        }
+        
+        // Set environment for full terminal compatibility
+        cmd.env("TERM", "xterm-256color");
+        cmd.env("COLORTERM", "truecolor");
+        cmd.env("LANG", std::env::var("LANG").unwrap_or_else(|_| "en_US.UTF-8".to_string()));
+        cmd.env("LC_ALL", std::env::var("LC_ALL").unwrap_or_else(|_| "en_US.UTF-8".to_string()));
+        if config.line_buffered {
+            cmd.env("PYTHONUNBUFFERED", "1");
+        }
+        
+        // Pass through important environment variables for full functionality
+        if let Ok(shell) = std::env::var("SHELL") {
+            cmd.env("SHELL", shell);
+        }
+        if let Ok(user) = std::env::var("USER") {
+            cmd.env("USER", user);
+        }
+        if let Ok(home) = std::env::var("HOME") {
+            cmd.env("HOME", home);
+        }
+        cmd.env("PATH", Self::agent_path(config));
+        
+        cmd
+    }
+    
+    /// The `PATH` an agent is spawned with: `path_prepend` first, then, with
+    /// `inherit_path`, the per-user tool directories and the orchestrator's
+    /// own `PATH`. Entries that can't be joined, e.g. ones holding the
+    /// separator, are left out.
+    fn agent_path(config: &AgentConfig) -> std::ffi::OsString {
+        let mut entries = config.path_prepend.clone();
+        if config.inherit_path {
+            if let Ok(home) = std::env::var("HOME") {
+                let home = Path::new(&home);
+                entries.push(home.join(".nvm/versions/node/v22.15.0/bin"));
+                entries.push(home.join(".cargo/bin"));
+                entries.push(home.join(".local/bin"));
+            }
+            if let Some(path) = std::env::var_os("PATH") {
+                entries.extend(std::env::split_paths(&path));
+            }
+        }
+        std::env::join_paths(&entries).unwrap_or_else(|_| {
+            let joinable = entries.iter().filter(|entry| std::env::join_paths([entry]).is_ok());
+            std::env::join_paths(joinable).unwrap_or_default()
         })
     }
+    
+    /// Renders the argv and the names (never the values) of the extra
+    /// environment variables, with sensitive argument values redacted.
+    fn describe_command(cmd: &CommandBuilder) -> String {
+        let mut args = Vec::with_capacity(cmd.get_argv().len());
+        let mut redact_next = false;
+        for arg in cmd.get_argv() {
+            let arg = arg.to_string_lossy();
+            if redact_next {
+                args.push("<redacted>".to_string());
+                redact_next = false;
+                continue;
+            }
+            if let Some((flag, _)) = arg.split_once('=') {
+                if Self::is_sensitive_flag(flag) {
+                    args.push(format!("{}=<redacted>", flag));
+                    continue;
+                }
+            }
+            redact_next = Self::is_sensitive_flag(&arg);
+            args.push(arg.into_owned());
+        }
+        
+        let env_keys: Vec<&str> = cmd.iter_extra_env_as_str().map(|(k, _)| k).collect();
+        format!("{} [env: {}]", args.join(" "), env_keys.join(", "))
+    }
+    
+    fn is_sensitive_flag(arg: &str) -> bool {
+        if !arg.starts_with('-') {
+            return false;
+        }
+        let name = arg.trim_start_matches('-').to_lowercase();
+        SENSITIVE_ARG_MARKERS.iter().any(|marker| name.contains(marker))
+    }
+    
+    /// `command` with the values of sensitive flags and `NAME=value`
+    /// assignments (`export API_TOKEN=...`) replaced, as `describe_command`
+    /// does for argv. Commands without any are returned unchanged.
+    pub(crate) fn redact_command(command: &str) -> String {
+        let mut words = Vec::new();
+        let mut redacted = false;
+        let mut redact_next = false;
+        for word in command.split_whitespace() {
+            if redact_next {
+                words.push("<redacted>".to_string());
+                redacted = true;
+                redact_next = false;
+                continue;
+            }
+            if let Some((name, _)) = word.split_once('=') {
+                let variable = name.to_lowercase();
+                if Self::is_sensitive_flag(name)
+                    || SENSITIVE_ARG_MARKERS.iter().any(|marker| variable.contains(marker))
+                {
+                    words.push(format!("{}=<redacted>", name));
+                    redacted = true;
+                    continue;
+                }
+            }
+            redact_next = Self::is_sensitive_flag(word);
+            words.push(word.to_string());
+        }
+        if redacted {
+            words.join(" ")
+        } else {
+            command.to_string()
+        }
+    }
+    
+    /// Whether `redact_command` replaced a value in `command`.
+    pub(crate) fn is_redacted(command: &str) -> bool {
+        command.split_whitespace().any(|word| word == "<redacted>" || word.ends_with("=<redacted>"))
+    }
+    
+    /// Whether the `unshare` sandboxing relies on resolves on the agents'
+    /// `PATH`, alongside what the platform supports.
+    pub fn sandbox_support() -> serde_json::Value {
+        sandbox::support(Self::find_program("unshare").is_some())
+    }
+    
+    /// Resolves `program` on the `PATH` agents are spawned with.
+    pub(crate) fn find_program(program: &str) -> Option<PathBuf> {
+        let cmd = Self::build_command(&AgentConfig::new(AgentType::Bash));
+        let search_path = cmd.get_env("PATH").map(|p| p.to_os_string()).unwrap_or_default();
+        Self::resolve_program(program, &search_path)
+    }
+    
+    fn resolve_program(program: &str, search_path: &OsStr) -> Option<PathBuf> {
+        if program.contains('/') {
+            let path = PathBuf::from(program);
+            return path.is_file().then_some(path);
+        }
+        std::env::split_paths(search_path)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+    }
 }
 
 impl AgentProcess {
     pub async fn send_command(&self, command: &str) -> Result<()> {
+        let command = self.config.read().await.wrap_command(command);
         let mut writer = self.writer.lock().await;
         
+        // Before writing, so a quick echo is already expected
+        if let Some(echoes) = &self.pending_echoes {
+            echoes.push(&command);
+        }
+        
         // Send command with newline
         writer.write_all(format!("{}\n", command).as_bytes())?;
         writer.flush()?;
         
-        // Update status
+        // Update status; a failed write returned above and is not counted
         let mut status = self.status.write().await;
         status.commands_sent += 1;
         status.last_activity = chrono::Utc::now();
@@ -247,34 +1154,618 @@ impl AgentProcess {
         Ok(())
     }
     
+    pub async fn config(&self) -> AgentConfig {
+        self.config.read().await.clone()
+    }
+    
+    /// Reads `path` and formats it as context for this agent: a fenced code
+    /// block (tagged by file extension) for chat agents, or a quoted
+    /// here-doc for a plain shell.
+    pub async fn file_context(&self, path: &Path, prepend_message: Option<String>) -> Result<String> {
+        let max_bytes = self.config.read().await.max_context_bytes
+            .unwrap_or(DEFAULT_MAX_CONTEXT_BYTES);
+        checks::check_context_file(path, max_bytes).await?;
+        let contents = tokio::fs::read_to_string(path).await?;
+        let contents = contents.trim_end_matches('\n');
+        
+        let block = match self.agent_type {
+            AgentType::Bash => {
+                let mut delimiter = "CONDUCTOR_EOF".to_string();
+                while contents.lines().any(|line| line == delimiter) {
+                    delimiter.push('_');
+                }
+                let mut block = String::new();
+                if let Some(message) = &prepend_message {
+                    for line in message.lines() {
+                        block.push_str(&format!("# {}\n", line));
+                    }
+                }
+                block.push_str(&format!("cat <<'{0}'\n{1}\n{0}", delimiter, contents));
+                block
+            }
+            AgentType::Claude | AgentType::Gemini | AgentType::Plugin(_) => {
+                let language = path.extension()
+                    .and_then(OsStr::to_str)
+                    .map(code_fence_language)
+                    .unwrap_or_default();
+                let fence = if contents.contains("```") { "````" } else { "```" };
+                let mut block = String::new();
+                if let Some(message) = &prepend_message {
+                    block.push_str(message);
+                    block.push('\n');
+                }
+                block.push_str(&format!("{0}{1}\n{2}\n{0}", fence, language, contents));
+                block
+            }
+        };
+        Ok(block)
+    }
+    
+    pub async fn send_file_contents(&self, path: PathBuf, prepend_message: Option<String>) -> Result<()> {
+        let block = self.file_context(&path, prepend_message).await?;
+        self.send_command(&block).await
+    }
+    
+    /// Replaces the prefix/suffix applied by `send_command`.
+    pub async fn set_command_wrapper(&self, prefix: Option<String>, suffix: Option<String>) {
+        let mut config = self.config.write().await;
+        config.command_prefix = prefix;
+        config.command_suffix = suffix;
+        debug!("Updated command wrapper for agent {}", self.id);
+    }
+    
+    /// Sets an environment variable in the agent's running shell by typing
+    /// the assignment in the dialect of the shell its agent type spawns.
+    pub async fn set_pty_env(&self, key: String, value: String) -> Result<()> {
+        let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            bail!("Invalid environment variable name: {:?}", key);
+        }
+        if value.contains(['\n', '\r']) {
+            bail!("Environment variable values cannot contain newlines");
+        }
+        
+        let line = match self.shell {
+            ShellKind::Posix => format!("export {}='{}'\n", key, value.replace('\'', "'\\''")),
+            ShellKind::Cmd => format!("set \"{}={}\"\r\n", key, value),
+        };
+        self.send_raw(line.as_bytes()).await?;
+        
+        debug!("Set {} in PTY environment of agent {}", key, self.id);
+        Ok(())
+    }
+    
     pub async fn resize(&self, rows: u16, cols: u16) -> Result<()> {
-        let pty_pair = self.pty_pair.lock().await;
+        let Some(pty_pair) = &self.pty_pair else {
+            return Err(AgentError::NotSupported {
+                agent_id: self.id.clone(),
+                operation: "resize".to_string(),
+            }.into());
+        };
+        let pty_pair = pty_pair.lock().await;
         pty_pair.master.resize(PtySize {
             rows,
             cols,
             pixel_width: 0,
             pixel_height: 0,
         })?;
+        drop(pty_pair);
+        
+        let mut status = self.status.write().await;
+        status.rows = rows;
+        status.cols = cols;
         info!("Resized PTY for agent {} to {}x{}", self.id, cols, rows);
         Ok(())
     }
     
+    /// Whether commands sent to this agent are run by a POSIX shell.
+    pub fn uses_posix_shell(&self) -> bool {
+        matches!(self.agent_type, AgentType::Bash) && self.shell == ShellKind::Posix
+    }
+    
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+    
+    /// Bytes read from the agent so far, including ones no listener saw.
+    pub fn bytes_received(&self) -> u64 {
+        self.output_meter.total_bytes()
+    }
+    
+    /// Memory, descriptor and CPU usage of the agent's process, with CPU
+    /// measured since the previous call. `None` once the process has
+    /// exited or if its usage can't be read.
+    pub async fn resource_usage(&self) -> Option<ProcessResources> {
+        let pid = self.pid?;
+        if !matches!(self.child.lock().await.try_wait(), Ok(None)) {
+            return None;
+        }
+        self.resource_sampler.sample_one(pid)
+            .map_err(|e| debug!("Could not read resources of agent {} (pid {}): {}", self.id, pid, e))
+            .ok()
+    }
+    
+    /// Pins the running process to `cpus`; see `AgentConfig::cpu_affinity`.
+    pub async fn set_cpu_affinity(&self, cpus: Vec<usize>) -> Result<()> {
+        affinity::validate(&cpus)?;
+        affinity::apply(&self.id, self.pid, &cpus)?;
+        info!("Pinned agent {} to CPUs {:?}", self.id, cpus);
+        self.config.write().await.cpu_affinity = Some(cpus);
+        Ok(())
+    }
+    
+    /// Readiness and prompt patterns in effect, compiled at spawn.
+    pub fn patterns(&self) -> CompiledPatterns {
+        self.patterns.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    fn prompt_pattern(&self) -> Option<Regex> {
+        self.patterns.read().unwrap_or_else(|e| e.into_inner()).prompt.clone()
+    }
+    
+    /// Receives a copy of all output from now on, without taking it from
+    /// `get_output`.
+    pub fn subscribe_output(&self) -> broadcast::Receiver<Vec<u8>> {
+        self.output_tap.subscribe()
+    }
+    
+    /// Receives each output line that matches an error pattern from now on.
+    pub fn subscribe_errors(&self) -> broadcast::Receiver<String> {
+        self.error_tap.subscribe()
+    }
+    
+    /// Waits for the next output chunk, merging any further chunks that
+    /// arrive within the coalescing window or before `flush_output`.
     pub async fn get_output(&self) -> Option<Vec<u8>> {
         let mut receiver = self.output_receiver.lock().await;
-        receiver.recv().await
+        let mut data = receiver.recv().await?;
+        
+        if !self.coalesce_window.is_zero() {
+            // Listen before announcing the merge, so a flush that sees it
+            // is never missed
+            let flushed = self.flush_requested.notified();
+            tokio::pin!(flushed);
+            flushed.as_mut().enable();
+            self.coalescing.send_replace(true);
+            let _merged = CoalescingGuard(&self.coalescing);
+            
+            let deadline = tokio::time::Instant::now() + self.coalesce_window;
+            loop {
+                tokio::select! {
+                    more = tokio::time::timeout_at(deadline, receiver.recv()) => match more {
+                        Ok(Some(more)) => data.extend_from_slice(&more),
+                        _ => break,
+                    },
+                    _ = &mut flushed => {
+                        while let Ok(more) = receiver.try_recv() {
+                            data.extend_from_slice(&more);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        Some(data)
     }
     
-    pub async fn kill(&self) -> Result<()> {
-        info!("Killing agent {}", self.id);
+    /// Ends a coalescing window in progress, so the chunks merged so far are
+    /// returned by `get_output` now, and resolves once they have been.
+    /// Input is flushed on every write, so nothing else is buffered; with
+    /// no merge in progress this returns at once.
+    pub async fn flush_output(&self) {
+        self.flush_requested.notify_waiters();
+        let mut coalescing = self.coalescing.subscribe();
+        // The sender lives as long as `self`, so this cannot fail
+        let _ = coalescing.wait_for(|merging| !merging).await;
+    }
+    
+    /// Objects parsed from the output under `OutputFraming::Jsonl`. Like
+    /// `get_output`, objects it consumes are not seen by other readers.
+    pub fn json_stream(self: Arc<Self>) -> Pin<Box<dyn Stream<Item = Value> + Send>> {
+        Box::pin(stream::unfold(self, |agent| async move {
+            let object = agent.json_output_receiver.lock().await.recv().await?;
+            Some((object, agent))
+        }))
+    }
+    
+    pub async fn set_color_scheme(&self, scheme: Option<ColorScheme>) {
+        *self.color_scheme.write().await = scheme;
+    }
+    
+    /// Like `get_output`, with styling resolved against the active color
+    /// scheme. `None` when the stream has ended or no scheme is set.
+    pub async fn get_colorized_output(&self) -> Option<Vec<ColorizedChunk>> {
+        let scheme = self.color_scheme.read().await.clone()?;
+        let data = self.get_output().await?;
+        Some(self.sgr_parser.lock().await.push(&data, &scheme))
+    }
+    
+    /// Turns the output stream into styled chunks for `scheme`. This takes
+    /// over `get_output`: chunks it consumes are not seen by other readers.
+    pub fn colorize_output(
+        self: Arc<Self>,
+        scheme: ColorScheme,
+    ) -> Pin<Box<dyn Stream<Item = ColorizedChunk> + Send>> {
+        let chunks = stream::unfold(
+            (self, SgrParser::new()),
+            move |(agent, mut parser)| {
+                let scheme = scheme.clone();
+                async move {
+                    let data = agent.get_output().await?;
+                    let chunks = parser.push(&data, &scheme);
+                    Some((stream::iter(chunks), (agent, parser)))
+                }
+            },
+        );
+        Box::pin(stream::StreamExt::flatten(chunks))
+    }
+    
+    /// The last `n` complete scrollback lines, then with `follow` each new
+    /// line as it completes, like `tail -f`. A following stream ends once
+    /// the agent has exited and its output has gone quiet. New lines are read
+    /// from the scrollback, so they are filtered as stored there, and lines
+    /// evicted before the stream catches up are skipped.
+    pub fn tail(self: Arc<Self>, n: usize, follow: bool) -> Pin<Box<dyn Stream<Item = String> + Send>> {
+        let state = TailState {
+            output: follow.then(|| self.subscribe_output()),
+            agent: self,
+            next_line: None,
+            pending: VecDeque::new(),
+            exited: false,
+            done: false,
+        };
+        Box::pin(stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(line) = state.pending.pop_front() {
+                    return Some((line, state));
+                }
+                if state.done {
+                    return None;
+                }
+                let start = match state.next_line {
+                    None => {
+                        state.done = state.output.is_none();
+                        let total_lines = state.agent.scrollback.lock().await.range(0, 0).total_lines;
+                        total_lines.saturating_sub(n)
+                    }
+                    Some(next_line) => {
+                        let output = state.output.as_mut()?;
+                        if state.exited {
+                            // Lines printed right before the exit may still
+                            // be on their way from the readers
+                            let received = tokio::time::timeout(EXIT_POLL_INTERVAL, output.recv()).await;
+                            state.done = !matches!(
+                                received,
+                                Ok(Ok(_) | Err(broadcast::error::RecvError::Lagged(_)))
+                            );
+                        } else {
+                            tokio::select! {
+                                received = output.recv() => {
+                                    state.done = matches!(received, Err(broadcast::error::RecvError::Closed));
+                                }
+                                _ = state.agent.wait() => state.exited = true,
+                            }
+                        }
+                        next_line
+                    }
+                };
+                let range = state.agent.scrollback.lock().await.range(start, usize::MAX);
+                state.next_line = Some(range.total_lines);
+                state.pending.extend(range.lines);
+            }
+        }))
+    }
+    
+    /// The last `lines` complete scrollback lines, or all of them.
+    pub async fn get_scrollback(&self, lines: Option<usize>) -> Vec<String> {
+        let scrollback = self.scrollback.lock().await;
+        scrollback.tail(lines.unwrap_or(usize::MAX))
+    }
+    
+    /// The last `rows` lines of output, including the line being written
+    /// (usually the prompt). Without a terminal parser this cannot account
+    /// for cursor movement, so it is the scrollback tail rather than a
+    /// rendered screen, and lines are not padded to `cols`.
+    pub async fn screen_dump(&self) -> Result<String> {
+        let rows = self.status.read().await.rows as usize;
+        let lines = self.scrollback.lock().await.screen(rows);
+        Ok(lines.join("\n"))
+    }
+    
+    /// Captures the assembled scrollback, including the unterminated line,
+    /// for `restore_terminal_view`.
+    pub async fn snapshot_terminal(&self) -> TerminalSnapshot {
+        let (rows, cols) = {
+            let status = self.status.read().await;
+            (status.rows, status.cols)
+        };
+        TerminalSnapshot {
+            lines: self.scrollback.lock().await.screen(usize::MAX),
+            rows,
+            cols,
+            taken_at: chrono::Utc::now(),
+        }
+    }
+    
+    /// Re-emits a snapshot on the live output stream so a terminal view can
+    /// repaint. Only the stream sees it; scrollback is left unchanged.
+    pub async fn restore_terminal_view(&self, snapshot: &TerminalSnapshot) -> Result<()> {
+        self.output_sender.send(snapshot.replay_bytes()).await
+            .map_err(|_| anyhow::anyhow!("Output stream for agent {} is closed", self.id))
+    }
+    
+    /// Waits until the prompt pattern shows up after the previous response
+    /// and returns the scrollback lines in between, without the prompt line
+    /// itself. Sends nothing; pair it with `send_command`. Lines are matched
+    /// with escape sequences removed but returned as received.
+    pub async fn get_last_response(&self, timeout: Duration) -> Result<String> {
+        let Some(prompt) = self.prompt_pattern() else {
+            return Err(AgentError::NotSupported {
+                agent_id: self.id.clone(),
+                operation: "waiting for a response without a prompt pattern".to_string(),
+            }.into());
+        };
+        // Subscribe before scanning so output arriving in between still
+        // wakes the loop up
+        let mut output = self.subscribe_output();
+        let mut start = self.last_prompt_pos.lock().await;
+        let deadline = tokio::time::Instant::now() + timeout;
+        
+        loop {
+            if let Some((prompt_line, response)) = self.find_prompt(&prompt, *start).await {
+                *start = prompt_line + 1;
+                return Ok(response.join("\n"));
+            }
+            match tokio::time::timeout_at(deadline, output.recv()).await {
+                Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => {}
+                Ok(Err(broadcast::error::RecvError::Closed)) => {
+                    bail!("Output of agent {} ended before its prompt appeared", self.id);
+                }
+                Err(_) => {
+                    bail!("Agent {} showed no prompt within {} ms", self.id, timeout.as_millis());
+                }
+            }
+        }
+    }
+    
+    /// Marks the responses already in the scrollback as read, so the next
+    /// `get_last_response` only sees output that follows. Returns whether
+    /// the agent has shown its prompt at all; until it has, the next
+    /// "response" is its startup output.
+    pub async fn skip_responses(&self) -> bool {
+        let Some(prompt) = self.prompt_pattern() else {
+            return false;
+        };
+        let mut start = self.last_prompt_pos.lock().await;
+        while let Some((prompt_line, _)) = self.find_prompt(&prompt, *start).await {
+            *start = prompt_line + 1;
+        }
+        *start > 0
+    }
+    
+    /// Waits until a scrollback line from `start` on, or the line being
+    /// written, matches the readiness pattern. Agents without one, and
+    /// agents on pipes, where no prompt is drawn, are ready right away.
+    pub async fn wait_until_ready(&self, start: usize, timeout: Duration) -> Result<()> {
+        let Some(readiness) = self.patterns().readiness else {
+            return Ok(());
+        };
+        if self.config.read().await.tty_type == TtyType::Pipe {
+            return Ok(());
+        }
+        let mut output = self.subscribe_output();
+        let deadline = tokio::time::Instant::now() + timeout;
         
-        // Send Ctrl+C first to try graceful shutdown
-        self.send_raw(b"\x03").await.ok();
+        loop {
+            if self.find_prompt(&readiness, start).await.is_some() {
+                return Ok(());
+            }
+            match tokio::time::timeout_at(deadline, output.recv()).await {
+                Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => {}
+                Ok(Err(broadcast::error::RecvError::Closed)) => {
+                    bail!("Output of agent {} ended before it was ready", self.id);
+                }
+                Err(_) => {
+                    bail!("Agent {} was not ready within {} ms", self.id, timeout.as_millis());
+                }
+            }
+        }
+    }
+    
+    /// Has the agent print a sentinel line for `marker` after everything
+    /// sent so far and returns a handle that resolves once the line comes
+    /// back. The sentinel line is left out of the scrollback and output
+    /// stream. Only shell agents can print it; `marker` may hold letters,
+    /// digits, `-` and `_`.
+    pub async fn output_watermark(&self, marker: String) -> Result<WatermarkHandle> {
+        if !matches!(self.agent_type, AgentType::Bash) {
+            return Err(AgentError::NotSupported {
+                agent_id: self.id.clone(),
+                operation: "output watermarks".to_string(),
+            }.into());
+        }
+        if !watermark::is_valid_marker(&marker) {
+            bail!("Invalid watermark marker {:?}", marker);
+        }
+        // Registered first so a fast reply can't slip past the readers
+        let handle = self.watermarks.register(&marker);
+        self.send_command(&watermark::sentinel_command(&marker)).await?;
+        Ok(handle)
+    }
+    
+    /// Works out the agent's prompt from its replies to bare newlines and
+    /// uses it as the prompt pattern from now on, for `get_last_response`
+    /// too. Newlines are sent until `sample_lines` lines of output have
+    /// come back, at least two and at most `MAX_PROMPT_PROBES`; the part
+    /// the last line of every reply ends with is taken as the prompt, so a
+    /// varying prefix such as a session id drops out. Returns the stored
+    /// pattern, that text escaped and anchored at the end of the line.
+    pub async fn detect_prompt(&self, sample_lines: usize) -> Result<String> {
+        if sample_lines == 0 {
+            bail!("At least one sample line is needed to detect a prompt");
+        }
+        let mut output = self.subscribe_output();
+        // Let startup output finish so it isn't taken for a reply
+        settle(&mut output).await;
+        
+        let mut prompts = Vec::new();
+        let mut sampled = 0;
+        for probe in 0..MAX_PROMPT_PROBES {
+            if probe >= 2 && sampled >= sample_lines {
+                break;
+            }
+            let start = self.scrollback.lock().await.range(usize::MAX, usize::MAX).total_lines;
+            self.send_raw(b"\n").await?;
+            settle(&mut output).await;
+            
+            let reply = {
+                let scrollback = self.scrollback.lock().await;
+                let range = scrollback.range(start, usize::MAX);
+                range.lines.iter().map(String::as_str)
+                    .chain(Some(scrollback.partial()))
+                    .map(|line| plain_text(line).trim_end_matches('\r').to_string())
+                    .filter(|line| !line.trim().is_empty())
+                    .collect::<Vec<_>>()
+            };
+            sampled += reply.len();
+            prompts.extend(reply.last().cloned());
+        }
+        
+        let prompt = common_suffix(&prompts);
+        if prompts.is_empty() || prompt.trim().is_empty() {
+            bail!("Agent {} showed no recognizable prompt", self.id);
+        }
+        let pattern = format!("{}$", regex::escape(&prompt));
+        let compiled = Regex::new(&pattern)?;
+        self.patterns.write().unwrap_or_else(|e| e.into_inner()).prompt = Some(compiled);
+        self.config.write().await.patterns.prompt = Some(pattern.clone());
+        // The replies to the probes are not a response
+        self.skip_responses().await;
+        
+        info!("Detected prompt {:?} of agent {}", prompt, self.id);
+        Ok(pattern)
+    }
+    
+    /// The first line from `start` on, including the unterminated one, that
+    /// matches `prompt`, with the lines before it.
+    async fn find_prompt(&self, prompt: &Regex, start: usize) -> Option<(usize, Vec<String>)> {
+        let scrollback = self.scrollback.lock().await;
+        let range = scrollback.range(start, usize::MAX);
+        // `range` clamps to the complete lines, so the unterminated line may
+        // be the prompt this scan starts after
+        let partial = Some(scrollback.partial())
+            .filter(|line| !line.is_empty() && range.total_lines >= start);
+        
+        let lines = range.lines.iter().map(String::as_str).chain(partial);
+        for (offset, line) in lines.enumerate() {
+            if prompt.is_match(&plain_text(line)) {
+                return Some((range.start + offset, range.lines[..offset].to_vec()));
+            }
+        }
+        None
+    }
+    
+    /// Scrollback lines `start..end` with the bounds a virtualized viewer
+    /// needs; see `OutputBuffer::range` for numbering.
+    pub async fn get_lines(&self, start: usize, end: usize) -> OutputRange {
+        self.scrollback.lock().await.range(start, end)
+    }
+    
+    /// Binary outputs from index `since_index` on, numbered from the first
+    /// one the agent produced.
+    pub async fn binary_outputs(&self, since_index: usize) -> Vec<(usize, BinaryOutput)> {
+        self.binary_outputs.lock().await
+            .since(since_index)
+            .map(|(index, output)| (index, output.clone()))
+            .collect()
+    }
+    
+    /// Character and line-length statistics over the current scrollback.
+    /// The lock is only held while the lines are copied out.
+    pub async fn output_stats(&self) -> OutputStats {
+        let lines = self.get_scrollback(None).await;
+        OutputStats::from_lines(&lines)
+    }
+    
+    /// Drops output lines `predicate` rejects from the scrollback and the
+    /// output channel, replacing any earlier filter. Output already stored
+    /// is only affected by a later `refilter_scrollback`.
+    pub fn output_filter(&self, predicate: LinePredicate) {
+        *self.output_filter.write().unwrap_or_else(|e| e.into_inner()) = Some(predicate);
+    }
+    
+    pub fn clear_output_filter(&self) {
+        *self.output_filter.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+    
+    /// Runs the stored scrollback through the current output filter,
+    /// replacing the lines it rejects with `REDACTED_LINE`, and returns how
+    /// many were replaced. Line numbers are unchanged, so prompt and tail
+    /// positions and the task history's `output_line`s still point where
+    /// they did. The reader waits on the scrollback lock meanwhile, so live
+    /// output is neither lost nor interleaved, and the channel is not
+    /// touched.
+    pub async fn refilter_scrollback(&self) -> usize {
+        let predicate = self.output_filter.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let Some(predicate) = predicate else {
+            return 0;
+        };
+        self.scrollback.lock().await.redact_lines(|line| predicate(line))
+    }
+    
+    pub async fn set_collapse_cr_updates(&self, enabled: bool) {
+        self.scrollback.lock().await.set_collapse_cr_updates(enabled);
+        self.config.write().await.collapse_cr_updates = enabled;
+    }
+    
+    /// Waits for the agent's process to exit. Polls rather than blocking a
+    /// thread, so dropping the future abandons the wait.
+    pub async fn wait(&self) -> Result<ExitStatus> {
+        let code = loop {
+            if let Some(code) = self.child.lock().await.try_wait()? {
+                break code;
+            }
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        };
         
-        // Wait a bit
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        self.status.write().await.running = false;
+        debug!("Agent {} exited with code {}", self.id, code);
+        Ok(ExitStatus::Exited { code })
+    }
+    
+    /// Runs the configured shutdown sequence, then force-kills the agent if
+    /// it is still running.
+    pub async fn kill(&self) -> Result<()> {
+        info!("Killing agent {}", self.id);
         
-        // Send Ctrl+D to PTY
-        self.send_raw(b"\x04").await.ok();
+        let sequence = self.config.read().await.effective_shutdown_sequence();
+        for step in sequence {
+            if self.child.lock().await.try_wait()?.is_some() {
+                break;
+            }
+            match step {
+                // The agent may already be going away; writes are best effort
+                ShutdownStep::Raw(data) => {
+                    self.send_raw(&data).await.ok();
+                }
+                ShutdownStep::Interrupt => {
+                    if let Err(e) = self.send_interrupt().await {
+                        debug!("Writing ^C to agent {} instead: {}", self.id, e);
+                        self.send_raw(b"\x03").await.ok();
+                    }
+                }
+                ShutdownStep::Command(command) => {
+                    self.send_raw(format!("{}\n", command).as_bytes()).await.ok();
+                }
+                // Ends early if the agent exits
+                ShutdownStep::Wait(duration) => {
+                    let _ = tokio::time::timeout(duration, self.wait()).await;
+                }
+                ShutdownStep::ForceKill => break,
+            }
+        }
+        self.force_kill().await?;
         
         let mut status = self.status.write().await;
         status.running = false;
@@ -282,17 +1773,183 @@ impl AgentProcess {
         Ok(())
     }
     
-    pub async fn get_status(&self) -> serde_json::Value {
+    /// Sends SIGINT to the agent's foreground process group: the job its
+    /// shell is running, or the agent itself. Unlike writing `^C` this
+    /// doesn't rely on the terminal turning the byte into a signal, which
+    /// it won't in raw mode. On Windows the group gets Ctrl+Break.
+    pub async fn send_interrupt(&self) -> Result<()> {
+        let Some(pid) = self.pid else {
+            return Err(AgentError::NotSupported {
+                agent_id: self.id.clone(),
+                operation: "interrupting an agent without a known pid".to_string(),
+            }.into());
+        };
+        if !matches!(self.child.lock().await.try_wait(), Ok(None)) {
+            bail!("Agent {} is not running", self.id);
+        }
+        
+        // Piped agents lead their own group; on a PTY the terminal knows
+        // which group is in the foreground
+        #[cfg(unix)]
+        let group = match &self.pty_pair {
+            Some(pty_pair) => pty_pair.lock().await.master.process_group_leader().map(|leader| leader as u32),
+            None => None,
+        }.unwrap_or(pid);
+        #[cfg(not(unix))]
+        let group = pid;
+        interrupt::interrupt_group(&self.id, group)?;
+        
+        self.status.write().await.signals_sent += 1;
+        debug!("Interrupted process group {} of agent {}", group, self.id);
+        Ok(())
+    }
+    
+    /// Kills the process unless it has already exited.
+    async fn force_kill(&self) -> Result<()> {
+        let mut child = self.child.lock().await;
+        if child.try_wait()?.is_some() {
+            return Ok(());
+        }
+        debug!("Force-killing agent {}", self.id);
+        if self.pty_pair.is_none() {
+            // Without a PTY control characters are just bytes: close stdin
+            // so the agent at least sees EOF
+            *self.writer.lock().await = Box::new(std::io::sink());
+        }
+        child.kill()
+    }
+    
+    pub async fn record_response_timeout(&self) {
+        self.status.write().await.response_timeout_count += 1;
+    }
+    
+    /// Forgets the matched error lines, so the agent no longer shows as
+    /// failing.
+    pub async fn clear_error(&self) {
+        let mut status = self.status.write().await;
+        status.last_error = None;
+        status.error_count = 0;
+    }
+    
+    pub async fn is_headless(&self) -> bool {
+        self.status.read().await.headless
+    }
+    
+    pub async fn start_time(&self) -> chrono::DateTime<chrono::Utc> {
+        self.status.read().await.start_time
+    }
+    
+    /// Terminal size as `(rows, cols)`.
+    pub async fn terminal_size(&self) -> (u16, u16) {
         let status = self.status.read().await;
-        json!({
-            "id": status.id,
-            "type": status.agent_type,
-            "running": status.running,
-            "start_time": status.start_time.to_rfc3339(),
-            "last_activity": status.last_activity.to_rfc3339(),
-            "commands_sent": status.commands_sent,
-            "workspace": status.workspace,
-        })
+        (status.rows, status.cols)
+    }
+    
+    pub async fn status_dto(&self) -> AgentStatusDto {
+        let status = self.status.read().await;
+        AgentStatusDto {
+            id: status.id.clone(),
+            agent_type: status.agent_type.clone(),
+            running: status.running,
+            start_time: status.start_time,
+            last_activity: status.last_activity,
+            commands_sent: status.commands_sent,
+            workspace: status.workspace.clone(),
+            managed: status.managed,
+            reattached: status.reattached,
+            headless: status.headless,
+            rows: status.rows,
+            cols: status.cols,
+            has_output: self.output_meter.total_bytes() > 0,
+            bytes_received: self.output_meter.total_bytes(),
+            output_rate_bps: self.output_meter.rate_bps().round() as u64,
+            response_timeout_count: status.response_timeout_count,
+            last_error: status.last_error.clone(),
+            error_count: status.error_count,
+            signals_sent: status.signals_sent,
+        }
+    }
+    
+    pub async fn get_status(&self) -> serde_json::Value {
+        serde_json::to_value(self.status_dto().await).unwrap_or_default()
+    }
+}
+
+/// Markdown fence tag for a file extension; unknown extensions are used as-is.
+fn code_fence_language(extension: &str) -> &str {
+    match extension {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "tsx" => "tsx",
+        "sh" | "bash" => "bash",
+        "md" => "markdown",
+        "yml" | "yaml" => "yaml",
+        "h" | "c" => "c",
+        "cc" | "cpp" | "hpp" => "cpp",
+        "rb" => "ruby",
+        other => other,
+    }
+}
+
+/// Waits until `output` has been quiet for `PROMPT_SETTLE`, or at most
+/// `PROMPT_SETTLE_LIMIT`.
+async fn settle(output: &mut broadcast::Receiver<Vec<u8>>) {
+    let limit = tokio::time::Instant::now() + PROMPT_SETTLE_LIMIT;
+    while tokio::time::Instant::now() < limit {
+        match tokio::time::timeout(PROMPT_SETTLE, output.recv()).await {
+            Ok(Ok(_)) | Ok(Err(broadcast::error::RecvError::Lagged(_))) => {}
+            Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => return,
+        }
+    }
+}
+
+/// The longest text every line ends with.
+fn common_suffix(lines: &[String]) -> String {
+    let Some((first, rest)) = lines.split_first() else {
+        return String::new();
+    };
+    let mut suffix: &str = first;
+    for line in rest {
+        let shared = suffix.chars().rev()
+            .zip(line.chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum::<usize>();
+        suffix = &suffix[suffix.len() - shared..];
+    }
+    suffix.to_string()
+}
+
+#[async_trait]
+impl AgentProcessTrait for AgentProcess {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    async fn send_command(&self, command: &str) -> Result<()> {
+        AgentProcess::send_command(self, command).await
+    }
+    
+    async fn send_raw(&self, data: &[u8]) -> Result<()> {
+        AgentProcess::send_raw(self, data).await
+    }
+    
+    async fn resize(&self, rows: u16, cols: u16) -> Result<()> {
+        AgentProcess::resize(self, rows, cols).await
+    }
+    
+    async fn get_output(&self) -> Option<Vec<u8>> {
+        AgentProcess::get_output(self).await
+    }
+    
+    async fn kill(&self) -> Result<()> {
+        AgentProcess::kill(self).await
+    }
+    
+    async fn get_status(&self) -> serde_json::Value {
+        AgentProcess::get_status(self).await
     }
 }
 
@@ -307,4 +1964,20 @@ impl Drop for AgentProcess {
             info!("Cleaned up agent {}", id);
         });
     }
-}
\ No newline at end of file
+}
+
+mod option_duration_millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&(duration.as_millis() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+    }
+}
@@ -0,0 +1,123 @@
+// Binary Output - Non-text output chunks kept apart from the scrollback
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use infer::MatcherType;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+
+/// Binary outputs kept per agent before the oldest are evicted.
+pub const MAX_BINARY_OUTPUTS: usize = 64;
+/// Largest single binary output; further continuation bytes are dropped.
+pub const MAX_BINARY_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct BinaryOutput {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl BinaryOutput {
+    /// The output as the frontend receives it, with `data` base64-encoded.
+    pub fn to_json(&self, index: usize) -> Value {
+        json!({
+            "index": index,
+            "mime_type": self.mime_type,
+            "size": self.data.len(),
+            "data": base64::engine::general_purpose::STANDARD.encode(&self.data),
+            "timestamp": self.timestamp.to_rfc3339(),
+        })
+    }
+}
+
+/// Binary outputs of one agent, numbered from the first it ever produced
+/// so indexes stay stable as old ones are evicted.
+#[derive(Debug, Default)]
+pub struct BinaryOutputs {
+    outputs: VecDeque<BinaryOutput>,
+    evicted: usize,
+}
+
+/// Splits binary off one output reader's chunks. Each reader has its own,
+/// so where stdout and stderr share one `BinaryOutputs` (pipe agents) a
+/// chunk only continues an output its own reader started.
+#[derive(Debug, Default)]
+pub(crate) struct BinarySplitter {
+    /// Index of the output the previous chunk started or continued.
+    open: Option<usize>,
+}
+
+impl BinarySplitter {
+    /// Keeps the binary part of `data`, if any, in `outputs` and returns the
+    /// text before it for the scrollback. While an output is open, a chunk
+    /// that is not UTF-8 continues it, since the reader splits large
+    /// payloads. Otherwise binary starts at the start of the chunk or of a
+    /// line in it, where `infer` recognizes a non-text type and the bytes
+    /// are not UTF-8; the latter keeps text that merely begins like a
+    /// signature (`MZ`, `%PDF`) in the scrollback.
+    pub(crate) fn split_off_binary<'a>(&mut self, outputs: &mut BinaryOutputs, data: &'a [u8]) -> &'a [u8] {
+        if let Some(index) = self.open.filter(|_| !is_text(data)) {
+            // Dropped if it was evicted meanwhile
+            if let Some(output) = outputs.get_mut(index) {
+                let room = MAX_BINARY_OUTPUT_BYTES.saturating_sub(output.data.len());
+                output.data.extend_from_slice(&data[..data.len().min(room)]);
+            }
+            return &[];
+        }
+        self.open = None;
+
+        let line_starts = std::iter::once(0)
+            .chain(data.iter().enumerate().filter(|(_, &b)| b == b'\n').map(|(i, _)| i + 1))
+            .filter(|&start| start < data.len());
+        for start in line_starts {
+            let rest = &data[start..];
+            let Some(kind) = infer::get(rest) else {
+                continue;
+            };
+            if kind.matcher_type() == MatcherType::Text || is_text(rest) {
+                continue;
+            }
+            self.open = Some(outputs.push(BinaryOutput {
+                mime_type: kind.mime_type().to_string(),
+                data: rest.to_vec(),
+                timestamp: Utc::now(),
+            }));
+            return &data[..start];
+        }
+        data
+    }
+}
+
+impl BinaryOutputs {
+    /// Adds an output, evicting the oldest past `MAX_BINARY_OUTPUTS`, and
+    /// returns its index.
+    fn push(&mut self, output: BinaryOutput) -> usize {
+        self.outputs.push_back(output);
+        while self.outputs.len() > MAX_BINARY_OUTPUTS {
+            self.outputs.pop_front();
+            self.evicted += 1;
+        }
+        self.evicted + self.outputs.len() - 1
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut BinaryOutput> {
+        self.outputs.get_mut(index.checked_sub(self.evicted)?)
+    }
+
+    /// Outputs from index `since_index` on, with their indexes.
+    pub fn since(&self, since_index: usize) -> impl Iterator<Item = (usize, &BinaryOutput)> {
+        let skip = since_index.saturating_sub(self.evicted);
+        self.outputs.iter()
+            .enumerate()
+            .skip(skip)
+            .map(move |(i, output)| (self.evicted + i, output))
+    }
+}
+
+/// Valid UTF-8, allowing a character cut off at the end of the chunk.
+fn is_text(data: &[u8]) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
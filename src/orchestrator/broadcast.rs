@@ -0,0 +1,12 @@
+// Broadcast Report - Outcome of sending the same input to several agents
+use serde::{Deserialize, Serialize};
+
+/// What `AgentOrchestrator::broadcast_raw` delivered, agent by agent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastReport {
+    pub sent_to: Vec<String>,
+    /// Agents the data could not be written to, with the error.
+    pub failed: Vec<(String, String)>,
+    /// Summed over `sent_to`.
+    pub bytes_sent: usize,
+}
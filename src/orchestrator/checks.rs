@@ -0,0 +1,44 @@
+// Orchestration Checks - Validation shared by the orchestrator and its dry run
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use super::agent_manager::{AgentConfig, AgentManager, AgentType};
+use crate::plugin::PluginManager;
+
+/// Fails if `agent_id` is `taken`, or the `running` agents already reach
+/// `max_agents`.
+pub(crate) fn check_spawn(agent_id: &str, taken: bool, running: usize, max_agents: Option<usize>) -> Result<()> {
+    if taken {
+        bail!("Agent {} already exists", agent_id);
+    }
+    if let Some(max_agents) = max_agents {
+        if running >= max_agents {
+            bail!("Agent limit reached ({} of {} running)", running, max_agents);
+        }
+    }
+    Ok(())
+}
+
+/// The spawn-time checks for `config`: a plugin agent's plugin must be
+/// loaded, anything else must pass `AgentManager::validate`.
+pub(crate) fn check_config(config: &AgentConfig, plugins: &PluginManager) -> Result<()> {
+    if let AgentType::Plugin(name) = &config.agent_type {
+        if !plugins.contains(name) {
+            bail!("Plugin {} not loaded", name);
+        }
+        return Ok(());
+    }
+    AgentManager::validate(config)
+}
+
+/// The size of the file at `path`, failing if it is over `max_bytes`.
+pub(crate) async fn check_context_file(path: &Path, max_bytes: usize) -> Result<u64> {
+    let size = tokio::fs::metadata(path).await?.len();
+    if size > max_bytes as u64 {
+        bail!(
+            "{} is {} bytes, over the {} byte context limit",
+            path.display(), size, max_bytes
+        );
+    }
+    Ok(size)
+}
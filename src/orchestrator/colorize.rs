@@ -0,0 +1,284 @@
+// Colorize - Remaps ANSI SGR styling in agent output onto a color scheme
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(rgb: Rgb) -> Self {
+        rgb.to_string()
+    }
+}
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        let hex = s.strip_prefix('#').unwrap_or(&s);
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)
+            .ok_or_else(|| format!("Invalid color {} (expected #rrggbb)", s))?;
+        Ok(Rgb::new((value >> 16) as u8, (value >> 8) as u8, value as u8))
+    }
+}
+
+/// Colors applied in place of the terminal defaults. `error` replaces red
+/// and `prompt` replaces green, which shells use for prompts by default;
+/// the other ANSI colors keep the xterm palette.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorScheme {
+    pub foreground: Rgb,
+    pub background: Rgb,
+    pub error: Rgb,
+    pub prompt: Rgb,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        Self {
+            foreground: Rgb::new(0xe5, 0xe5, 0xe5),
+            background: Rgb::new(0x00, 0x00, 0x00),
+            error: Rgb::new(0xcd, 0x00, 0x00),
+            prompt: Rgb::new(0x00, 0xcd, 0x00),
+        }
+    }
+}
+
+impl ColorScheme {
+    fn indexed(&self, index: u8) -> Rgb {
+        match index {
+            1 | 9 => self.error,
+            2 | 10 => self.prompt,
+            0..=15 => XTERM_PALETTE[index as usize],
+            16..=231 => {
+                // 6x6x6 color cube
+                let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                let i = index - 16;
+                Rgb::new(level(i / 36), level(i / 6 % 6), level(i % 6))
+            }
+            232..=255 => {
+                let gray = 8 + (index - 232) * 10;
+                Rgb::new(gray, gray, gray)
+            }
+        }
+    }
+}
+
+const XTERM_PALETTE: [Rgb; 16] = [
+    Rgb::new(0x00, 0x00, 0x00),
+    Rgb::new(0xcd, 0x00, 0x00),
+    Rgb::new(0x00, 0xcd, 0x00),
+    Rgb::new(0xcd, 0xcd, 0x00),
+    Rgb::new(0x00, 0x00, 0xee),
+    Rgb::new(0xcd, 0x00, 0xcd),
+    Rgb::new(0x00, 0xcd, 0xcd),
+    Rgb::new(0xe5, 0xe5, 0xe5),
+    Rgb::new(0x7f, 0x7f, 0x7f),
+    Rgb::new(0xff, 0x00, 0x00),
+    Rgb::new(0x00, 0xff, 0x00),
+    Rgb::new(0xff, 0xff, 0x00),
+    Rgb::new(0x5c, 0x5c, 0xff),
+    Rgb::new(0xff, 0x00, 0xff),
+    Rgb::new(0x00, 0xff, 0xff),
+    Rgb::new(0xff, 0xff, 0xff),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColorizedChunk {
+    pub text: String,
+    pub fg: Rgb,
+    pub bg: Rgb,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Color {
+    Default,
+    Indexed(u8),
+    Rgb(Rgb),
+}
+
+/// Streaming SGR parser. Styled text is emitted as chunks; other escape
+/// sequences (cursor movement, OSC titles, ...) are dropped. Sequences and
+/// UTF-8 characters split across reads are carried to the next one.
+#[derive(Debug)]
+pub struct SgrParser {
+    fg: Color,
+    bg: Color,
+    bold: bool,
+    italic: bool,
+    /// Unconsumed bytes: an incomplete escape sequence or UTF-8 character.
+    carry: Vec<u8>,
+}
+
+impl Default for SgrParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SgrParser {
+    pub fn new() -> Self {
+        Self {
+            fg: Color::Default,
+            bg: Color::Default,
+            bold: false,
+            italic: false,
+            carry: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8], scheme: &ColorScheme) -> Vec<ColorizedChunk> {
+        let mut bytes = std::mem::take(&mut self.carry);
+        bytes.extend_from_slice(data);
+
+        let mut chunks = Vec::new();
+        let mut text_start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] != 0x1b {
+                i += 1;
+                continue;
+            }
+            let Some(len) = escape_len(&bytes[i..]) else {
+                // Incomplete sequence; finish the text before it and wait
+                self.emit(&bytes[text_start..i], scheme, &mut chunks);
+                self.carry = bytes[i..].to_vec();
+                return chunks;
+            };
+            self.emit(&bytes[text_start..i], scheme, &mut chunks);
+            let sequence = &bytes[i..i + len];
+            if sequence.len() >= 3 && sequence[1] == b'[' && sequence[len - 1] == b'm' {
+                self.apply_sgr(&sequence[2..len - 1]);
+            }
+            i += len;
+            text_start = i;
+        }
+
+        let text = &bytes[text_start..];
+        let complete = match std::str::from_utf8(text) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => text.len(),
+        };
+        self.carry = text[complete..].to_vec();
+        self.emit(&text[..complete], scheme, &mut chunks);
+        chunks
+    }
+
+    fn emit(&self, text: &[u8], scheme: &ColorScheme, chunks: &mut Vec<ColorizedChunk>) {
+        if text.is_empty() {
+            return;
+        }
+        let resolve = |color: Color, default: Rgb| match color {
+            Color::Default => default,
+            Color::Indexed(index) => scheme.indexed(index),
+            Color::Rgb(rgb) => rgb,
+        };
+        chunks.push(ColorizedChunk {
+            text: String::from_utf8_lossy(text).into_owned(),
+            fg: resolve(self.fg, scheme.foreground),
+            bg: resolve(self.bg, scheme.background),
+            bold: self.bold,
+            italic: self.italic,
+        });
+    }
+
+    fn apply_sgr(&mut self, params: &[u8]) {
+        let params: Vec<u16> = String::from_utf8_lossy(params)
+            .split(';')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => {
+                    self.fg = Color::Default;
+                    self.bg = Color::Default;
+                    self.bold = false;
+                    self.italic = false;
+                }
+                1 => self.bold = true,
+                3 => self.italic = true,
+                22 => self.bold = false,
+                23 => self.italic = false,
+                code @ 30..=37 => self.fg = Color::Indexed((code - 30) as u8),
+                code @ 90..=97 => self.fg = Color::Indexed((code - 90 + 8) as u8),
+                39 => self.fg = Color::Default,
+                code @ 40..=47 => self.bg = Color::Indexed((code - 40) as u8),
+                code @ 100..=107 => self.bg = Color::Indexed((code - 100 + 8) as u8),
+                49 => self.bg = Color::Default,
+                code @ (38 | 48) => {
+                    let (color, used) = extended_color(&params[i + 1..]);
+                    if let Some(color) = color {
+                        if code == 38 {
+                            self.fg = color;
+                        } else {
+                            self.bg = color;
+                        }
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// `text` with all escape sequences removed.
+pub(crate) fn plain_text(text: &str) -> String {
+    SgrParser::new().push(text.as_bytes(), &ColorScheme::default())
+        .into_iter()
+        .map(|chunk| chunk.text)
+        .collect()
+}
+
+/// Parses the arguments after 38/48: `5;n` or `2;r;g;b`. Returns the color
+/// and how many parameters it consumed.
+fn extended_color(params: &[u16]) -> (Option<Color>, usize) {
+    match params {
+        [5, n, ..] => (Some(Color::Indexed(*n as u8)), 2),
+        [2, r, g, b, ..] => (Some(Color::Rgb(Rgb::new(*r as u8, *g as u8, *b as u8))), 4),
+        _ => (None, params.len()),
+    }
+}
+
+/// Length of the escape sequence at the start of `bytes`, or `None` if it
+/// is cut off.
+fn escape_len(bytes: &[u8]) -> Option<usize> {
+    match bytes.get(1)? {
+        b'[' => bytes[2..].iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map(|end| end + 3),
+        b']' => {
+            // OSC, terminated by BEL or ESC \
+            let body = &bytes[2..];
+            body.iter().enumerate().find_map(|(i, &b)| match b {
+                0x07 => Some(i + 3),
+                0x1b if body.get(i + 1) == Some(&b'\\') => Some(i + 4),
+                _ => None,
+            })
+        }
+        _ => Some(2),
+    }
+}
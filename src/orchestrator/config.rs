@@ -0,0 +1,114 @@
+// Orchestrator Config - Tunables shared by the orchestrator and its agents
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::agent_manager::DEFAULT_MAX_CONTEXT_BYTES;
+use super::output_buffer::DEFAULT_SCROLLBACK_LINES;
+use super::workspace_tree::DEFAULT_TREE_DEPTH;
+
+/// Points at a JSON settings file, overriding the default location.
+pub const SETTINGS_PATH_ENV: &str = "CONDUCTOR_SETTINGS";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrchestratorConfig {
+    /// Upper bound on concurrently running agents; `None` is unlimited.
+    pub max_agents: Option<usize>,
+    /// Scrollback lines kept per agent unless its config overrides it.
+    pub default_scrollback_lines: usize,
+    /// Where `save_session` writes the session history.
+    pub session_save_path: Option<PathBuf>,
+    /// Output chunks arriving within this window are returned together by
+    /// `get_output`; 0 returns every chunk as it arrives.
+    pub output_coalesce_ms: u64,
+    /// Capacity of the IPC broadcast channel.
+    pub broadcast_capacity: usize,
+    /// Largest file `send_file_contents` will inject unless the agent's
+    /// config overrides it.
+    pub max_context_bytes: usize,
+    /// Prune the session history to this many most recent records as
+    /// commands are logged; `None` keeps everything.
+    pub auto_prune_history: Option<usize>,
+    /// Where session templates are saved; unset uses
+    /// `~/.config/conductor-max/templates`.
+    pub template_dir: Option<PathBuf>,
+    /// Raise a desktop notification when an agent reports an error; see
+    /// `ErrorNotifier`.
+    pub error_notifications: bool,
+    /// Directory levels `get_workspace_file_tree` lists.
+    pub workspace_tree_depth: usize,
+    /// Duration `simulate` assumes for a command while the session has no
+    /// timed responses to average.
+    pub simulation_baseline_ms: u64,
+    /// How long `spawn_agent_with_context` waits for an agent's readiness
+    /// pattern, before injecting the files and again after.
+    pub context_ready_timeout_ms: u64,
+}
+
+impl Default for OrchestratorConfig {
+    fn default() -> Self {
+        Self {
+            max_agents: None,
+            default_scrollback_lines: DEFAULT_SCROLLBACK_LINES,
+            session_save_path: None,
+            output_coalesce_ms: 0,
+            broadcast_capacity: 1000,
+            max_context_bytes: DEFAULT_MAX_CONTEXT_BYTES,
+            auto_prune_history: None,
+            template_dir: None,
+            error_notifications: false,
+            workspace_tree_depth: DEFAULT_TREE_DEPTH,
+            simulation_baseline_ms: 1000,
+            context_ready_timeout_ms: 60_000,
+        }
+    }
+}
+
+impl OrchestratorConfig {
+    /// Loads settings from `$CONDUCTOR_SETTINGS`, or
+    /// `~/.config/conductor-max/settings.json` when that exists, then applies
+    /// `CONDUCTOR_MAX_AGENTS` / `CONDUCTOR_SESSION_PATH` overrides. Missing
+    /// fields keep their defaults.
+    pub fn load() -> Result<Self> {
+        let explicit = std::env::var_os(SETTINGS_PATH_ENV).map(PathBuf::from);
+        let default_path = std::env::var_os("HOME")
+            .map(|home| Path::new(&home).join(".config/conductor-max/settings.json"));
+
+        let mut config = match (explicit, default_path) {
+            (Some(path), _) => Self::from_file(&path)?,
+            (None, Some(path)) if path.is_file() => Self::from_file(&path)?,
+            _ => Self::default(),
+        };
+
+        if let Ok(max_agents) = std::env::var("CONDUCTOR_MAX_AGENTS") {
+            config.max_agents = Some(max_agents.parse()
+                .context("CONDUCTOR_MAX_AGENTS must be a number")?);
+        }
+        if let Some(path) = std::env::var_os("CONDUCTOR_SESSION_PATH") {
+            config.session_save_path = Some(PathBuf::from(path));
+        }
+
+        Ok(config)
+    }
+
+    /// The configured `template_dir`, or its default under `$HOME`.
+    pub fn template_dir(&self) -> Result<PathBuf> {
+        if let Some(dir) = &self.template_dir {
+            return Ok(dir.clone());
+        }
+        let home = std::env::var_os("HOME")
+            .context("No template_dir configured and HOME is not set")?;
+        Ok(Path::new(&home).join(".config/conductor-max/templates"))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read settings file {}", path.display()))?;
+        let config = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid settings file {}", path.display()))?;
+        info!("⚙️ Loaded orchestrator settings from {}", path.display());
+        Ok(config)
+    }
+}
@@ -0,0 +1,62 @@
+// Conversation History - Pairing an agent's commands with their responses
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::colorize::plain_text;
+use super::output_buffer::OutputRange;
+use super::session_state::TaskRecord;
+
+/// A command from the task history with the output that followed it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationTurn {
+    pub record: TaskRecord,
+    /// Output between this command and the next, without the echoed
+    /// command line. `None` once it has been evicted from the scrollback,
+    /// for commands sent to an earlier process of the agent, or for agents
+    /// without one.
+    pub response: Option<String>,
+}
+
+impl ConversationTurn {
+    pub(crate) fn without_response(record: TaskRecord) -> Self {
+        Self { record, response: None }
+    }
+}
+
+/// Pairs one agent's `records` with their responses in `scrollback`, which
+/// holds all of its retained complete lines, of the process started at
+/// `started_at`. A response ends where the next recorded command's starts,
+/// so reordered history still splits the output where it was sent.
+pub(crate) fn pair_responses(
+    records: Vec<TaskRecord>,
+    scrollback: &OutputRange,
+    started_at: DateTime<Utc>,
+) -> Vec<ConversationTurn> {
+    let line = |record: &TaskRecord| {
+        record.output_line.filter(|_| record.process_started_at == Some(started_at))
+    };
+    let mut starts: Vec<usize> = records.iter().filter_map(line).collect();
+    starts.sort_unstable();
+
+    records.into_iter().map(|record| {
+        // Line numbers of an earlier process say nothing about this one's
+        let start = line(&record)
+            .filter(|&start| (scrollback.first_line..=scrollback.total_lines).contains(&start));
+        let response = start.map(|start| {
+            let end = starts.iter()
+                .copied()
+                .find(|&line| line > start)
+                .unwrap_or(scrollback.total_lines)
+                .min(scrollback.total_lines);
+            let mut lines = &scrollback.lines[start - scrollback.start..end - scrollback.start];
+            let command = record.command.trim();
+            let echoed = lines.first()
+                .is_some_and(|line| !command.is_empty() && plain_text(line).trim_end().ends_with(command));
+            if echoed {
+                lines = &lines[1..];
+            }
+            lines.join("\n")
+        });
+        ConversationTurn { record, response }
+    }).collect()
+}
@@ -0,0 +1,202 @@
+// Dry Run Orchestrator - Validates orchestration calls without touching a PTY
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use uuid::Uuid;
+
+use super::agent_manager::{AgentConfig, AgentManager};
+use super::checks;
+use super::config::OrchestratorConfig;
+use crate::plugin::PluginManager;
+
+/// How long `bash -n` or `shellcheck` may take before the check gives up.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunAction {
+    pub method: String,
+    pub args: Value,
+}
+
+/// Preview of a command, as `AgentOrchestrator::command_dry_run` reports it.
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunResult {
+    pub syntax_ok: bool,
+    pub warnings: Vec<String>,
+    pub estimated_tokens: usize,
+}
+
+impl DryRunResult {
+    /// Token estimate only, for agents that do not run input in a shell.
+    pub(crate) fn unchecked(command: &str) -> Self {
+        Self {
+            syntax_ok: true,
+            warnings: Vec::new(),
+            estimated_tokens: estimate_tokens(command),
+        }
+    }
+
+    /// Parses `command` with `bash -n`, without running it, and adds
+    /// `shellcheck`'s findings when it is installed.
+    pub(crate) async fn check_shell(command: &str) -> Self {
+        let mut result = Self::unchecked(command);
+        
+        match AgentManager::find_program("bash") {
+            Some(bash) => {
+                let mut cmd = Command::new(bash);
+                cmd.args(["-n", "-c", command]);
+                match run_check(cmd, None).await {
+                    Ok((success, output)) => {
+                        result.syntax_ok = success;
+                        result.warnings.extend(output);
+                    }
+                    Err(e) => result.warnings.push(format!("Syntax not checked: {}", e)),
+                }
+            }
+            None => result.warnings.push("Syntax not checked: bash not found".to_string()),
+        }
+        
+        if let Some(shellcheck) = AgentManager::find_program("shellcheck") {
+            let mut cmd = Command::new(shellcheck);
+            cmd.args(["--shell=bash", "--format=gcc", "-"]);
+            match run_check(cmd, Some(command)).await {
+                // Findings are reported as `-:line:col: level: message`
+                Ok((_, output)) => result.warnings.extend(output.into_iter()
+                    .map(|line| line.strip_prefix("-:").map(str::to_string).unwrap_or(line))),
+                Err(e) => result.warnings.push(format!("shellcheck failed: {}", e)),
+            }
+        }
+        result
+    }
+}
+
+/// Rough input token count for a cost preview, at about four characters
+/// per token.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Runs a checker, with `input` on its stdin, and returns whether it exited
+/// successfully along with the non-empty lines it printed.
+async fn run_check(mut cmd: Command, input: Option<&str>) -> Result<(bool, Vec<String>)> {
+    cmd.stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = cmd.spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+    let output = tokio::time::timeout(CHECK_TIMEOUT, child.wait_with_output()).await
+        .map_err(|_| anyhow::anyhow!("timed out after {}s", CHECK_TIMEOUT.as_secs()))??;
+    let lines = String::from_utf8_lossy(&output.stdout).lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    Ok((output.status.success(), lines))
+}
+
+/// Mirrors `AgentOrchestrator`'s agent operations, applying the same checks
+/// from `checks` but only recording what would have been done. Build one from a live
+/// orchestrator with `AgentOrchestrator::dry_run` so existence checks see
+/// its current agents.
+pub struct DryRunOrchestrator {
+    agents: Mutex<BTreeSet<String>>,
+    actions: Mutex<Vec<DryRunAction>>,
+    config: OrchestratorConfig,
+    plugins: Arc<PluginManager>,
+}
+
+impl DryRunOrchestrator {
+    pub(crate) fn new(
+        agents: BTreeSet<String>,
+        config: OrchestratorConfig,
+        plugins: Arc<PluginManager>,
+    ) -> Self {
+        Self {
+            agents: Mutex::new(agents),
+            actions: Mutex::new(Vec::new()),
+            config,
+            plugins,
+        }
+    }
+
+    /// The actions accepted so far, in call order.
+    pub fn report(&self) -> Vec<DryRunAction> {
+        self.actions.lock().unwrap().clone()
+    }
+
+    fn record(&self, method: &str, args: Value) {
+        self.actions.lock().unwrap().push(DryRunAction {
+            method: method.to_string(),
+            args,
+        });
+    }
+
+    fn require_agent(&self, agent_id: &str) -> Result<()> {
+        if !self.agents.lock().unwrap().contains(agent_id) {
+            anyhow::bail!("Agent {} not found", agent_id);
+        }
+        Ok(())
+    }
+
+    pub async fn spawn_agent(&self, config: AgentConfig) -> Result<String> {
+        let agent_id = config.agent_id.clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        {
+            let agents = self.agents.lock().unwrap();
+            checks::check_spawn(&agent_id, agents.contains(&agent_id), agents.len(), self.config.max_agents)?;
+        }
+        checks::check_config(&config, &self.plugins)?;
+        
+        self.agents.lock().unwrap().insert(agent_id.clone());
+        self.record("spawn_agent", json!({
+            "agent_id": agent_id,
+            "config": AgentConfig { agent_id: Some(agent_id.clone()), ..config.clone() },
+        }));
+        for command in &config.startup_commands {
+            self.record("send_command", json!({ "agent_id": agent_id, "command": command }));
+        }
+        Ok(agent_id)
+    }
+
+    pub async fn send_command(&self, agent_id: &str, command: &str) -> Result<()> {
+        self.require_agent(agent_id)?;
+        self.record("send_command", json!({ "agent_id": agent_id, "command": command }));
+        Ok(())
+    }
+
+    pub async fn send_raw(&self, agent_id: &str, data: &[u8]) -> Result<()> {
+        self.require_agent(agent_id)?;
+        self.record("send_raw", json!({ "agent_id": agent_id, "bytes": data.len() }));
+        Ok(())
+    }
+
+    /// Checks the file exists and is within the orchestrator's default
+    /// context limit.
+    pub async fn send_file_contents(&self, agent_id: &str, path: &Path) -> Result<()> {
+        self.require_agent(agent_id)?;
+        let size = checks::check_context_file(path, self.config.max_context_bytes).await?;
+        self.record("send_file_contents", json!({
+            "agent_id": agent_id,
+            "path": path.display().to_string(),
+            "bytes": size,
+        }));
+        Ok(())
+    }
+
+    pub async fn kill_agent(&self, agent_id: &str) -> Result<()> {
+        self.require_agent(agent_id)?;
+        self.agents.lock().unwrap().remove(agent_id);
+        self.record("kill_agent", json!({ "agent_id": agent_id }));
+        Ok(())
+    }
+}
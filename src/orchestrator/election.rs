@@ -0,0 +1,89 @@
+// Elections - Picking one answer from several agents asked the same question
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// How long each agent has to answer, as for workflow steps.
+pub const ELECTION_TIMEOUT_MS: u64 = super::workflow::DEFAULT_STEP_TIMEOUT_MS;
+
+/// How `run_election` picks the winning answer. Answers are compared
+/// exactly, after trimming; a response that is (or ends with a line that
+/// is) a JSON object with an `answer` string counts as that answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElectionStrategy {
+    /// The answer most agents gave; ties go to the one given first.
+    MajorityVote,
+    /// The first answer to arrive; the remaining agents are not waited for.
+    FirstResponse,
+    /// The answer with the highest `confidence` from responses such as
+    /// `{"answer": "...", "confidence": 0.95}`. Responses without one don't
+    /// compete; ties go to the answer given first.
+    HighestConfidence,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElectionResult {
+    pub winner: String,
+    /// How many agents gave each answer.
+    pub votes: HashMap<String, usize>,
+    /// Each answering agent's response, escape sequences removed.
+    pub raw_responses: HashMap<String, String>,
+}
+
+/// One agent's answer, as the strategies compare it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Ballot {
+    pub answer: String,
+    pub confidence: Option<f64>,
+}
+
+impl Ballot {
+    pub fn parse(response: &str) -> Self {
+        let response = response.trim();
+        let json = std::iter::once(response)
+            .chain(response.lines().rev().map(str::trim))
+            .find_map(|text| serde_json::from_str::<Value>(text).ok().filter(Value::is_object));
+        match json {
+            Some(Value::Object(object)) if object.get("answer").is_some_and(Value::is_string) => Self {
+                answer: object["answer"].as_str().unwrap_or_default().trim().to_string(),
+                confidence: object.get("confidence").and_then(Value::as_f64),
+            },
+            _ => Self { answer: response.to_string(), confidence: None },
+        }
+    }
+}
+
+/// Picks the winner among `responses`, `(agent_id, response)` in the
+/// order they arrived.
+pub(crate) fn tally(strategy: ElectionStrategy, responses: Vec<(String, String)>) -> Result<ElectionResult> {
+    let ballots: Vec<Ballot> = responses.iter().map(|(_, response)| Ballot::parse(response)).collect();
+    let mut votes: HashMap<String, usize> = HashMap::new();
+    for ballot in &ballots {
+        *votes.entry(ballot.answer.clone()).or_default() += 1;
+    }
+
+    let winner = match strategy {
+        ElectionStrategy::FirstResponse => ballots.first(),
+        // `max_by_key` keeps the last of equal maxima, so scan newest first
+        ElectionStrategy::MajorityVote => ballots.iter().rev().max_by_key(|ballot| votes[&ballot.answer]),
+        ElectionStrategy::HighestConfidence => ballots.iter().rev()
+            .filter(|ballot| ballot.confidence.is_some_and(f64::is_finite))
+            .max_by(|a, b| a.confidence.partial_cmp(&b.confidence).unwrap_or(std::cmp::Ordering::Equal)),
+    };
+    let Some(winner) = winner else {
+        match strategy {
+            ElectionStrategy::HighestConfidence if !ballots.is_empty() => {
+                bail!("No response gave an answer with a confidence")
+            }
+            _ => bail!("No agent answered"),
+        }
+    };
+
+    Ok(ElectionResult {
+        winner: winner.answer.clone(),
+        votes,
+        raw_responses: responses.into_iter().collect(),
+    })
+}
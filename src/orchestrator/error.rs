@@ -0,0 +1,113 @@
+// Agent Errors - Typed failures surfaced through the orchestrator
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AgentError {
+    /// The agent process could not be started. `command` is the redacted
+    /// command line that was attempted.
+    SpawnFailed { command: String, reason: String },
+    /// No PTY could be opened for the agent, typically because the process
+    /// ran out of file descriptors. `open_agents` is the number of agents
+    /// running at the time, where known.
+    PtyAllocationFailed { reason: String, open_agents: Option<usize> },
+    /// The agent is known to the session (e.g. from a reloaded session) but
+    /// has no live process to receive input.
+    AgentNotLive { agent_id: String },
+    /// A context file could not be injected into a freshly spawned agent,
+    /// which was killed as a result.
+    ContextInjectionFailed { agent_id: String, path: String, reason: String },
+    /// The operation needs a capability this agent lacks, e.g. resizing an
+    /// agent that has no PTY.
+    NotSupported { agent_id: String, operation: String },
+    /// A CPU affinity list was empty or named CPUs outside the `available`
+    /// ones this process may run on.
+    InvalidAffinityMask { cpus: Vec<usize>, available: Vec<usize> },
+    /// A path given relative to the agent's workspace resolves outside it.
+    WorkspaceAccessDenied { agent_id: String, path: String },
+    /// No live or recorded agent has this id.
+    AgentNotFound { agent_id: String },
+    /// A command would fail before it runs, e.g. a shell syntax error.
+    InvalidCommand { agent_id: String, reason: String },
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentError::SpawnFailed { command, reason } => {
+                write!(f, "Failed to spawn `{}`: {}", command, reason)
+            }
+            AgentError::PtyAllocationFailed { reason, open_agents } => {
+                write!(f, "Failed to allocate a PTY: {}", reason)?;
+                if let Some(open_agents) = open_agents {
+                    write!(f, " ({} agents open)", open_agents)?;
+                }
+                write!(f, "; lower max_agents or raise the open file limit (ulimit -n)")
+            }
+            AgentError::AgentNotLive { agent_id } => {
+                write!(f, "Agent {} is history-only and has no live process", agent_id)
+            }
+            AgentError::ContextInjectionFailed { agent_id, path, reason } => {
+                write!(f, "Failed to inject {} into agent {}: {}", path, agent_id, reason)
+            }
+            AgentError::NotSupported { agent_id, operation } => {
+                write!(f, "Agent {} does not support {}", agent_id, operation)
+            }
+            AgentError::InvalidAffinityMask { cpus, available } => {
+                write!(f, "Invalid CPU affinity {:?}: CPUs {:?} are available", cpus, available)
+            }
+            AgentError::WorkspaceAccessDenied { agent_id, path } => {
+                write!(f, "{} is outside the workspace of agent {}", path, agent_id)
+            }
+            AgentError::AgentNotFound { agent_id } => write!(f, "Agent {} not found", agent_id),
+            AgentError::InvalidCommand { agent_id, reason } => {
+                write!(f, "Invalid command for agent {}: {}", agent_id, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AgentError {}
+
+#[derive(Debug)]
+pub enum SessionError {
+    /// A saved session was written with a schema this build cannot read.
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+    /// No task history record has this id (for the agent, where one is
+    /// given).
+    RecordNotFound { record_id: String },
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::UnsupportedSchemaVersion { found, supported } => write!(
+                f,
+                "Unsupported session schema version {} (this build supports up to {})",
+                found, supported
+            ),
+            SessionError::RecordNotFound { record_id } => {
+                write!(f, "Task record {} not found", record_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+#[derive(Debug)]
+pub enum OperationError {
+    /// A long-running call was stopped through `cancel_operation`.
+    Cancelled { operation_id: String },
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationError::Cancelled { operation_id } => {
+                write!(f, "Operation {} was cancelled", operation_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OperationError {}
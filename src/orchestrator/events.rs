@@ -0,0 +1,95 @@
+// Agent Events - Typed agent output, status and lifecycle events
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::ipc_bridge::{IpcMessage, MessageType};
+
+/// What happened to an agent, published over the IPC bridge as the
+/// payload of an `IpcMessage`. Serialized with a `type` tag, e.g.
+/// `{"type": "exited", "agent_id": "...", "code": 0}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// A chunk of output as streamed to the terminal, base64-encoded
+    /// when serialized.
+    Output {
+        agent_id: String,
+        #[serde(with = "base64_bytes")]
+        bytes: Vec<u8>,
+    },
+    StatusChanged { agent_id: String, status: AgentStatusDto },
+    /// The process ended, by itself or through `kill_agent`.
+    Exited { agent_id: String, code: u32 },
+    Error { agent_id: String, message: String },
+}
+
+/// A native agent's status, with the fields `get_agent_status` reports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentStatusDto {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub agent_type: String,
+    pub running: bool,
+    pub start_time: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub commands_sent: usize,
+    pub workspace: Option<String>,
+    pub managed: bool,
+    pub reattached: bool,
+    pub headless: bool,
+    pub rows: u16,
+    pub cols: u16,
+    pub has_output: bool,
+    pub bytes_received: u64,
+    pub output_rate_bps: u64,
+    pub response_timeout_count: u64,
+    /// Latest output line matching one of the agent's error patterns.
+    pub last_error: Option<String>,
+    pub error_count: u64,
+    pub signals_sent: u64,
+}
+
+impl AgentEvent {
+    pub fn agent_id(&self) -> &str {
+        match self {
+            AgentEvent::Output { agent_id, .. }
+            | AgentEvent::StatusChanged { agent_id, .. }
+            | AgentEvent::Exited { agent_id, .. }
+            | AgentEvent::Error { agent_id, .. } => agent_id,
+        }
+    }
+
+    pub fn message_type(&self) -> MessageType {
+        match self {
+            AgentEvent::Output { .. } => MessageType::Output,
+            AgentEvent::StatusChanged { .. } | AgentEvent::Exited { .. } => MessageType::Status,
+            AgentEvent::Error { .. } => MessageType::Error,
+        }
+    }
+
+    pub fn into_message(self) -> IpcMessage {
+        IpcMessage {
+            agent_id: self.agent_id().to_string(),
+            message_type: self.message_type(),
+            payload: serde_json::to_value(&self).unwrap_or_default(),
+            timestamp: Utc::now(),
+            batch_id: None,
+            correlation_id: None,
+        }
+    }
+}
+
+/// Bytes as a base64 string rather than an array of numbers.
+mod base64_bytes {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,69 @@
+// Output Framing - JSON objects split out of line-delimited agent output
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Longest line held while waiting for its `\n` before it is passed on as
+/// text.
+const MAX_JSONL_LINE_BYTES: usize = 1024 * 1024;
+
+/// How an agent's output is divided into messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFraming {
+    /// A plain byte stream.
+    #[default]
+    Raw,
+    /// One JSON object per line, as CLIs print with `--json` or `--jsonl`.
+    /// Objects go to `AgentProcess::json_stream`, anything else is text.
+    Jsonl,
+}
+
+/// JSONL state of one output stream. A line that starts with `{` is held
+/// until its `\n` and then parsed; other lines are text as soon as they
+/// arrive, so prompts are not held back.
+#[derive(Default)]
+pub(crate) struct JsonlFramer {
+    line: Vec<u8>,
+    /// The current line is text and has been passed on so far.
+    passing: bool,
+}
+
+impl JsonlFramer {
+    /// Splits `data` into text to forward as before and the objects parsed
+    /// from complete lines.
+    pub fn split(&mut self, data: &[u8]) -> (Vec<u8>, Vec<Value>) {
+        let mut text = Vec::new();
+        let mut objects = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let (segment, terminated) = match rest.iter().position(|&b| b == b'\n') {
+                Some(i) => (&rest[..=i], true),
+                None => (rest, false),
+            };
+            rest = &rest[segment.len()..];
+
+            if self.passing {
+                text.extend_from_slice(segment);
+                self.passing = !terminated;
+                continue;
+            }
+            self.line.extend_from_slice(segment);
+            if !terminated {
+                let first = self.line.iter().find(|b| !b.is_ascii_whitespace());
+                if first.is_some_and(|&b| b != b'{') || self.line.len() >= MAX_JSONL_LINE_BYTES {
+                    text.append(&mut self.line);
+                    self.passing = true;
+                }
+                continue;
+            }
+            match serde_json::from_slice(self.line.trim_ascii()) {
+                Ok(object @ Value::Object(_)) => {
+                    objects.push(object);
+                    self.line.clear();
+                }
+                _ => text.append(&mut self.line),
+            }
+        }
+        (text, objects)
+    }
+}
@@ -0,0 +1,51 @@
+// Interrupts - Signalling an agent's process group instead of writing ^C
+use anyhow::Result;
+use std::io;
+
+use super::error::AgentError;
+
+/// Interrupts process group `group`, the agent's foreground job or the
+/// agent itself. Platforms without a way to do so report
+/// `AgentError::NotSupported`.
+pub(crate) fn interrupt_group(agent_id: &str, group: u32) -> Result<()> {
+    match send_interrupt(group) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => Err(AgentError::NotSupported {
+            agent_id: agent_id.to_string(),
+            operation: "interrupting".to_string(),
+        }.into()),
+        Err(e) => Err(anyhow::anyhow!("Failed to interrupt agent {}: {}", agent_id, e)),
+    }
+}
+
+#[cfg(unix)]
+fn send_interrupt(group: u32) -> io::Result<()> {
+    use nix::sys::signal::{killpg, Signal};
+    use nix::unistd::{getpgrp, Pid};
+
+    let group = Pid::from_raw(group as i32);
+    // An agent left in our own group would take the orchestrator with it
+    if group == getpgrp() {
+        return Err(io::Error::other("agent shares the orchestrator's process group"));
+    }
+    killpg(group, Signal::SIGINT).map_err(io::Error::from)
+}
+
+/// Consoles have no SIGINT; a process group gets Ctrl+Break instead. Only
+/// groups attached to our own console receive it, which rules out agents
+/// on a pseudoconsole.
+#[cfg(windows)]
+fn send_interrupt(group: u32) -> io::Result<()> {
+    use windows_sys::Win32::System::Console::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+
+    // SAFETY: takes no pointers
+    if unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, group) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn send_interrupt(_group: u32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "signals are not available on this platform"))
+}
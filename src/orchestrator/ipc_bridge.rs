@@ -1,19 +1,33 @@
 // IPC Bridge for frontend communication
 use anyhow::Result;
+use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::future::Future;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, oneshot};
 use tracing::debug;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::events::AgentEvent;
+use super::observer::OutputObservers;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct IpcMessage {
     pub agent_id: String,
     pub message_type: MessageType,
     pub payload: serde_json::Value,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Shared by all messages sent together through `send_batch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Set on a request sent through `send_request`, and on its reply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MessageType {
     Output,
     Input,
@@ -22,45 +36,237 @@ pub enum MessageType {
     SystemEvent,
 }
 
+impl MessageType {
+    const ALL: [MessageType; 5] = [
+        MessageType::Output,
+        MessageType::Input,
+        MessageType::Status,
+        MessageType::Error,
+        MessageType::SystemEvent,
+    ];
+
+    fn index(&self) -> usize {
+        match self {
+            MessageType::Output => 0,
+            MessageType::Input => 1,
+            MessageType::Status => 2,
+            MessageType::Error => 3,
+            MessageType::SystemEvent => 4,
+        }
+    }
+}
+
+/// Traffic through an `IpcBridge` since it was created, for telling a
+/// frontend that misses messages because it lags from a backend that never
+/// sent them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpcStats {
+    /// Messages sent, batched ones included.
+    pub messages_sent: u64,
+    /// Current `subscribe` receivers.
+    pub subscribers: usize,
+    /// Messages subscribers skipped because they fell behind, as reported
+    /// through `record_lag`.
+    pub lagged_messages: u64,
+    /// Messages sent per `MessageType`, e.g. `"Output"`.
+    pub by_type: BTreeMap<String, u64>,
+}
+
 pub struct IpcBridge {
     sender: broadcast::Sender<IpcMessage>,
     receiver: Arc<tokio::sync::Mutex<broadcast::Receiver<IpcMessage>>>,
+    /// Held for every send, and for the whole of a batch, so nothing is
+    /// interleaved with a batch's messages.
+    send_lock: Mutex<()>,
+    batches_sent: AtomicUsize,
+    batched_messages: AtomicUsize,
+    messages_sent: AtomicU64,
+    /// Per `MessageType::index`.
+    messages_by_type: [AtomicU64; MessageType::ALL.len()],
+    lagged_messages: AtomicU64,
+    /// Requests waiting for a reply, by `correlation_id`.
+    pending: Arc<DashMap<String, PendingRequest>>,
+    observers: OutputObservers,
+}
+
+struct PendingRequest {
+    agent_id: String,
+    reply: oneshot::Sender<IpcMessage>,
+}
+
+impl Default for IpcBridge {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl IpcBridge {
     pub fn new() -> Self {
-        let (sender, receiver) = broadcast::channel(1000);
+        Self::with_capacity(1000)
+    }
+    
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, receiver) = broadcast::channel(capacity.max(1));
         Self {
             sender,
             receiver: Arc::new(tokio::sync::Mutex::new(receiver)),
+            send_lock: Mutex::new(()),
+            batches_sent: AtomicUsize::new(0),
+            batched_messages: AtomicUsize::new(0),
+            messages_sent: AtomicU64::new(0),
+            messages_by_type: Default::default(),
+            lagged_messages: AtomicU64::new(0),
+            pending: Arc::new(DashMap::new()),
+            observers: OutputObservers::default(),
         }
     }
     
+    /// Observers called with every message sent, before subscribers get it.
+    pub fn observers(&self) -> &OutputObservers {
+        &self.observers
+    }
+    
     pub fn send_message(&self, message: IpcMessage) -> Result<()> {
         debug!("Sending IPC message: {:?}", message.message_type);
+        // Outside the send lock, so an observer may send messages itself
+        self.observers.event(&message);
+        self.count(&message);
+        let _guard = self.send_lock.lock().unwrap_or_else(|e| e.into_inner());
         self.sender.send(message)?;
         Ok(())
     }
     
+    fn count(&self, message: &IpcMessage) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.messages_by_type[message.message_type.index()].fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// Sends related messages back to back, tagged with one `batch_id`, so
+    /// no other message lands between them. Returns how many were sent;
+    /// with no subscribers that is 0.
+    pub fn send_batch(&self, messages: Vec<IpcMessage>) -> Result<usize> {
+        let batch_id = uuid::Uuid::new_v4().to_string();
+        debug!("Sending IPC batch {} of {} messages", batch_id, messages.len());
+        
+        let messages: Vec<IpcMessage> = messages.into_iter()
+            .map(|message| IpcMessage { batch_id: Some(batch_id.clone()), ..message })
+            .collect();
+        for message in &messages {
+            self.observers.event(message);
+            self.count(message);
+        }
+        
+        let _guard = self.send_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sent = 0;
+        for message in messages {
+            if self.sender.send(message).is_ok() {
+                sent += 1;
+            }
+        }
+        self.batches_sent.fetch_add(1, Ordering::Relaxed);
+        self.batched_messages.fetch_add(sent, Ordering::Relaxed);
+        Ok(sent)
+    }
+    
+    /// Sends `message` as a request and resolves to the reply `reply` makes
+    /// to it, or fails after `timeout`. A `correlation_id` is assigned when
+    /// the message has none. The request is registered and sent before this
+    /// returns, so a reply made before the future is first polled still
+    /// arrives. Having no subscribers does not fail the request; the reply
+    /// goes to the caller either way.
+    pub fn send_request(
+        &self,
+        mut message: IpcMessage,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<IpcMessage>> + Send + 'static {
+        let correlation_id = message.correlation_id
+            .get_or_insert_with(|| uuid::Uuid::new_v4().to_string())
+            .clone();
+        let (reply, replied) = oneshot::channel();
+        self.pending.insert(correlation_id.clone(), PendingRequest {
+            agent_id: message.agent_id.clone(),
+            reply,
+        });
+        if let Err(e) = self.send_message(message) {
+            debug!("No IPC subscribers for request {}: {}", correlation_id, e);
+        }
+        
+        let pending = self.pending.clone();
+        async move {
+            let result = tokio::time::timeout(timeout, replied).await;
+            pending.remove(&correlation_id);
+            match result {
+                Ok(Ok(reply)) => Ok(reply),
+                Ok(Err(_)) => anyhow::bail!("Request {} was dropped without a reply", correlation_id),
+                Err(_) => anyhow::bail!("No reply to request {} within {:?}", correlation_id, timeout),
+            }
+        }
+    }
+    
+    /// Replies to the request with `correlation_id`: the reply, a
+    /// `SystemEvent` carrying `payload`, goes to the waiting caller and to
+    /// subscribers. Fails when no request is waiting, e.g. once it timed out.
+    pub fn reply(&self, correlation_id: &str, payload: serde_json::Value) -> Result<()> {
+        let (_, request) = self.pending.remove(correlation_id)
+            .ok_or_else(|| anyhow::anyhow!("No request {} is waiting for a reply", correlation_id))?;
+        let message = IpcMessage {
+            agent_id: request.agent_id,
+            message_type: MessageType::SystemEvent,
+            payload,
+            timestamp: chrono::Utc::now(),
+            batch_id: None,
+            correlation_id: Some(correlation_id.to_string()),
+        };
+        if let Err(e) = self.send_message(message.clone()) {
+            debug!("No IPC subscribers for reply {}: {}", correlation_id, e);
+        }
+        // The caller may have stopped waiting in the meantime
+        let _ = request.reply.send(message);
+        Ok(())
+    }
+    
+    pub fn batch_stats(&self) -> serde_json::Value {
+        serde_json::json!({
+            "batches_sent": self.batches_sent.load(Ordering::Relaxed),
+            "batched_messages": self.batched_messages.load(Ordering::Relaxed),
+        })
+    }
+    
+    pub fn ipc_stats(&self) -> IpcStats {
+        let by_type = MessageType::ALL.iter()
+            .map(|message_type| {
+                let sent = self.messages_by_type[message_type.index()].load(Ordering::Relaxed);
+                (format!("{:?}", message_type), sent)
+            })
+            .collect();
+        IpcStats {
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            // Less the bridge's own receiver
+            subscribers: self.sender.receiver_count().saturating_sub(1),
+            lagged_messages: self.lagged_messages.load(Ordering::Relaxed),
+            by_type,
+        }
+    }
+    
+    /// Counts messages a subscriber skipped, from its `RecvError::Lagged`.
+    pub fn record_lag(&self, skipped: u64) {
+        self.lagged_messages.fetch_add(skipped, Ordering::Relaxed);
+    }
+    
     pub async fn subscribe(&self) -> broadcast::Receiver<IpcMessage> {
         self.sender.subscribe()
     }
     
+    /// Publishes a typed agent event; see `AgentEvent::into_message`.
+    pub fn send_event(&self, event: AgentEvent) -> Result<()> {
+        self.send_message(event.into_message())
+    }
+    
     pub fn broadcast_output(&self, agent_id: String, output: String) -> Result<()> {
-        self.send_message(IpcMessage {
-            agent_id,
-            message_type: MessageType::Output,
-            payload: serde_json::json!({ "text": output }),
-            timestamp: chrono::Utc::now(),
-        })
+        self.send_event(AgentEvent::Output { agent_id, bytes: output.into_bytes() })
     }
     
     pub fn broadcast_error(&self, agent_id: String, error: String) -> Result<()> {
-        self.send_message(IpcMessage {
-            agent_id,
-            message_type: MessageType::Error,
-            payload: serde_json::json!({ "error": error }),
-            timestamp: chrono::Utc::now(),
-        })
+        self.send_event(AgentEvent::Error { agent_id, message: error })
     }
 }
\ No newline at end of file
@@ -1,117 +1,2585 @@
 // 🔱 Agent Orchestrator Module
+mod ab_test;
+mod affinity;
 mod agent_manager;
+mod binary_output;
+mod broadcast;
+mod checks;
+mod colorize;
+mod config;
+mod conversation;
+mod dry_run;
+mod election;
+mod error;
+mod events;
+mod framing;
+mod interrupt;
 mod ipc_bridge;
+mod observer;
+mod operation;
+mod output_buffer;
+mod output_filter;
+mod output_meter;
+mod patterns;
+mod pipe;
+mod resources;
+mod sandbox;
+mod scheduler;
 mod session_state;
+mod session_template;
+mod shutdown;
+#[cfg(feature = "simulation")]
+mod simulation;
+mod stats;
+mod timeline;
+mod transcript;
+mod watermark;
+mod workflow;
+mod workspace_path;
+mod workspace_tree;
+mod workspace_watcher;
 
-pub use agent_manager::{AgentManager, AgentConfig, AgentType, AgentProcess};
-pub use ipc_bridge::IpcBridge;
-pub use session_state::SessionState;
+pub use ab_test::{AbTestResult, AbTestRound, AbVerdict};
+pub use agent_manager::{AgentManager, AgentConfig, AgentType, AgentProcess, ExitStatus, TtyType};
+pub use broadcast::BroadcastReport;
+pub use colorize::{ColorScheme, ColorizedChunk, Rgb};
+pub use config::OrchestratorConfig;
+pub use conversation::ConversationTurn;
+pub use dry_run::{DryRunAction, DryRunOrchestrator, DryRunResult};
+pub use election::{ElectionResult, ElectionStrategy};
+pub use error::{AgentError, OperationError};
+pub use events::{AgentEvent, AgentStatusDto};
+pub use framing::OutputFraming;
+pub use ipc_bridge::{IpcBridge, IpcMessage, IpcStats, MessageType};
+pub use observer::{ErrorNotifier, OutputObserver, OutputObservers};
+pub use operation::Operation;
+pub use output_buffer::{TerminalSnapshot, REDACTED_LINE};
+pub use output_filter::{FilterFactory, LinePredicate};
+pub use patterns::{AgentPatterns, CompiledPatterns};
+pub use pipe::PipeHandle;
+pub use resources::{ProcessResources, ResourceSnapshot};
+pub use sandbox::SandboxConfig;
+pub use scheduler::{Schedule, ScheduledCommand};
+pub use session_state::{AgentSession, SessionState, TaskRecord};
+pub use session_template::{SessionTemplate, TemplateAgent};
+pub use shutdown::ShutdownStep;
+#[cfg(feature = "simulation")]
+pub use simulation::{SimulationResult, SimulationStep};
+pub use stats::OrchestratorStats;
+pub use timeline::TimelineEvent;
+pub use transcript::TranscriptFormat;
+pub use watermark::WatermarkHandle;
+pub use workflow::{StepResult, StepStatus, WorkflowRun, WorkflowSpec, WorkflowStatus, WorkflowStep};
+pub use workspace_tree::{FileEntry, WorkspaceTree, MAX_TREE_ENTRIES};
 
-use anyhow::Result;
-use dashmap::DashMap;
+use anyhow::{Context, Result};
+use dashmap::{DashMap, DashSet};
+use dashmap::mapref::entry::Entry;
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use regex::Regex;
 use serde_json::Value;
-use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 use tracing::{info, error, debug};
 use uuid::Uuid;
 
+use ab_test::AB_TEST_TIMEOUT_MS;
+use agent_manager::{DEFAULT_PROMPT_SAMPLE_LINES, DEFAULT_PTY_COLS, DEFAULT_PTY_ROWS};
+use colorize::plain_text;
+use election::ELECTION_TIMEOUT_MS;
+use patterns::LineSplitter;
+use pipe::{PipeEntry, MAX_PIPED_LINES_PER_WINDOW, MAX_PIPED_LINE_BYTES, PIPE_RATE_WINDOW};
+use resources::ResourceSampler;
+use transcript::Transcript;
+use scheduler::{ScheduleEntry, MIN_SCHEDULE_INTERVAL};
+use stats::StatsHistory;
+use workflow::DEFAULT_STEP_TIMEOUT_MS;
+use workspace_watcher::WorkspaceWatcher;
+use crate::plugin::{AgentProcessTrait, PluginManager};
+
+/// Upper bounds for `repeat_input`, so a bad call can't flood an agent.
+const MAX_REPEAT_COUNT: usize = 1000;
+const MAX_REPEAT_DELAY_MS: u64 = 10_000;
+
+/// Collection window for `send_command_ack`, by default and at most.
+const DEFAULT_ACK_MS: u64 = 200;
+const MAX_ACK_MS: u64 = 5_000;
+
+/// How often `heartbeat_task` advances the heartbeat counter.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a watermark from `create_watermark` waits to be awaited
+/// before it is discarded.
+const WATERMARK_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// An agent window's label, tag and when it was opened.
+type WindowEntry = (String, Option<String>, Instant);
+
 pub struct AgentOrchestrator {
-    pub agents: Arc<DashMap<String, Arc<AgentProcess>>>,
+    agents: Arc<DashMap<String, Arc<AgentProcess>>>,
+    plugin_agents: Arc<DashMap<String, Arc<dyn AgentProcessTrait>>>,
+    groups: Arc<DashMap<String, BTreeSet<String>>>,
+    aliases: Arc<DashMap<String, String>>,
+    /// Label of each agent's open window, by agent id, with its tag and
+    /// when it was opened; see `assign_window_label`.
+    window_labels: Arc<DashMap<String, WindowEntry>>,
+    /// Messages routed between agents, keyed by `(from_id, to_id)`.
+    routing_counts: Arc<DashMap<(String, String), AtomicUsize>>,
+    workspace_watchers: Arc<DashMap<String, WorkspaceWatcher>>,
+    schedules: Arc<DashMap<String, ScheduleEntry>>,
+    /// Commands waiting for `send_next_queued`, per agent.
+    command_queues: Arc<DashMap<String, VecDeque<String>>>,
+    pipes: Arc<DashMap<String, PipeEntry>>,
+    /// Held by `pipe` from its cycle check until the new pipe is in
+    /// `pipes`, so two pipes can't close a loop between them.
+    pipe_lock: std::sync::Mutex<()>,
+    /// Watermarks waiting for `await_watermark`, by id, with their agent
+    /// and when they were created.
+    watermarks: Arc<DashMap<String, (String, WatermarkHandle, Instant)>>,
+    /// Agents `reset_agent` is replacing with a new process.
+    resetting: Arc<DashSet<String>>,
+    /// Cancellation tokens of running long operations, by operation id.
+    operations: Arc<DashMap<String, CancellationToken>>,
+    resource_sampler: ResourceSampler,
+    /// Spawns, kills, errors and output volume, for `stats_since`.
+    stats_history: Arc<tokio::sync::Mutex<StatsHistory>>,
     session: Arc<RwLock<SessionState>>,
+    /// Native agents' output bytes not yet added to the session's
+    /// `total_bytes_received`, so the forwarders needn't lock the session.
+    bytes_received: Arc<AtomicU64>,
     ipc_bridge: Arc<IpcBridge>,
+    config: OrchestratorConfig,
+    started_at: Instant,
+    heartbeat: Arc<AtomicU64>,
+    // Declared last so plugin agents are dropped before their libraries
+    plugins: Arc<PluginManager>,
+}
+
+impl Default for AgentOrchestrator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AgentOrchestrator {
     pub fn new() -> Self {
+        Self::with_config(OrchestratorConfig::default())
+    }
+
+    pub fn with_config(config: OrchestratorConfig) -> Self {
         Self {
             agents: Arc::new(DashMap::new()),
+            plugin_agents: Arc::new(DashMap::new()),
+            groups: Arc::new(DashMap::new()),
+            aliases: Arc::new(DashMap::new()),
+            window_labels: Arc::new(DashMap::new()),
+            routing_counts: Arc::new(DashMap::new()),
+            workspace_watchers: Arc::new(DashMap::new()),
+            schedules: Arc::new(DashMap::new()),
+            command_queues: Arc::new(DashMap::new()),
+            pipes: Arc::new(DashMap::new()),
+            pipe_lock: std::sync::Mutex::new(()),
+            watermarks: Arc::new(DashMap::new()),
+            resetting: Arc::new(DashSet::new()),
+            operations: Arc::new(DashMap::new()),
+            resource_sampler: ResourceSampler::default(),
+            stats_history: Arc::new(tokio::sync::Mutex::new(StatsHistory::default())),
             session: Arc::new(RwLock::new(SessionState::new())),
-            ipc_bridge: Arc::new(IpcBridge::new()),
+            bytes_received: Arc::new(AtomicU64::new(0)),
+            ipc_bridge: Arc::new(IpcBridge::with_capacity(config.broadcast_capacity)),
+            plugins: Arc::new(PluginManager::from_env()),
+            config,
+            started_at: Instant::now(),
+            heartbeat: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Looks up a running agent, native or plugin-provided.
+    pub fn get_process(&self, agent_id: &str) -> Option<Arc<dyn AgentProcessTrait>> {
+        if let Some(agent) = self.agents.get(agent_id) {
+            let process: Arc<dyn AgentProcessTrait> = agent.value().clone();
+            return Some(process);
+        }
+        self.plugin_agents.get(agent_id).map(|agent| agent.value().clone())
+    }
+
+    /// Looks up an agent that can accept input, distinguishing agents that
+    /// only exist in session history from unknown ids.
+    pub async fn get_live_process(&self, agent_id: &str) -> Result<Arc<dyn AgentProcessTrait>> {
+        if let Some(agent) = self.get_process(agent_id) {
+            return Ok(agent);
+        }
+        if self.session.read().await.is_history_only(agent_id) {
+            return Err(AgentError::AgentNotLive { agent_id: agent_id.to_string() }.into());
+        }
+        Err(anyhow::anyhow!("Agent {} not found", agent_id))
+    }
+
+    /// Like `get_live_process`, for operations only native PTY agents support.
+    async fn get_live_agent(&self, agent_id: &str) -> Result<Arc<AgentProcess>> {
+        if let Some(agent) = self.agents.get(agent_id) {
+            return Ok(agent.value().clone());
+        }
+        if self.plugin_agents.contains_key(agent_id) {
+            anyhow::bail!("Agent {} is a plugin agent and does not support this operation", agent_id);
+        }
+        if self.session.read().await.is_history_only(agent_id) {
+            return Err(AgentError::AgentNotLive { agent_id: agent_id.to_string() }.into());
+        }
+        Err(anyhow::anyhow!("Agent {} not found", agent_id))
+    }
+
+    /// A dry-run view of this orchestrator: same config, plugins and running
+    /// agents, but nothing it does reaches a process.
+    pub fn dry_run(&self) -> DryRunOrchestrator {
+        let agents = self.agents.iter().map(|entry| entry.key().clone())
+            .chain(self.plugin_agents.iter().map(|entry| entry.key().clone()))
+            .collect();
+        DryRunOrchestrator::new(agents, self.config.clone(), self.plugins.clone())
+    }
+
+    /// Previews `command` for an agent without sending it: shell agents get
+    /// a syntax check of the command as it would be written, other agents
+    /// only the token estimate. Leaves the session untouched.
+    pub async fn command_dry_run(&self, agent_id: &str, command: String) -> Result<DryRunResult> {
+        let Some(agent) = self.agents.get(agent_id).map(|entry| entry.value().clone()) else {
+            self.get_live_process(agent_id).await?;
+            return Ok(DryRunResult::unchecked(&command));
+        };
+        let command = agent.config().await.wrap_command(&command);
+        if !agent.uses_posix_shell() {
+            return Ok(DryRunResult::unchecked(&command));
+        }
+        Ok(DryRunResult::check_shell(&command).await)
+    }
+
+    /// Checks a sequence of `(agent_id, command)` pairs without sending
+    /// anything, so a workflow can be validated before it runs. Each command
+    /// gets the preview `command_dry_run` gives; agents only known from the
+    /// session are checked against their recorded config. Durations are
+    /// the agent's average response time in the session, falling back to
+    /// all agents' and then to `simulation_baseline_ms`.
+    #[cfg(feature = "simulation")]
+    pub async fn simulate(&self, commands: Vec<(String, String)>) -> Result<SimulationResult> {
+        let mut steps = Vec::new();
+        let mut errors = Vec::new();
+        let count = commands.len();
+        for (index, (agent_id, command)) in commands.into_iter().enumerate() {
+            let agent_id = self.resolve_agent_id(&agent_id);
+            let preview = if let Some(agent) = self.agents.get(&agent_id).map(|e| e.value().clone()) {
+                let command = agent.config().await.wrap_command(&command);
+                if agent.uses_posix_shell() {
+                    DryRunResult::check_shell(&command).await
+                } else {
+                    DryRunResult::unchecked(&command)
+                }
+            } else if self.plugin_agents.contains_key(&agent_id) {
+                DryRunResult::unchecked(&command)
+            } else {
+                let recorded = self.session.read().await.agents.get(&agent_id).map(|agent| agent.config.clone());
+                match recorded {
+                    Some(Some(config)) if matches!(config.agent_type, AgentType::Bash) => {
+                        DryRunResult::check_shell(&config.wrap_command(&command)).await
+                    }
+                    Some(_) => DryRunResult::unchecked(&command),
+                    None => {
+                        errors.push((index, AgentError::AgentNotFound { agent_id }));
+                        continue;
+                    }
+                }
+            };
+            
+            if !preview.syntax_ok {
+                let reason = match preview.warnings.is_empty() {
+                    true => "syntax error".to_string(),
+                    false => preview.warnings.join("; "),
+                };
+                errors.push((index, AgentError::InvalidCommand { agent_id: agent_id.clone(), reason }));
+            }
+            let estimated_duration_ms = self.session.read().await
+                .average_duration_ms(&agent_id)
+                .unwrap_or(self.config.simulation_baseline_ms);
+            steps.push(SimulationStep { index, agent_id, command, preview, estimated_duration_ms });
+        }
+        
+        let total_duration_estimate_ms = steps.iter().map(|step| step.estimated_duration_ms).sum();
+        info!("🧪 Simulated {} commands: {} problem(s)", count, errors.len());
+        Ok(SimulationResult { steps, errors, total_duration_estimate_ms })
+    }
+
+    /// A future that advances the heartbeat counter until the orchestrator
+    /// is dropped. Spawn it on the runtime that serves commands; if the
+    /// counter reported by `health` stops moving, that runtime is wedged.
+    pub fn heartbeat_task(self: &Arc<Self>) -> impl Future<Output = ()> + Send + 'static {
+        let orchestrator = Arc::downgrade(self);
+        async move {
+            let mut ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let Some(orchestrator) = orchestrator.upgrade() else {
+                    return;
+                };
+                orchestrator.heartbeat.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Receives every message published on the IPC bridge from now on,
+    /// including `AgentEvent`s.
+    pub async fn subscribe_ipc(&self) -> tokio::sync::broadcast::Receiver<IpcMessage> {
+        self.ipc_bridge.subscribe().await
+    }
+
+    /// Registers compiled-in logic to run on every native agent's output
+    /// lines and every IPC message; see `OutputObserver`.
+    pub fn add_output_observer(&self, observer: Arc<dyn OutputObserver>) {
+        self.ipc_bridge.observers().add(observer);
+    }
+
+    /// Liveness report for the orchestrator itself, independent of agents.
+    pub fn health(&self) -> Value {
+        serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "uptime_secs": self.started_at.elapsed().as_secs(),
+            "agent_count": self.agents.len() + self.plugin_agents.len(),
+            "scheduled_commands": self.schedules.len(),
+            "queued_commands": self.command_queues.iter().map(|queue| queue.len()).sum::<usize>(),
+            "heartbeat": self.heartbeat.load(Ordering::Relaxed),
+        })
+    }
+
+    pub fn list_plugins(&self) -> Vec<Value> {
+        self.plugins.list()
+    }
+
+    pub async fn spawn_agent(&self, config: AgentConfig) -> Result<String> {
+        self.spawn_agent_replacing(config, None).await
+    }
+
+    /// `spawn_agent`, where a native agent may take the id of `replacing`,
+    /// which stays registered until the new process is swapped in for it.
+    /// Fails if another agent holds the id by then, or none does.
+    async fn spawn_agent_replacing(&self, config: AgentConfig, replacing: Option<&Arc<AgentProcess>>) -> Result<String> {
+        let agent_id = config.agent_id.clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let running = self.agents.len() + self.plugin_agents.len();
+        checks::check_spawn(
+            &agent_id,
+            replacing.is_none() && self.get_process(&agent_id).is_some(),
+            running - usize::from(replacing.is_some()),
+            self.config.max_agents,
+        )?;
+        let config = AgentConfig {
+            agent_id: Some(agent_id.clone()),
+            scrollback_lines: config.scrollback_lines
+                .or(Some(self.config.default_scrollback_lines)),
+            output_coalesce_ms: config.output_coalesce_ms
+                .or(Some(self.config.output_coalesce_ms)),
+            max_context_bytes: config.max_context_bytes
+                .or(Some(self.config.max_context_bytes)),
+            ..config
+        };
+        let startup_commands = config.startup_commands.clone();
+        let nudge_prompt = config.nudge_prompt;
+        let watch_workspace = config.watch_workspace;
+        let mut watched_workspace = config.workspace_path.clone();
+        
+        info!("Spawning {} agent with ID: {}", config.agent_type, agent_id);
+        
+        if let AgentType::Plugin(name) = &config.agent_type {
+            let agent: Arc<dyn AgentProcessTrait> = Arc::from(self.plugins.spawn(name, &config)?);
+            // Inserted under the entry lock, so of two spawns with the same id
+            // only one lands
+            let inserted = match self.plugin_agents.entry(agent_id.clone()) {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    entry.insert(agent.clone());
+                    true
+                }
+            };
+            if !inserted {
+                agent.kill().await?;
+                anyhow::bail!("Agent {} already exists", agent_id);
+            }
+            
+            let mut session = self.session.write().await;
+            session.register_agent(agent_id.clone(), config.agent_type.to_string());
+            session.set_agent_config(&agent_id, &config);
+            session.set_history_limit(&agent_id, config.max_session_history_per_agent)?;
+            drop(session);
+            self.stats_history.lock().await.record_spawn(&agent_id);
+            
+            info!("✅ Plugin agent {} spawned successfully", agent_id);
+        } else {
+            let history_limit = config.max_session_history_per_agent;
+            let spawned_config = config.clone();
+            let observers = self.ipc_bridge.observers().clone();
+            let agent = AgentManager::spawn_observed(config, observers).await.map_err(|e| match e.downcast_ref() {
+                Some(AgentError::PtyAllocationFailed { reason, .. }) => AgentError::PtyAllocationFailed {
+                    reason: reason.clone(),
+                    open_agents: Some(self.agents.len() + self.plugin_agents.len()),
+                }.into(),
+                _ => e,
+            })?;
+            
+            // Store agent process, under the entry lock so of two spawns
+            // with the same id only one lands
+            let agent = Arc::new(agent);
+            let stored = match (self.agents.entry(agent_id.clone()), replacing) {
+                (Entry::Occupied(mut entry), Some(old)) if Arc::ptr_eq(entry.get(), old) => {
+                    entry.insert(agent.clone());
+                    Ok(())
+                }
+                (Entry::Occupied(_), _) => Err(anyhow::anyhow!("Agent {} already exists", agent_id)),
+                (Entry::Vacant(_), Some(_)) => Err(anyhow::anyhow!("Agent {} went away while it was replaced", agent_id)),
+                (Entry::Vacant(entry), None) => {
+                    entry.insert(agent.clone());
+                    Ok(())
+                }
+            };
+            if let Err(e) = stored {
+                agent.kill().await?;
+                return Err(e);
+            }
+            
+            // Register with session
+            let mut session = self.session.write().await;
+            session.register_agent(agent_id.clone(), agent.agent_type.to_string());
+            session.set_agent_config(&agent_id, &spawned_config);
+            session.set_history_limit(&agent_id, history_limit)?;
+            drop(session);
+            
+            // The workspace as expanded by the spawn
+            watched_workspace = agent.config().await.workspace_path;
+            self.stats_history.lock().await.record_spawn(&agent_id);
+            
+            info!("✅ Agent {} spawned successfully", agent_id);
+            publish_event(&self.ipc_bridge, AgentEvent::StatusChanged {
+                agent_id: agent_id.clone(),
+                status: agent.status_dto().await,
+            });
+            tokio::spawn(forward_agent_events(
+                agent,
+                self.agents.clone(),
+                self.ipc_bridge.clone(),
+                self.stats_history.clone(),
+                self.bytes_received.clone(),
+            ));
+        }
+        
+        if let Some(workspace) = watched_workspace.filter(|_| watch_workspace) {
+            if let Err(e) = self.start_workspace_watcher(&agent_id, Path::new(&workspace)) {
+                error!("Workspace watcher for agent {} failed: {}", agent_id, e);
+            }
+        }
+        
+        if nudge_prompt {
+            if let Some(agent) = self.get_process(&agent_id) {
+                if let Err(e) = agent.send_raw(b"\n").await {
+                    error!("Prompt nudge for agent {} failed: {}", agent_id, e);
+                }
+            }
+        }
+        
+        for command in &startup_commands {
+            if let Err(e) = self.send_command(&agent_id, command).await {
+                error!("Startup command for agent {} failed: {}", agent_id, e);
+            }
+        }
+        
+        Ok(agent_id)
+    }
+
+    /// Spawns an agent and injects `context_files` before handing out its id,
+    /// health-checking it before and after. Each file, and then the id,
+    /// waits up to `context_ready_timeout_ms` for the readiness pattern in
+    /// output following the previous file. If the agent isn't ready in time
+    /// or any file fails, the agent is killed and the error returned,
+    /// `ContextInjectionFailed` for a file.
+    pub async fn spawn_agent_with_context(
+        &self,
+        config: AgentConfig,
+        context_files: Vec<PathBuf>,
+    ) -> Result<String> {
+        let agent_id = self.spawn_agent(config).await?;
+        
+        // Plugin agents have no readiness pattern to wait for
+        let native = self.agents.get(&agent_id).map(|entry| entry.value().clone());
+        let ready_timeout = Duration::from_millis(self.config.context_ready_timeout_ms);
+        let injected = async {
+            self.check_agent_health(&agent_id).await?;
+            let mut ready_from = 0;
+            for path in &context_files {
+                if let Some(agent) = &native {
+                    agent.wait_until_ready(ready_from, ready_timeout).await?;
+                    // Past the line being written now, which the file's echo ends
+                    ready_from = agent.get_lines(usize::MAX, usize::MAX).await.total_lines + 1;
+                }
+                if let Err(e) = self.send_file_contents(&agent_id, path.clone(), None).await {
+                    return Err(AgentError::ContextInjectionFailed {
+                        agent_id: agent_id.clone(),
+                        path: path.display().to_string(),
+                        reason: e.to_string(),
+                    }.into());
+                }
+            }
+            self.check_agent_health(&agent_id).await?;
+            if let Some(agent) = &native {
+                agent.wait_until_ready(ready_from, ready_timeout).await?;
+            }
+            Ok(())
+        }.await;
+        
+        if let Err(e) = injected {
+            error!("Context injection for agent {} failed: {}", agent_id, e);
+            if let Err(kill_err) = self.kill_agent(&agent_id).await {
+                error!("Failed to kill agent {}: {}", agent_id, kill_err);
+            }
+            return Err(e);
+        }
+        
+        info!("📎 Agent {} started with {} context file(s)", agent_id, context_files.len());
+        Ok(agent_id)
+    }
+
+    /// Fails unless the agent is registered and its process is running.
+    async fn check_agent_health(&self, agent_id: &str) -> Result<()> {
+        let status = self.get_agent_status(agent_id).await?;
+        if status["running"].as_bool() != Some(true) {
+            anyhow::bail!("Agent {} is not running", agent_id);
+        }
+        Ok(())
+    }
+
+    /// Replaces `old_id` with a freshly spawned agent. The new agent takes
+    /// over the old one's groups and aliases (and its startup commands, if
+    /// `new_config` has none) before the old agent is killed, so one of the
+    /// two is registered at every point. If the new agent isn't running once
+    /// spawned, or the old one can't be killed, the new one is killed and
+    /// the old one keeps everything.
+    pub async fn swap_agent(&self, old_id: &str, new_config: AgentConfig) -> Result<String> {
+        let old = self.get_live_agent(old_id).await?;
+        let old_config = old.config().await;
+        
+        let mut new_config = new_config;
+        if new_config.startup_commands.is_empty() {
+            new_config.startup_commands = old_config.startup_commands;
+        }
+        
+        let new_id = self.spawn_agent(new_config).await?;
+        if let Err(e) = self.check_agent_health(&new_id).await {
+            self.discard_agent(&new_id).await;
+            return Err(e);
+        }
+        self.transfer_agent_links(old_id, &new_id);
+        if let Err(e) = self.kill_agent(old_id).await {
+            self.transfer_agent_links(&new_id, old_id);
+            self.discard_agent(&new_id).await;
+            return Err(e);
+        }
+        
+        info!("🔁 Swapped agent {} for {}", old_id, new_id);
+        Ok(new_id)
+    }
+
+    /// Kills an agent `swap_agent` no longer needs, logging failures.
+    async fn discard_agent(&self, agent_id: &str) {
+        if let Err(e) = self.kill_agent(agent_id).await {
+            error!("Failed to kill agent {}: {}", agent_id, e);
+        }
+    }
+
+    /// Kills an agent and spawns it again under the same id with the config
+    /// it was started with, keeping its groups, aliases, window, schedules,
+    /// pipes and queued commands. The old process stays registered, and
+    /// takes commands, until the new one is up and swapped in, and stays in
+    /// place if the spawn fails; only then is it killed. Emits a `reset`
+    /// SystemEvent once the new process is up. A second reset of the same
+    /// agent fails while one is in progress.
+    pub async fn reset_agent(self: &Arc<Self>, agent_id: &str) -> Result<()> {
+        let agent_id = self.resolve_agent_id(agent_id);
+        let config = self.get_live_agent(&agent_id).await?.config().await;
+        if !self.resetting.insert(agent_id.clone()) {
+            anyhow::bail!("Agent {} is already being reset", agent_id);
+        }
+        let result = self.respawn_agent(&agent_id, config).await;
+        self.resetting.remove(&agent_id);
+        result?;
+        
+        self.stats_history.lock().await.record_restart(&agent_id);
+        info!("🔁 Reset agent {}", agent_id);
+        let event = IpcMessage {
+            agent_id: agent_id.clone(),
+            message_type: MessageType::SystemEvent,
+            payload: serde_json::json!({ "event": "reset" }),
+            timestamp: chrono::Utc::now(),
+            batch_id: None,
+            correlation_id: None,
+        };
+        if let Err(e) = self.ipc_bridge.send_message(event) {
+            debug!("No IPC subscribers for reset event: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Everything kept by agent id stays in place; only the pipes, which
+    /// hold the process, are reconnected.
+    async fn respawn_agent(self: &Arc<Self>, agent_id: &str, config: AgentConfig) -> Result<()> {
+        let old = self.agents.get(agent_id).map(|entry| entry.value().clone())
+            .ok_or_else(|| anyhow::anyhow!("Agent {} not found", agent_id))?;
+        // The session entry is only replaced once the new process is in
+        let config = AgentConfig { agent_id: Some(agent_id.to_string()), ..config };
+        self.spawn_agent_replacing(config, Some(&old)).await?;
+        
+        // Stopped before the old process, whose exit would close them
+        let pipe_ids: Vec<String> = self.pipes.iter()
+            .filter(|entry| entry.info.from_id == agent_id || entry.info.to_id == agent_id)
+            .map(|entry| entry.key().clone())
+            .collect();
+        let pipes: Vec<PipeEntry> = pipe_ids.iter()
+            .filter_map(|pipe_id| self.pipes.remove(pipe_id))
+            .map(|(_, entry)| entry)
+            .collect();
+        for entry in &pipes {
+            entry.task.abort();
+        }
+        if let Err(e) = old.kill().await {
+            error!("Failed to kill the old process of agent {}: {}", agent_id, e);
+        }
+        self.stats_history.lock().await.record_kill(agent_id);
+        self.watermarks.retain(|_, (owner, _, _)| owner != agent_id);
+        // The new session entry counts no history, but the old records stay
+        self.session.write().await.recount_history();
+        
+        for PipeEntry { info, filter, .. } in pipes {
+            let Some(from) = self.agents.get(&info.from_id).map(|entry| entry.value().clone()) else {
+                continue;
+            };
+            let to = self.agents.get(&info.to_id).map(|entry| entry.value().clone());
+            self.start_pipe(info, filter, from, to);
+        }
+        Ok(())
+    }
+
+    pub fn add_to_group(&self, group: &str, agent_id: &str) -> Result<()> {
+        if self.get_process(agent_id).is_none() {
+            anyhow::bail!("Agent {} not found", agent_id);
+        }
+        self.groups.entry(group.to_string()).or_default().insert(agent_id.to_string());
+        Ok(())
+    }
+
+    pub fn remove_from_group(&self, group: &str, agent_id: &str) {
+        if let Some(mut members) = self.groups.get_mut(group) {
+            members.remove(agent_id);
+        }
+        self.groups.remove_if(group, |_, members| members.is_empty());
+    }
+
+    pub fn list_groups(&self) -> HashMap<String, Vec<String>> {
+        self.groups.iter()
+            .map(|entry| (entry.key().clone(), entry.value().iter().cloned().collect()))
+            .collect()
+    }
+
+    pub fn set_alias(&self, alias: &str, agent_id: &str) -> Result<()> {
+        if self.get_process(agent_id).is_none() {
+            anyhow::bail!("Agent {} not found", agent_id);
+        }
+        if self.get_process(alias).is_some() {
+            anyhow::bail!("Alias {} would shadow an agent id", alias);
+        }
+        self.aliases.insert(alias.to_string(), agent_id.to_string());
+        Ok(())
+    }
+
+    pub fn remove_alias(&self, alias: &str) {
+        self.aliases.remove(alias);
+    }
+
+    pub fn list_aliases(&self) -> HashMap<String, String> {
+        self.aliases.iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Maps an alias to its agent id; anything else is returned unchanged.
+    pub fn resolve_agent_id(&self, id_or_alias: &str) -> String {
+        self.aliases.get(id_or_alias)
+            .map(|id| id.value().clone())
+            .unwrap_or_else(|| id_or_alias.to_string())
+    }
+
+    fn transfer_agent_links(&self, old_id: &str, new_id: &str) {
+        for mut members in self.groups.iter_mut() {
+            if members.remove(old_id) {
+                members.insert(new_id.to_string());
+            }
+        }
+        for mut target in self.aliases.iter_mut() {
+            if target.value() == old_id {
+                *target.value_mut() = new_id.to_string();
+            }
+        }
+        if let Some((_, window)) = self.window_labels.remove(old_id) {
+            self.window_labels.insert(new_id.to_string(), window);
+        }
+    }
+
+    /// Picks the label for a new window of the agent and records it with
+    /// `tag`: `agent_` and the id, with characters labels don't allow
+    /// replaced by `_` and a `-2`, `-3`, ... suffix while another agent's
+    /// window has that label. The label stays with the window when
+    /// `swap_agent` hands it to the new agent, or `reset_agent` re-spawns
+    /// the agent with the same id; killing the agent forgets it.
+    pub fn assign_window_label(&self, agent_id: &str, tag: Option<String>) -> String {
+        let agent_id = self.resolve_agent_id(agent_id);
+        let base: String = format!("agent_{}", agent_id).chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_') { c } else { '_' })
+            .collect();
+        let taken = |label: &str| self.window_labels.iter()
+            .any(|entry| entry.key() != &agent_id && entry.value().0 == label);
+        let mut label = base.clone();
+        let mut n = 1;
+        while taken(&label) {
+            n += 1;
+            label = format!("{}-{}", base, n);
+        }
+        self.window_labels.insert(agent_id, (label.clone(), tag, Instant::now()));
+        label
+    }
+
+    /// The label of the agent's open window, if it has one.
+    pub fn window_label_for(&self, agent_id: &str) -> Option<String> {
+        self.window_labels.get(&self.resolve_agent_id(agent_id)).map(|window| window.0.clone())
+    }
+
+    /// Forgets a closed window. Returns the agent it belonged to.
+    pub fn deregister_window(&self, label: &str) -> Option<String> {
+        let agent_id = self.window_labels.iter()
+            .find(|entry| entry.value().0 == label)
+            .map(|entry| entry.key().clone())?;
+        self.window_labels.remove(&agent_id);
+        debug!("Window {} of agent {} closed", label, agent_id);
+        Some(agent_id)
+    }
+
+    /// Window label of every agent with an open window, by agent id, for
+    /// reconciling windows with agents after a reload.
+    pub fn window_labels(&self) -> HashMap<String, String> {
+        self.window_labels.iter()
+            .map(|entry| (entry.key().clone(), entry.value().0.clone()))
+            .collect()
+    }
+
+    /// Label and tag of every open agent window, in the order they were
+    /// opened, for the window arranger.
+    pub fn open_windows(&self) -> Vec<(String, Option<String>)> {
+        let mut windows: Vec<WindowEntry> = self.window_labels.iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        windows.sort_by_key(|(_, _, opened)| *opened);
+        windows.into_iter().map(|(label, tag, _)| (label, tag)).collect()
+    }
+
+    fn forget_agent_links(&self, agent_id: &str) {
+        for mut members in self.groups.iter_mut() {
+            members.remove(agent_id);
+        }
+        self.groups.retain(|_, members| !members.is_empty());
+        self.aliases.retain(|_, target| target != agent_id);
+        self.window_labels.remove(agent_id);
+    }
+
+    /// Runs the spawn-time checks for `config` without starting a process.
+    pub fn validate_agent(&self, config: &AgentConfig) -> Result<()> {
+        checks::check_config(config, &self.plugins)
+    }
+
+    /// Sends a command, applying the agent's prefix/suffix wrapper. Session
+    /// history records the command as given, before wrapping, so replays and
+    /// analytics are not affected by later wrapper changes.
+    pub async fn send_command(&self, agent_id: &str, command: &str) -> Result<()> {
+        self.dispatch_command(agent_id, command, false).await
+    }
+
+    async fn dispatch_command(&self, agent_id: &str, command: &str, scheduled: bool) -> Result<()> {
+        let agent = self.get_live_process(agent_id).await?;
+        // Subscribed before sending, so a fast response still stops the timer
+        let native = self.agents.get(agent_id).map(|entry| entry.value().clone());
+        let mut response_watch = None;
+        let mut output_line = None;
+        if let Some(native) = native {
+            output_line = Some((native.get_lines(usize::MAX, usize::MAX).await.total_lines, native.start_time().await));
+            let config = native.config().await;
+            if let Some(timeout) = config.response_timeout {
+                let output = native.subscribe_output();
+                let echo = (config.tty_type == TtyType::Pty).then(|| config.wrap_command(command));
+                response_watch = Some((native, timeout, output, echo));
+            }
+        }
+        
+        let redacted = AgentManager::redact_command(command);
+        debug!("Sending command to agent {}: {}", agent_id, redacted);
+        // The command is published, redacted, then acknowledged once the
+        // write has been flushed; the frontend matches the two by
+        // correlation_id
+        let correlation_id = uuid::Uuid::new_v4().to_string();
+        let input = IpcMessage {
+            agent_id: agent_id.to_string(),
+            message_type: MessageType::Input,
+            payload: serde_json::json!({ "command": redacted }),
+            timestamp: chrono::Utc::now(),
+            batch_id: None,
+            correlation_id: Some(correlation_id.clone()),
+        };
+        if let Err(e) = self.ipc_bridge.send_message(input) {
+            debug!("No IPC subscribers for command to agent {}: {}", agent_id, e);
+        }
+        let sent = agent.send_command(command).await;
+        let delivery = IpcMessage {
+            agent_id: agent_id.to_string(),
+            message_type: MessageType::SystemEvent,
+            payload: match &sent {
+                Ok(()) => serde_json::json!({ "delivered": true }),
+                Err(e) => serde_json::json!({ "delivered": false, "error": e.to_string() }),
+            },
+            timestamp: chrono::Utc::now(),
+            batch_id: None,
+            correlation_id: Some(correlation_id),
+        };
+        if let Err(e) = self.ipc_bridge.send_message(delivery) {
+            debug!("No IPC subscribers for delivery to agent {}: {}", agent_id, e);
+        }
+        if let Err(e) = sent {
+            let message = format!("Failed to send command: {}", e);
+            self.stats_history.lock().await.record_error(agent_id, &message);
+            publish_event(&self.ipc_bridge, AgentEvent::Error {
+                agent_id: agent_id.to_string(),
+                message,
+            });
+            return Err(e);
+        }
+        
+        // Log to session; like the agent's own count, only once the write
+        // has succeeded
+        let mut session = self.session.write().await;
+        if scheduled {
+            session.log_scheduled_command(agent_id, command);
+        } else {
+            session.log_command(agent_id, command);
+        }
+        if let Some((output_line, started_at)) = output_line {
+            session.record_output_line(agent_id, output_line, started_at);
+        }
+        if let Some(keep_last) = self.config.auto_prune_history {
+            session.prune_history(keep_last);
+        }
+        drop(session);
+        
+        if let Some((native, timeout, output, echo)) = response_watch {
+            tokio::spawn(watch_response(native, timeout, output, echo, self.ipc_bridge.clone()));
+        }
+        Ok(())
+    }
+
+    /// Queues a command for the agent without sending it; the caller sends
+    /// queued commands one at a time with `send_next_queued`, e.g. once the
+    /// agent shows its prompt again. Returns the new queue depth.
+    pub async fn queue_command(&self, agent_id: &str, command: String) -> Result<usize> {
+        self.get_live_process(agent_id).await?;
+        // Plugin agents have no `AgentConfig` to carry a threshold
+        let native = self.agents.get(agent_id).map(|agent| agent.value().clone());
+        let threshold = match native {
+            Some(agent) => agent.config().await.queue_depth_warning_threshold,
+            None => None,
+        };
+        
+        let depth = {
+            let mut queue = self.command_queues.entry(agent_id.to_string()).or_default();
+            queue.push_back(command);
+            queue.len()
+        };
+        
+        if threshold.is_some_and(|threshold| depth > threshold) {
+            let event = IpcMessage {
+                agent_id: agent_id.to_string(),
+                message_type: MessageType::SystemEvent,
+                payload: serde_json::json!({ "event": "queue_deep", "depth": depth }),
+                timestamp: chrono::Utc::now(),
+                batch_id: None,
+                correlation_id: None,
+            };
+            if let Err(e) = self.ipc_bridge.send_message(event) {
+                debug!("No IPC subscribers for queue_deep event: {}", e);
+            }
+        }
+        Ok(depth)
+    }
+
+    /// Sends the oldest queued command. Returns `false` if the queue was empty.
+    pub async fn send_next_queued(&self, agent_id: &str) -> Result<bool> {
+        let next = self.command_queues.get_mut(agent_id)
+            .and_then(|mut queue| queue.pop_front());
+        match next {
+            Some(command) => {
+                self.send_command(agent_id, &command).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Commands waiting in the agent's queue; 0 if it never queued any.
+    pub fn get_pending_queue_depth(&self, agent_id: &str) -> Result<usize> {
+        if self.get_process(agent_id).is_none() && !self.command_queues.contains_key(agent_id) {
+            anyhow::bail!("Agent {} not found", agent_id);
+        }
+        Ok(self.command_queues.get(agent_id).map_or(0, |queue| queue.len()))
+    }
+
+    /// Discards the agent's queued commands, returning how many there were.
+    pub fn flush_queue(&self, agent_id: &str) -> Result<usize> {
+        self.get_pending_queue_depth(agent_id)?;
+        // Counted and cleared under one lock, so nothing queued in between
+        // is dropped uncounted
+        let depth = self.command_queues.get_mut(agent_id).map_or(0, |mut queue| queue.drain(..).count());
+        if depth > 0 {
+            info!("Flushed {} queued command(s) for agent {}", depth, agent_id);
+        }
+        Ok(depth)
+    }
+
+    /// Sends `command` to the agent on `schedule`, through the same path as
+    /// `send_command`. Returns the schedule id for `cancel_scheduled_command`.
+    pub async fn schedule_command(
+        self: &Arc<Self>,
+        agent_id: &str,
+        command: String,
+        schedule: Schedule,
+    ) -> Result<String> {
+        self.get_live_process(agent_id).await?;
+        if let Schedule::Every(interval) = schedule {
+            if interval < MIN_SCHEDULE_INTERVAL {
+                anyhow::bail!("Schedule interval must be at least {:?}", MIN_SCHEDULE_INTERVAL);
+            }
+        }
+        
+        let info = ScheduledCommand {
+            id: Uuid::new_v4().to_string(),
+            agent_id: agent_id.to_string(),
+            command,
+            schedule,
+            created_at: chrono::Utc::now(),
+        };
+        let id = info.id.clone();
+        // Spawned under the entry lock so a schedule that fires at once can't
+        // remove itself before it is registered
+        self.schedules.entry(id.clone()).or_insert_with(|| {
+            let task = tokio::spawn(run_schedule(Arc::downgrade(self), info.clone()));
+            ScheduleEntry { info, task }
+        });
+        
+        info!("⏰ Scheduled command {} for agent {}", id, agent_id);
+        Ok(id)
+    }
+
+    pub fn list_scheduled_commands(&self) -> Vec<ScheduledCommand> {
+        let mut scheduled: Vec<ScheduledCommand> = self.schedules
+            .iter()
+            .map(|entry| entry.value().info.clone())
+            .collect();
+        scheduled.sort_by_key(|command| command.created_at);
+        scheduled
+    }
+
+    pub fn cancel_scheduled_command(&self, id: &str) -> Result<()> {
+        let (_, entry) = self.schedules.remove(id)
+            .ok_or_else(|| anyhow::anyhow!("Scheduled command {} not found", id))?;
+        entry.task.abort();
+        Ok(())
+    }
+
+    fn cancel_agent_schedules(&self, agent_id: &str) {
+        self.schedules.retain(|_, entry| {
+            if entry.info.agent_id != agent_id {
+                return true;
+            }
+            entry.task.abort();
+            false
+        });
+    }
+
+    /// Queues each output line of `from_id` that `filter` matches as a
+    /// command for `to_id`, until either agent exits or is killed or the
+    /// pipe is removed; with a capture group in `filter`, its first group is
+    /// queued instead of the whole line. The source must be a native agent.
+    /// Pipes that would close a loop are refused, and each pipe forwards at
+    /// most `MAX_PIPED_LINES_PER_WINDOW` lines per `PIPE_RATE_WINDOW`.
+    pub async fn pipe(
+        self: &Arc<Self>,
+        from_id: &str,
+        to_id: &str,
+        filter: Option<Regex>,
+    ) -> Result<PipeHandle> {
+        let from_id = self.resolve_agent_id(from_id);
+        let to_id = self.resolve_agent_id(to_id);
+        let from = self.get_live_agent(&from_id).await?;
+        self.get_live_process(&to_id).await?;
+        let to = self.agents.get(&to_id).map(|entry| entry.value().clone());
+        
+        let info = PipeHandle {
+            id: Uuid::new_v4().to_string(),
+            from_id,
+            to_id,
+            filter: filter.as_ref().map(|filter| filter.as_str().to_string()),
+            created_at: chrono::Utc::now(),
+        };
+        let handle = info.clone();
+        {
+            let _guard = self.pipe_lock.lock().unwrap_or_else(|e| e.into_inner());
+            if self.pipe_path_exists(&handle.to_id, &handle.from_id) {
+                anyhow::bail!("Piping {} into {} would create a cycle", handle.from_id, handle.to_id);
+            }
+            self.start_pipe(info, filter, from, to);
+        }
+        
+        info!("🔗 Piped agent {} into agent {} ({})", handle.from_id, handle.to_id, handle.id);
+        Ok(handle)
+    }
+
+    fn start_pipe(
+        self: &Arc<Self>,
+        info: PipeHandle,
+        filter: Option<Regex>,
+        from: Arc<AgentProcess>,
+        to: Option<Arc<AgentProcess>>,
+    ) {
+        // Spawned under the entry lock so a pipe whose agent has already
+        // exited can't remove itself before it is registered
+        self.pipes.entry(info.id.clone()).or_insert_with(|| {
+            let output = from.subscribe_output();
+            let task = tokio::spawn(run_pipe(Arc::downgrade(self), info.clone(), filter.clone(), output, from, to));
+            PipeEntry { info, filter, task }
+        });
+    }
+
+    pub fn remove_pipe(&self, pipe_id: &str) -> Result<()> {
+        let (_, entry) = self.pipes.remove(pipe_id)
+            .ok_or_else(|| anyhow::anyhow!("Pipe {} not found", pipe_id))?;
+        entry.task.abort();
+        Ok(())
+    }
+
+    /// Whether existing pipes already carry output from `from_id` to `to_id`,
+    /// directly or through other agents.
+    fn pipe_path_exists(&self, from_id: &str, to_id: &str) -> bool {
+        let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+        for entry in self.pipes.iter() {
+            edges.entry(entry.info.from_id.clone()).or_default().push(entry.info.to_id.clone());
+        }
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![from_id.to_string()];
+        while let Some(agent_id) = pending.pop() {
+            if agent_id == to_id {
+                return true;
+            }
+            if seen.insert(agent_id.clone()) {
+                pending.extend(edges.get(&agent_id).into_iter().flatten().cloned());
+            }
+        }
+        false
+    }
+
+    fn remove_agent_pipes(&self, agent_id: &str) {
+        self.pipes.retain(|_, entry| {
+            if entry.info.from_id != agent_id && entry.info.to_id != agent_id {
+                return true;
+            }
+            entry.task.abort();
+            false
+        });
+    }
+
+    /// Registers a long-running call so it can be cancelled by id while it
+    /// runs. The id is freed when the returned `Operation` is dropped.
+    pub fn begin_operation(&self, operation_id: Option<String>) -> Result<Operation> {
+        Operation::register(&self.operations, operation_id)
+    }
+
+    pub fn cancel_operation(&self, operation_id: &str) -> Result<()> {
+        let token = self.operations.get(operation_id)
+            .map(|entry| entry.value().clone())
+            .ok_or_else(|| anyhow::anyhow!("Operation {} not found", operation_id))?;
+        info!("🛑 Cancelling operation {}", operation_id);
+        token.cancel();
+        Ok(())
+    }
+
+    /// Sends a command and returns whatever output arrives within
+    /// `collect_ms`, typically the echo and first response line. Output is
+    /// read from a subscription, so `get_output` still sees all of it.
+    /// Cancelling `operation` stops collecting; the command stays sent.
+    pub async fn send_command_ack(
+        &self,
+        agent_id: &str,
+        command: &str,
+        collect_ms: Option<u64>,
+        operation: &Operation,
+    ) -> Result<String> {
+        let window = Duration::from_millis(collect_ms.unwrap_or(DEFAULT_ACK_MS).min(MAX_ACK_MS));
+        let mut output = self.get_live_agent(agent_id).await?.subscribe_output();
+        self.send_command(agent_id, command).await?;
+        
+        let deadline = tokio::time::Instant::now() + window;
+        let mut ack = Vec::new();
+        loop {
+            let received = tokio::select! {
+                received = tokio::time::timeout_at(deadline, output.recv()) => received,
+                _ = operation.token().cancelled() => return Err(operation.cancelled()),
+            };
+            match received {
+                Ok(Ok(chunk)) => ack.extend_from_slice(&chunk),
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped))) => {
+                    debug!("Ack for agent {} skipped {} chunks", agent_id, skipped);
+                }
+                Ok(Err(tokio::sync::broadcast::error::RecvError::Closed)) | Err(_) => break,
+            }
+        }
+        Ok(String::from_utf8_lossy(&ack).into_owned())
+    }
+
+    /// Waits up to `timeout_ms` for the agent's next complete response; see
+    /// `AgentProcess::get_last_response`.
+    pub async fn await_agent_response(&self, agent_id: &str, timeout_ms: u64) -> Result<String> {
+        let agent = self.get_live_agent(agent_id).await?;
+        let response = agent.get_last_response(Duration::from_millis(timeout_ms)).await?;
+        self.stats_history.lock().await.record_completion(agent_id);
+        self.session.write().await.record_response_time(agent_id);
+        Ok(response)
+    }
+
+    /// Sets a watermark in the agent's output (see
+    /// `AgentProcess::output_watermark`) and returns its id for
+    /// `await_watermark`. Commands still in the agent's queue are sent
+    /// after it. Watermarks not awaited within `WATERMARK_TTL` are
+    /// discarded.
+    pub async fn create_watermark(&self, agent_id: &str) -> Result<String> {
+        let agent_id = self.resolve_agent_id(agent_id);
+        let agent = self.get_live_agent(&agent_id).await?;
+        self.watermarks.retain(|_, (_, _, created)| created.elapsed() < WATERMARK_TTL);
+        let watermark_id = Uuid::new_v4().simple().to_string();
+        let handle = agent.output_watermark(watermark_id.clone()).await?;
+        self.watermarks.insert(watermark_id.clone(), (agent_id, handle, Instant::now()));
+        Ok(watermark_id)
+    }
+
+    /// Waits up to `timeout` for a watermark from `create_watermark`. Each
+    /// watermark can be awaited once; it is discarded if the wait times out.
+    pub async fn await_watermark(&self, watermark_id: &str, timeout: Duration) -> Result<()> {
+        let (_, (_, handle, _)) = self.watermarks.remove(watermark_id)
+            .ok_or_else(|| anyhow::anyhow!("Watermark {} not found", watermark_id))?;
+        handle.wait(timeout).await
+    }
+
+    /// Detects the agent's prompt and keeps it as its prompt pattern; see
+    /// `AgentProcess::detect_prompt`. The session's copy of the config is
+    /// updated too, so templates carry the detected pattern.
+    pub async fn detect_agent_prompt(&self, agent_id: &str, sample_lines: Option<usize>) -> Result<String> {
+        let agent_id = self.resolve_agent_id(agent_id);
+        let agent = self.get_live_agent(&agent_id).await?;
+        let pattern = agent
+            .detect_prompt(sample_lines.unwrap_or(DEFAULT_PROMPT_SAMPLE_LINES))
+            .await?;
+        self.session.write().await.set_agent_config(&agent_id, &agent.config().await);
+        Ok(pattern)
+    }
+
+    pub async fn set_agent_env(&self, agent_id: &str, key: String, value: String) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.set_pty_env(key, value).await
+    }
+
+    /// Writes `data` to the agent as is: no prefix or suffix, no queue and
+    /// no task history record, but it counts as activity in the session.
+    pub async fn send_raw(&self, agent_id: &str, data: &[u8]) -> Result<()> {
+        let agent = self.get_live_process(agent_id).await?;
+        agent.send_raw(data).await?;
+        self.session.write().await.record_activity(agent_id);
+        debug!("Sent {} raw bytes to agent {}", data.len(), agent_id);
+        Ok(())
+    }
+
+    /// Re-pins a running native agent; see `AgentConfig::cpu_affinity`.
+    pub async fn set_agent_cpu_affinity(&self, agent_id: &str, cpus: Vec<usize>) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.set_cpu_affinity(cpus).await
+    }
+
+    /// Resizes the agent's terminal and tells every viewer the new size, so
+    /// windows showing the same agent stay consistent. The current size is
+    /// also in `get_agent_status` (`rows`, `cols`) for windows opened later.
+    pub async fn resize(&self, agent_id: &str, rows: u16, cols: u16) -> Result<()> {
+        let agent = self.get_live_process(agent_id).await?;
+        agent.resize(rows, cols).await?;
+        debug!("Resized agent {} to {}x{}", agent_id, cols, rows);
+        
+        let event = IpcMessage {
+            agent_id: agent_id.to_string(),
+            message_type: MessageType::SystemEvent,
+            payload: serde_json::json!({ "event": "resized", "rows": rows, "cols": cols }),
+            timestamp: chrono::Utc::now(),
+            batch_id: None,
+            correlation_id: None,
+        };
+        if let Err(e) = self.ipc_bridge.send_message(event) {
+            debug!("No IPC subscribers for resize event: {}", e);
+        }
+        if let Some(agent) = self.agents.get(agent_id).map(|entry| entry.value().clone()) {
+            publish_event(&self.ipc_bridge, AgentEvent::StatusChanged {
+                agent_id: agent_id.to_string(),
+                status: agent.status_dto().await,
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes `data` to the agent `count` times, `delay_ms` apart, and emits
+    /// a single summarized `Input` event.
+    pub async fn repeat_input(
+        &self,
+        agent_id: &str,
+        data: Vec<u8>,
+        count: usize,
+        delay_ms: u64,
+    ) -> Result<()> {
+        if count == 0 || count > MAX_REPEAT_COUNT {
+            anyhow::bail!("Repeat count must be between 1 and {}", MAX_REPEAT_COUNT);
+        }
+        if delay_ms > MAX_REPEAT_DELAY_MS {
+            anyhow::bail!("Repeat delay must be at most {} ms", MAX_REPEAT_DELAY_MS);
+        }
+        let agent = self.get_live_process(agent_id).await?;
+        
+        for i in 0..count {
+            if i > 0 && delay_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            }
+            agent.send_raw(&data).await?;
+        }
+        self.session.write().await.record_activity(agent_id);
+        
+        let event = IpcMessage {
+            agent_id: agent_id.to_string(),
+            message_type: MessageType::Input,
+            payload: serde_json::json!({
+                "repeat": count,
+                "bytes": data.len(),
+                "delay_ms": delay_ms,
+            }),
+            timestamp: chrono::Utc::now(),
+            batch_id: None,
+            correlation_id: None,
+        };
+        if let Err(e) = self.ipc_bridge.send_message(event) {
+            debug!("No IPC subscribers for repeat_input event: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Injects a file as context, recorded in history like any command.
+    pub async fn send_file_contents(
+        &self,
+        agent_id: &str,
+        path: PathBuf,
+        prepend_message: Option<String>,
+    ) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        let block = agent.file_context(&path, prepend_message).await?;
+        self.send_command(agent_id, &block).await
+    }
+
+    pub async fn set_command_wrapper(
+        &self,
+        agent_id: &str,
+        prefix: Option<String>,
+        suffix: Option<String>,
+    ) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.set_command_wrapper(prefix, suffix).await;
+        Ok(())
+    }
+
+    pub async fn get_scrollback(&self, agent_id: &str, lines: Option<usize>) -> Result<Vec<String>> {
+        let agent = self.get_live_agent(agent_id).await?;
+        Ok(agent.get_scrollback(lines).await)
+    }
+
+    pub async fn get_screen(&self, agent_id: &str) -> Result<String> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.screen_dump().await
+    }
+
+    /// Sets (or with `None`, clears) the theme used for colorized output.
+    pub async fn set_color_scheme(&self, agent_id: &str, scheme: Option<ColorScheme>) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.set_color_scheme(scheme).await;
+        Ok(())
+    }
+
+    pub async fn get_colorized_output(&self, agent_id: &str) -> Result<Vec<ColorizedChunk>> {
+        let agent = self.get_live_agent(agent_id).await?;
+        Ok(agent.get_colorized_output().await.unwrap_or_default())
+    }
+
+    /// Takes over the agent's JSON output stream; see
+    /// `AgentProcess::json_stream`.
+    pub async fn json_stream(&self, agent_id: &str) -> Result<Pin<Box<dyn Stream<Item = Value> + Send>>> {
+        let agent = self.get_live_agent(agent_id).await?;
+        if agent.config().await.output_framing != OutputFraming::Jsonl {
+            return Err(AgentError::NotSupported {
+                agent_id: agent_id.to_string(),
+                operation: "JSON output without jsonl framing".to_string(),
+            }.into());
         }
+        Ok(agent.json_stream())
     }
 
-    pub async fn spawn_agent(&self, config: AgentConfig) -> Result<String> {
-        let agent_id = config.agent_id.clone()
-            .unwrap_or_else(|| Uuid::new_v4().to_string());
-        
-        info!("Spawning {} agent with ID: {}", config.agent_type, agent_id);
-        
-        let agent = AgentManager::spawn(config).await?;
-        
-        // Register with session
-        self.session.write().await.register_agent(
-            agent_id.clone(),
-            agent.agent_type.to_string(),
-        );
-        
-        // Store agent process
-        self.agents.insert(agent_id.clone(), Arc::new(agent));
-        
-        info!("✅ Agent {} spawned successfully", agent_id);
-        Ok(agent_id)
+    /// Streams the agent's scrollback like `tail`; see `AgentProcess::tail`.
+    pub async fn tail(
+        &self,
+        agent_id: &str,
+        n: usize,
+        follow: bool,
+    ) -> Result<Pin<Box<dyn Stream<Item = String> + Send>>> {
+        let agent = self.get_live_agent(agent_id).await?;
+        Ok(agent.tail(n, follow))
     }
 
-    pub async fn send_command(&self, agent_id: &str, command: &str) -> Result<()> {
-        let agent = self.agents.get(agent_id)
-            .ok_or_else(|| anyhow::anyhow!("Agent {} not found", agent_id))?;
-        
-        debug!("Sending command to agent {}: {}", agent_id, command);
-        agent.send_command(command).await?;
-        
-        // Log to session
-        self.session.write().await.log_command(agent_id, command);
+    pub async fn snapshot_terminal(&self, agent_id: &str) -> Result<TerminalSnapshot> {
+        let agent = self.get_live_agent(agent_id).await?;
+        Ok(agent.snapshot_terminal().await)
+    }
+
+    pub async fn restore_terminal_view(&self, agent_id: &str, snapshot: &TerminalSnapshot) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.restore_terminal_view(snapshot).await
+    }
+
+    pub async fn get_output_range(&self, agent_id: &str, start: usize, end: usize) -> Result<Value> {
+        let agent = self.get_live_agent(agent_id).await?;
+        Ok(serde_json::to_value(agent.get_lines(start, end).await)?)
+    }
+
+    /// Binary outputs kept by MIME detection, from `since_index` on, with
+    /// base64-encoded data.
+    pub async fn get_binary_outputs(&self, agent_id: &str, since_index: usize) -> Result<Vec<Value>> {
+        let agent = self.get_live_agent(agent_id).await?;
+        Ok(agent.binary_outputs(since_index).await
+            .iter()
+            .map(|(index, output)| output.to_json(*index))
+            .collect())
+    }
+
+    pub async fn get_output_stats(&self, agent_id: &str) -> Result<Value> {
+        let agent = self.get_live_agent(agent_id).await?;
+        Ok(serde_json::to_value(agent.output_stats().await)?)
+    }
+
+    pub async fn set_collapse_cr_updates(&self, agent_id: &str, enabled: bool) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.set_collapse_cr_updates(enabled).await;
+        Ok(())
+    }
+
+    /// Keeps only output lines matching `include` and not matching
+    /// `exclude`. With neither, the agent's filter is removed.
+    pub async fn set_agent_output_filter(
+        &self,
+        agent_id: &str,
+        include: Option<String>,
+        exclude: Option<String>,
+    ) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        let compile = |pattern: &str| {
+            Regex::new(pattern).with_context(|| format!("Invalid filter pattern {:?}", pattern))
+        };
+        let include = include.as_deref().map(compile).transpose()?.map(FilterFactory::include_only);
+        let exclude = exclude.as_deref().map(compile).transpose()?.map(FilterFactory::exclude_matching);
         
+        match (include, exclude) {
+            (Some(include), Some(exclude)) => agent.output_filter(FilterFactory::and(include, exclude)),
+            (Some(filter), None) | (None, Some(filter)) => agent.output_filter(filter),
+            (None, None) => agent.clear_output_filter(),
+        }
+        info!("🧹 Output filter updated for agent {}", agent_id);
+        Ok(())
+    }
+
+    /// Applies the agent's current output filter chain, as last set with
+    /// `set_agent_output_filter`. New output goes through it from the moment
+    /// it is set; with `reprocess_existing` the retained scrollback is
+    /// rebuilt through it too, e.g. to redact a secret printed earlier; see
+    /// `AgentProcess::refilter_scrollback`. Returns the number of scrollback
+    /// lines redacted.
+    pub async fn reapply_filters(&self, agent_id: &str, reprocess_existing: bool) -> Result<usize> {
+        let agent = self.get_live_agent(agent_id).await?;
+        if !reprocess_existing {
+            return Ok(0);
+        }
+        let redacted = agent.refilter_scrollback().await;
+        info!("🧹 Refiltered scrollback of agent {}: {} lines redacted", agent_id, redacted);
+        Ok(redacted)
+    }
+
+    /// Starts emitting workspace change events for a running agent.
+    pub async fn watch_agent_workspace(&self, agent_id: &str) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        let workspace = agent.config().await.workspace_path
+            .ok_or_else(|| anyhow::anyhow!("Agent {} has no workspace_path", agent_id))?;
+        self.start_workspace_watcher(agent_id, Path::new(&workspace))
+    }
+
+    /// The files and directories in a running native agent's workspace,
+    /// `workspace_tree_depth` levels deep; see `list_agent_workspace`.
+    pub async fn get_workspace_file_tree(&self, agent_id: &str) -> Result<WorkspaceTree> {
+        self.list_agent_workspace(agent_id, "").await
+    }
+
+    /// Like `get_workspace_file_tree`, starting at `path` relative to the
+    /// workspace. Paths resolving outside the workspace, symlinks included,
+    /// fail with `AgentError::WorkspaceAccessDenied`. Past `MAX_TREE_ENTRIES`
+    /// the listing stops and is marked `truncated`.
+    pub async fn list_agent_workspace(&self, agent_id: &str, path: &str) -> Result<WorkspaceTree> {
+        let agent = self.get_live_agent(agent_id).await?;
+        let workspace = agent.config().await.workspace_path
+            .ok_or_else(|| anyhow::anyhow!("Agent {} has no workspace_path", agent_id))?;
+        let workspace = Path::new(&workspace);
+        let dir = workspace_tree::resolve(agent_id, workspace, path)?;
+        workspace_tree::list(workspace, &dir, self.config.workspace_tree_depth).await
+    }
+
+    pub fn unwatch_agent_workspace(&self, agent_id: &str) {
+        if self.workspace_watchers.remove(agent_id).is_some() {
+            debug!("Stopped watching workspace of agent {}", agent_id);
+        }
+    }
+
+    fn start_workspace_watcher(&self, agent_id: &str, workspace: &Path) -> Result<()> {
+        let watcher = WorkspaceWatcher::start(
+            agent_id.to_string(),
+            workspace,
+            self.ipc_bridge.clone(),
+        )?;
+        self.workspace_watchers.insert(agent_id.to_string(), watcher);
+        Ok(())
+    }
+
+    /// Interrupts whatever the agent is running without stopping the agent;
+    /// see `AgentProcess::send_interrupt`.
+    pub async fn interrupt_agent(&self, agent_id: &str) -> Result<()> {
+        let agent = self.get_live_agent(agent_id).await?;
+        agent.send_interrupt().await?;
+        self.session.write().await.record_activity(agent_id);
+        info!("✋ Interrupted agent {}", agent_id);
         Ok(())
     }
 
     pub async fn kill_agent(&self, agent_id: &str) -> Result<()> {
         if let Some((_, agent)) = self.agents.remove(agent_id) {
             info!("Killing agent {}", agent_id);
+            // Its event forwarder reports the exit
             agent.kill().await?;
             
             // Update session
             self.session.write().await.unregister_agent(agent_id);
+            self.stats_history.lock().await.record_kill(agent_id);
+        } else if let Some((_, agent)) = self.plugin_agents.remove(agent_id) {
+            info!("Killing plugin agent {}", agent_id);
+            agent.kill().await?;
+            
+            self.session.write().await.unregister_agent(agent_id);
+            self.stats_history.lock().await.record_kill(agent_id);
         }
+        self.release_agent_resources(agent_id);
         Ok(())
     }
 
+    /// Drops everything the orchestrator keeps per agent besides the
+    /// process itself and its session entry.
+    fn release_agent_resources(&self, agent_id: &str) {
+        self.forget_agent_links(agent_id);
+        self.unwatch_agent_workspace(agent_id);
+        self.cancel_agent_schedules(agent_id);
+        self.remove_agent_pipes(agent_id);
+        self.command_queues.remove(agent_id);
+        self.watermarks.retain(|_, (owner, _, _)| owner != agent_id);
+    }
+
+    /// Waits for every native agent's process to exit, up to `timeout` in
+    /// total. Agents that exit are unregistered; the rest are reported as
+    /// `ExitStatus::Timeout` and left running. Plugin agents are reported as
+    /// `ExitStatus::Unsupported` without waiting. Cancelling `operation`
+    /// stops waiting and leaves the agents exited so far unregistered.
+    pub async fn wait_for_all(
+        &self,
+        timeout: Duration,
+        operation: &Operation,
+    ) -> Result<Vec<(String, ExitStatus)>> {
+        let mut pending: BTreeSet<String> = BTreeSet::new();
+        let mut waits = tokio::task::JoinSet::new();
+        for entry in self.agents.iter() {
+            let (agent_id, agent) = (entry.key().clone(), entry.value().clone());
+            pending.insert(agent_id.clone());
+            waits.spawn(async move { (agent_id, agent.wait().await) });
+        }
+        
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut results = Vec::new();
+        loop {
+            let next = tokio::select! {
+                next = tokio::time::timeout_at(deadline, waits.join_next()) => next,
+                _ = operation.token().cancelled() => {
+                    waits.abort_all();
+                    return Err(operation.cancelled());
+                }
+            };
+            let Ok(Some(joined)) = next else {
+                break;
+            };
+            let (agent_id, status) = joined?;
+            pending.remove(&agent_id);
+            match status {
+                Ok(status) => {
+                    self.agents.remove(&agent_id);
+                    self.session.write().await.unregister_agent(&agent_id);
+                    self.release_agent_resources(&agent_id);
+                    results.push((agent_id, status));
+                }
+                Err(e) => error!("Waiting for agent {} failed: {}", agent_id, e),
+            }
+        }
+        waits.abort_all();
+        
+        results.extend(pending.into_iter().map(|agent_id| (agent_id, ExitStatus::Timeout)));
+        results.extend(self.plugin_agents.iter().map(|entry| (entry.key().clone(), ExitStatus::Unsupported)));
+        Ok(results)
+    }
+
+    /// Runs a workflow's steps one at a time in dependency order. Each step
+    /// sends its command and waits for the agent's next prompt (see
+    /// `AgentProcess::get_last_response`), so its agent must be a native one
+    /// with a prompt pattern. A failed step skips the steps that depend on
+    /// it; the others still run. Cancelling `operation` ends the current
+    /// step's wait and the run, which is returned either way; `Err` means
+    /// the spec itself is invalid.
+    pub async fn run_workflow(&self, workflow: WorkflowSpec, operation: &Operation) -> Result<WorkflowRun> {
+        let plan = workflow.plan()?;
+        info!("▶️ Running workflow {} ({} steps)", operation.id(), plan.len());
+        
+        let started_at = chrono::Utc::now();
+        let mut vars = HashMap::new();
+        let mut step_results: HashMap<String, StepResult> = HashMap::new();
+        for step in &plan {
+            let blocked = step.depends_on.iter()
+                .any(|dep| step_results[dep].status != StepStatus::Succeeded);
+            let result = if operation.token().is_cancelled() {
+                StepResult::ended(StepStatus::Cancelled, None, 0)
+            } else if blocked {
+                StepResult::ended(StepStatus::Skipped, None, 0)
+            } else {
+                let started = Instant::now();
+                let outcome = tokio::select! {
+                    outcome = self.run_workflow_step(step, &vars) => outcome,
+                    _ = operation.token().cancelled() => Err(operation.cancelled()),
+                };
+                let duration_ms = started.elapsed().as_millis() as u64;
+                match outcome {
+                    Ok(output) => {
+                        let result = StepResult::succeeded(&output, duration_ms);
+                        if let Some(var) = &step.output_var {
+                            vars.insert(var.clone(), output);
+                        }
+                        result
+                    }
+                    Err(e) => {
+                        let status = if operation.token().is_cancelled() {
+                            StepStatus::Cancelled
+                        } else {
+                            StepStatus::Failed
+                        };
+                        error!("Workflow {} step {} failed: {}", operation.id(), step.id, e);
+                        StepResult::ended(status, Some(e.to_string()), duration_ms)
+                    }
+                }
+            };
+            step_results.insert(step.id.clone(), result);
+        }
+        
+        let status = if operation.token().is_cancelled() {
+            WorkflowStatus::Cancelled
+        } else if step_results.values().all(|result| result.status == StepStatus::Succeeded) {
+            WorkflowStatus::Succeeded
+        } else {
+            WorkflowStatus::Failed
+        };
+        info!("✅ Workflow {} finished: {:?}", operation.id(), status);
+        Ok(WorkflowRun {
+            run_id: operation.id().to_string(),
+            name: workflow.name.clone(),
+            status,
+            step_results,
+            order: plan.iter().map(|step| step.id.clone()).collect(),
+            started_at,
+            finished_at: chrono::Utc::now(),
+        })
+    }
+
+    /// Sends one step's command and returns the response as plain text.
+    async fn run_workflow_step(&self, step: &WorkflowStep, vars: &HashMap<String, String>) -> Result<String> {
+        let command = workflow::render(&step.command_template, vars)?;
+        let agent_id = self.resolve_agent_id(&step.agent_id_or_alias);
+        let timeout = Duration::from_millis(step.timeout_ms.unwrap_or(DEFAULT_STEP_TIMEOUT_MS));
+        self.ask_agent(&agent_id, &command, timeout).await
+    }
+
+    /// Sends `command` to a native agent and returns its response up to the
+    /// next prompt, escape sequences removed and trimmed. A prompt still due
+    /// for earlier input, such as a startup command sent right before,
+    /// ends the response early.
+    async fn ask_agent(&self, agent_id: &str, command: &str, timeout: Duration) -> Result<String> {
+        let agent = self.get_live_agent(agent_id).await?;
+        
+        // Output from before the command is not its response
+        if !agent.skip_responses().await {
+            agent.get_last_response(timeout).await?;
+        }
+        self.send_command(agent_id, command).await?;
+        let response = agent.get_last_response(timeout).await?;
+        self.stats_history.lock().await.record_completion(agent_id);
+        self.session.write().await.record_response_time(agent_id);
+        Ok(plain_text(&response).trim().to_string())
+    }
+
+    /// Asks every agent in `agent_ids` (ids or aliases) `question` at once
+    /// and returns the answer `strategy` picks; see `hold_election`.
+    pub async fn run_election(
+        &self,
+        agent_ids: Vec<String>,
+        question: String,
+        strategy: ElectionStrategy,
+    ) -> Result<String> {
+        Ok(self.hold_election(agent_ids, question, strategy).await?.winner)
+    }
+
+    /// Like `run_election`, with every agent's response and the votes per
+    /// answer. Agents must be native ones with a prompt pattern, as their
+    /// response is read up to the next prompt. Agents that fail or don't
+    /// answer within `ELECTION_TIMEOUT_MS` are left out; the election fails
+    /// only when none answers.
+    pub async fn hold_election(
+        &self,
+        agent_ids: Vec<String>,
+        question: String,
+        strategy: ElectionStrategy,
+    ) -> Result<ElectionResult> {
+        let mut voters: Vec<String> = Vec::new();
+        for agent_id in agent_ids {
+            let agent_id = self.resolve_agent_id(&agent_id);
+            if !voters.contains(&agent_id) {
+                voters.push(agent_id);
+            }
+        }
+        if voters.is_empty() {
+            anyhow::bail!("An election needs at least one agent");
+        }
+        for agent_id in &voters {
+            self.get_live_agent(agent_id).await?;
+        }
+        info!("🗳️ Asking {} agents for a {:?} election", voters.len(), strategy);
+        
+        let timeout = Duration::from_millis(ELECTION_TIMEOUT_MS);
+        let question = question.as_str();
+        let mut pending: FuturesUnordered<_> = voters.iter()
+            .map(|agent_id| async move {
+                (agent_id, self.ask_agent(agent_id, question, timeout).await)
+            })
+            .collect();
+        let mut responses = Vec::new();
+        while let Some((agent_id, response)) = pending.next().await {
+            match response {
+                Ok(response) => responses.push((agent_id.clone(), response)),
+                Err(e) => error!("Agent {} did not vote: {}", agent_id, e),
+            }
+            if strategy == ElectionStrategy::FirstResponse && !responses.is_empty() {
+                break;
+            }
+        }
+        drop(pending);
+        
+        let result = election::tally(strategy, responses)?;
+        info!("🗳️ Election won by {:?} ({} votes)", result.winner, result.votes[&result.winner]);
+        Ok(result)
+    }
+
+    /// Sends each prompt to agents A and B at once, then asks the judge
+    /// which response is better; see `AbVerdict::parse` for how its answer
+    /// is read. All three must be native agents with a prompt pattern, and
+    /// each gets `AB_TEST_TIMEOUT_MS` to answer. A failed answer fails the
+    /// test, as does cancelling `operation`.
+    pub async fn run_ab_test(
+        &self,
+        agent_a_id: &str,
+        agent_b_id: &str,
+        prompts: Vec<String>,
+        judge_agent_id: String,
+        operation: &Operation,
+    ) -> Result<AbTestResult> {
+        let agent_a = self.resolve_agent_id(agent_a_id);
+        let agent_b = self.resolve_agent_id(agent_b_id);
+        let judge = self.resolve_agent_id(&judge_agent_id);
+        if agent_a == agent_b {
+            anyhow::bail!("An A/B test needs two different agents");
+        }
+        for agent_id in [&agent_a, &agent_b, &judge] {
+            self.get_live_agent(agent_id).await?;
+        }
+        info!("⚖️ A/B test {}: {} vs {} on {} prompts, judged by {}",
+            operation.id(), agent_a, agent_b, prompts.len(), judge);
+        
+        let timeout = Duration::from_millis(AB_TEST_TIMEOUT_MS);
+        let mut result = AbTestResult::default();
+        for prompt in prompts {
+            let round = async {
+                let (response_a, response_b) = futures::future::try_join(
+                    self.ask_after_queue(&agent_a, &prompt, timeout),
+                    self.ask_after_queue(&agent_b, &prompt, timeout),
+                ).await?;
+                let question = ab_test::judge_prompt(&response_a, &response_b);
+                let judgement = self.ask_after_queue(&judge, &question, timeout).await?;
+                let verdict = AbVerdict::parse(&judgement, &question);
+                Ok::<_, anyhow::Error>(AbTestRound { prompt: prompt.clone(), response_a, response_b, judgement, verdict })
+            };
+            let round = tokio::select! {
+                round = round => round?,
+                _ = operation.token().cancelled() => return Err(operation.cancelled()),
+            };
+            result.record(round);
+        }
+        
+        info!("⚖️ A/B test {} finished: A {}, B {}, ties {}",
+            operation.id(), result.a_wins, result.b_wins, result.ties);
+        Ok(result)
+    }
+
+    /// `ask_agent`, after sending the agent's queued commands one at a time
+    /// and waiting for each response, so `command` doesn't overtake them. A
+    /// queued command whose ask fails goes back to the front of the queue.
+    async fn ask_after_queue(&self, agent_id: &str, command: &str, timeout: Duration) -> Result<String> {
+        loop {
+            let queued = self.command_queues.get_mut(agent_id)
+                .and_then(|mut queue| queue.pop_front());
+            let Some(queued) = queued else {
+                break;
+            };
+            if let Err(e) = self.ask_agent(agent_id, &queued, timeout).await {
+                self.command_queues.entry(agent_id.to_string()).or_default().push_front(queued);
+                return Err(e);
+            }
+        }
+        self.ask_agent(agent_id, command, timeout).await
+    }
+
     pub async fn get_agent_status(&self, agent_id: &str) -> Result<Value> {
-        let agent = self.agents.get(agent_id)
+        let agent = self.get_process(agent_id)
             .ok_or_else(|| anyhow::anyhow!("Agent {} not found", agent_id))?;
         
         Ok(agent.get_status().await)
     }
 
+    /// Resets the agent's `last_error` and `error_count`. Plugin agents do
+    /// not match error patterns, so for them this only checks the agent
+    /// exists.
+    pub async fn clear_agent_error(&self, agent_id: &str) -> Result<()> {
+        match self.agents.get(agent_id).map(|entry| entry.value().clone()) {
+            Some(agent) => agent.clear_error().await,
+            None => {
+                self.get_live_process(agent_id).await?;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn list_agents(&self) -> Vec<Value> {
         let mut agents = Vec::new();
         for entry in self.agents.iter() {
             let status = entry.value().get_status().await;
             agents.push(status);
         }
+        for entry in self.plugin_agents.iter() {
+            agents.push(entry.value().get_status().await);
+        }
         agents
     }
 
+    /// Status of the running agents spawned with `headless` set.
+    pub async fn list_headless_agents(&self) -> Vec<Value> {
+        let agents: Vec<Arc<AgentProcess>> = self.agents.iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        let mut headless = Vec::new();
+        for agent in agents {
+            if agent.is_headless().await {
+                headless.push(agent.get_status().await);
+            }
+        }
+        headless
+    }
+
+    pub fn ipc_batch_stats(&self) -> Value {
+        self.ipc_bridge.batch_stats()
+    }
+
+    pub fn ipc_stats(&self) -> IpcStats {
+        self.ipc_bridge.ipc_stats()
+    }
+
+    /// Counts IPC messages a subscriber of `subscribe_ipc` skipped; see
+    /// `IpcBridge::record_lag`.
+    pub fn record_ipc_lag(&self, skipped: u64) {
+        self.ipc_bridge.record_lag(skipped);
+    }
+
+    /// Memory, descriptor and CPU usage of every native agent's process.
+    /// Plugin agents have no process of their own and are not included.
+    pub fn get_resource_snapshot(&self) -> ResourceSnapshot {
+        let processes = self.agents.iter()
+            .filter_map(|entry| entry.value().pid().map(|pid| (entry.key().clone(), pid)))
+            .collect();
+        self.resource_sampler.sample(processes)
+    }
+
+    /// Usage of one native agent's process, `None` once it has exited.
+    pub async fn get_agent_resources(&self, agent_id: &str) -> Result<Option<ProcessResources>> {
+        let agent = self.get_live_agent(&self.resolve_agent_id(agent_id)).await?;
+        Ok(agent.resource_usage().await)
+    }
+
+    /// Caps the session history kept for an agent, trimming what exceeds it.
+    pub async fn set_agent_history_limit(&self, agent_id: &str, max: usize) -> Result<()> {
+        self.session.write().await.set_history_limit(agent_id, max)
+    }
+
+    /// Trims the session history to the `keep_last` most recent records
+    /// and/or those at or after `before`. Returns how many were removed.
+    pub async fn prune_session_history(
+        &self,
+        keep_last: Option<usize>,
+        before: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> usize {
+        let mut session = self.session.write().await;
+        let mut removed = 0;
+        if let Some(cutoff) = before {
+            removed += session.prune_before(cutoff);
+        }
+        if let Some(keep_last) = keep_last {
+            removed += session.prune_history(keep_last);
+        }
+        info!("✂️ Pruned {} session history records", removed);
+        removed
+    }
+
+    /// The session's task history, optionally for one agent, oldest first.
+    pub async fn get_task_history(&self, agent_id: Option<&str>) -> Vec<TaskRecord> {
+        self.session.read().await.task_history.iter()
+            .filter(|record| agent_id.is_none_or(|id| id == record.agent_id))
+            .cloned()
+            .collect()
+    }
+
+    /// `agent_id`'s task history, oldest first, with each command's response
+    /// from the scrollback. Responses no longer there, and all of a plugin
+    /// or history-only agent's, are `None`.
+    pub async fn get_agent_conversation_history(&self, agent_id: &str) -> Result<Vec<ConversationTurn>> {
+        let agent_id = self.resolve_agent_id(agent_id);
+        let records = self.get_task_history(Some(&agent_id)).await;
+        if let Some(agent) = self.agents.get(&agent_id).map(|entry| entry.value().clone()) {
+            let scrollback = agent.get_lines(0, usize::MAX).await;
+            return Ok(conversation::pair_responses(records, &scrollback, agent.start_time().await));
+        }
+        
+        let known = self.plugin_agents.contains_key(&agent_id)
+            || self.session.read().await.agents.contains_key(&agent_id);
+        if !known {
+            return Err(AgentError::AgentNotFound { agent_id }.into());
+        }
+        Ok(records.into_iter().map(ConversationTurn::without_response).collect())
+    }
+
+    /// Rearranges an agent's history records into `new_order` (record ids),
+    /// dropping the ones left out. Other agents' records are unaffected.
+    pub async fn reorder_task_history(&self, agent_id: &str, new_order: Vec<String>) -> Result<()> {
+        let agent_id = self.resolve_agent_id(agent_id);
+        self.session.write().await.reorder_agent_history(&agent_id, &new_order)
+    }
+
+    pub async fn delete_task_record(&self, record_id: &str) -> Result<()> {
+        self.session.write().await.delete_record(record_id)?;
+        Ok(())
+    }
+
+    /// Sends `agent_id`'s recorded commands, in history order, to `target_id`
+    /// (by default the same agent). Returns how many were sent.
+    pub async fn replay_commands(&self, agent_id: &str, target_id: Option<&str>) -> Result<usize> {
+        let agent_id = self.resolve_agent_id(agent_id);
+        let target_id = target_id.map_or_else(|| agent_id.clone(), |id| self.resolve_agent_id(id));
+        let commands: Vec<String> = self.get_task_history(Some(&agent_id)).await
+            .into_iter()
+            .map(|record| record.command)
+            .collect();
+        
+        for command in &commands {
+            self.send_command(&target_id, command).await?;
+        }
+        info!("🔁 Replayed {} commands from agent {} to {}", commands.len(), agent_id, target_id);
+        Ok(commands.len())
+    }
+
+    pub async fn get_command_frequency_histogram(&self, agent_id: &str) -> HashMap<String, usize> {
+        self.session.read().await.command_frequency(Some(agent_id))
+    }
+
+    /// The `n` most frequently sent commands, for one agent or the whole
+    /// session, most frequent first.
+    pub async fn get_top_commands(&self, agent_id: Option<String>, n: usize) -> Vec<(String, usize)> {
+        let histogram = self.session.read().await.command_frequency(agent_id.as_deref());
+        let mut commands: Vec<(String, usize)> = histogram.into_iter().collect();
+        commands.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        commands.truncate(n);
+        commands
+    }
+
     pub async fn get_agent_output(&self, agent_id: &str) -> Result<Option<Vec<u8>>> {
-        let agent = self.agents.get(agent_id)
+        let agent = self.get_process(agent_id)
             .ok_or_else(|| anyhow::anyhow!("Agent {} not found", agent_id))?;
         
         Ok(agent.get_output().await)
     }
 
+    /// Makes output merged by coalescing available to `get_agent_output`
+    /// now rather than at the end of the window. Plugin agents do not
+    /// coalesce, so for them this only checks the agent exists.
+    pub async fn flush_agent_output(&self, agent_id: &str) -> Result<()> {
+        match self.agents.get(agent_id).map(|entry| entry.value().clone()) {
+            Some(agent) => agent.flush_output().await,
+            None => {
+                self.get_live_process(agent_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The configs of the running native agents, in the order they were
+    /// spawned, for `import_agents_config` to recreate the fleet. Their
+    /// ids are cleared, as for templates, so the imported agents get fresh
+    /// ones. Plugin agents keep no config and are left out.
+    pub async fn export_agents_config(&self) -> Result<Vec<AgentConfig>> {
+        let agents: Vec<Arc<AgentProcess>> = self.agents.iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        let mut configs = Vec::new();
+        for agent in agents {
+            configs.push((agent.status_dto().await.start_time, agent.config().await));
+        }
+        configs.sort_by_key(|(start_time, _)| *start_time);
+        Ok(configs.into_iter().map(|(_, config)| AgentConfig { agent_id: None, ..config }).collect())
+    }
+
+    /// Spawns an agent for each config in order and returns their ids. If
+    /// one fails to spawn, the agents spawned before it are killed again.
+    pub async fn import_agents_config(&self, configs: Vec<AgentConfig>) -> Result<Vec<String>> {
+        let mut agent_ids = Vec::new();
+        for config in configs {
+            match self.spawn_agent(config).await {
+                Ok(agent_id) => agent_ids.push(agent_id),
+                Err(e) => {
+                    for agent_id in &agent_ids {
+                        if let Err(kill_error) = self.kill_agent(agent_id).await {
+                            error!("Failed to kill imported agent {}: {}", agent_id, kill_error);
+                        }
+                    }
+                    return Err(e.context(format!("Failed to import agent {} of the fleet", agent_ids.len() + 1)));
+                }
+            }
+        }
+        info!("✅ Imported {} agents", agent_ids.len());
+        Ok(agent_ids)
+    }
+
+    /// The current session as a reusable template; see
+    /// `SessionState::to_template`.
+    pub async fn session_template(&self) -> SessionTemplate {
+        self.session.read().await.to_template()
+    }
+
+    /// Saves the current session's template as `name` in the template
+    /// directory, replacing any template of that name.
+    pub async fn save_session_template(&self, name: &str) -> Result<PathBuf> {
+        session_template::validate_name(name)?;
+        let dir = self.config.template_dir()?;
+        let path = dir.join(format!("{}.json", name));
+        let json = serde_json::to_string_pretty(&self.session_template().await)?;
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(&path, json).await?;
+        
+        info!("💾 Saved session template {} to {}", name, path.display());
+        Ok(path)
+    }
+
+    pub async fn load_session_template(&self, name: &str) -> Result<SessionTemplate> {
+        session_template::validate_name(name)?;
+        let path = self.config.template_dir()?.join(format!("{}.json", name));
+        let json = tokio::fs::read_to_string(&path).await
+            .with_context(|| format!("Cannot read session template {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid session template {}", path.display()))
+    }
+
+    /// Names of the saved templates, sorted. A missing template directory
+    /// has none.
+    pub async fn list_templates(&self) -> Result<Vec<String>> {
+        let dir = self.config.template_dir()?;
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Spawns every agent of `template` as through `import_agents_config`,
+    /// then sends each its initial prompt. Commands with redacted values
+    /// are skipped rather than replayed with the placeholder. Returns the
+    /// new agent ids.
+    pub async fn spawn_from_template(&self, template: SessionTemplate) -> Result<Vec<String>> {
+        let (configs, prompts): (Vec<AgentConfig>, Vec<Option<String>>) = template.agents.into_iter()
+            .map(|agent| (
+                AgentConfig { agent_id: None, ..agent.config.without_redacted_commands() },
+                agent.initial_prompt.filter(|prompt| !AgentManager::is_redacted(prompt)),
+            ))
+            .unzip();
+        let agent_ids = self.import_agents_config(configs).await?;
+        for (agent_id, prompt) in agent_ids.iter().zip(prompts) {
+            if let Some(prompt) = prompt {
+                if let Err(e) = self.send_command(agent_id, &prompt).await {
+                    error!("Initial prompt for agent {} failed: {}", agent_id, e);
+                }
+            }
+        }
+        Ok(agent_ids)
+    }
+
+    pub async fn export_session(&self) -> Value {
+        self.session_with_bytes_received().await.export()
+    }
+
+    /// Agent and command counts with the bytes sent and received so far.
+    pub async fn get_session_summary(&self) -> Value {
+        self.session_with_bytes_received().await.summary()
+    }
+
+    /// The session, with the output counted since the last call added to
+    /// its `total_bytes_received`.
+    async fn session_with_bytes_received(&self) -> tokio::sync::RwLockWriteGuard<'_, SessionState> {
+        let mut session = self.session.write().await;
+        session.record_bytes_received(self.bytes_received.swap(0, Ordering::Relaxed) as usize);
+        session
+    }
+
+    /// Writes the session history to the configured `session_save_path`.
+    pub async fn save_session(&self) -> Result<PathBuf> {
+        let path = self.config.session_save_path.clone()
+            .ok_or_else(|| anyhow::anyhow!("No session_save_path configured"))?;
+        let json = serde_json::to_string_pretty(&self.export_session().await)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, json).await?;
+        
+        info!("💾 Saved session to {}", path.display());
+        Ok(path)
+    }
+
+    /// Renders one agent's session metadata, commands and scrollback. With a
+    /// `path` the transcript is written there and the path is returned;
+    /// otherwise the transcript itself is. History-only agents have no
+    /// scrollback, so their transcript lists only metadata and commands.
+    /// `suppress_echo` leaves out the terminal's echo of each command.
+    pub async fn export_agent_transcript(
+        &self,
+        agent_id: &str,
+        format: TranscriptFormat,
+        path: Option<PathBuf>,
+        suppress_echo: bool,
+    ) -> Result<String> {
+        let agent_id = self.resolve_agent_id(agent_id);
+        let (output, (rows, cols)) = match self.agents.get(&agent_id).map(|a| a.value().clone()) {
+            Some(agent) => (agent.get_scrollback(None).await, agent.terminal_size().await),
+            None => (Vec::new(), (DEFAULT_PTY_ROWS, DEFAULT_PTY_COLS)),
+        };
+        
+        let rendered = {
+            let session = self.session.read().await;
+            let agent = session.agents.get(&agent_id)
+                .ok_or_else(|| anyhow::anyhow!("Agent {} not found", agent_id))?;
+            let records: Vec<TaskRecord> = session.task_history.iter()
+                .filter(|record| record.agent_id == agent_id)
+                .cloned()
+                .collect();
+            Transcript { agent, records: &records, output: &output, rows, cols, suppress_echo }
+                .render(format)
+        };
+        
+        let Some(path) = path else {
+            return Ok(rendered);
+        };
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, rendered).await?;
+        info!("💾 Exported transcript of agent {} to {}", agent_id, path.display());
+        Ok(path.display().to_string())
+    }
+
+    /// Replaces the session history with a saved one. Agents from the saved
+    /// session are history-only; currently live agents stay registered.
+    pub async fn import_session(&self, json: &str) -> Result<String> {
+        let mut imported = SessionState::import_from_str(json)?;
+        
+        let mut session = self.session.write().await;
+        for (id, agent) in session.agents.drain() {
+            if agent.live {
+                imported.agents.insert(id, agent);
+            }
+        }
+        imported.recount_history();
+        // The imported totals replace those of the current session
+        self.bytes_received.store(0, Ordering::Relaxed);
+        *session = imported;
+        
+        info!("📥 Imported session {}", session.id);
+        Ok(session.id.clone())
+    }
+
+    /// Delivers `message` from one agent to another as a command and counts
+    /// it towards the pair's routing total.
+    pub async fn route_message(&self, from_id: &str, to_id: &str, message: &str) -> Result<()> {
+        let from_id = self.resolve_agent_id(from_id);
+        let to_id = self.resolve_agent_id(to_id);
+        if self.get_process(&from_id).is_none() {
+            anyhow::bail!("Agent {} not found", from_id);
+        }
+        self.send_command(&to_id, message).await?;
+        
+        self.routing_counts
+            .entry((from_id, to_id))
+            .or_insert_with(|| AtomicUsize::new(0))
+            .fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Snapshot of routed message counts per `(from_id, to_id)` pair.
+    pub fn get_inter_agent_message_count(&self) -> HashMap<(String, String), usize> {
+        self.routing_counts
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .collect()
+    }
+
+    /// Activity since `since`: commands from the session's task history,
+    /// the rest from what this orchestrator has recorded.
+    pub async fn stats_since(&self, since: chrono::DateTime<chrono::Utc>) -> OrchestratorStats {
+        let session = self.session.read().await;
+        self.stats_history.lock().await.window(since, &session.task_history)
+    }
+
+    /// Spawns, commands, completions, errors and kills of this session in
+    /// chronological order.
+    pub async fn get_session_timeline(&self) -> Vec<TimelineEvent> {
+        let session = self.session.read().await;
+        self.stats_history.lock().await.timeline(&session.task_history)
+    }
+
+    pub fn reset_stats(&self) {
+        self.routing_counts.clear();
+        debug!("Routing stats reset");
+    }
+
     pub async fn broadcast_to_strategy(&self, message: &str) -> Result<()> {
-        // Broadcast strategic message to all agents
-        for entry in self.agents.iter() {
-            if let Err(e) = entry.value().send_command(message).await {
-                error!("Failed to broadcast to agent {}: {}", entry.key(), e);
+        // Broadcast strategic message to all agents, through `send_command`
+        // so each delivery is logged like any other command
+        let agent_ids: Vec<String> = self.agents.iter()
+            .map(|entry| entry.key().clone())
+            .chain(self.plugin_agents.iter().map(|entry| entry.key().clone()))
+            .collect();
+        for agent_id in agent_ids {
+            if let Err(e) = self.send_command(&agent_id, message).await {
+                error!("Failed to broadcast to agent {}: {}", agent_id, e);
             }
         }
         Ok(())
     }
+
+    /// Writes `data` to every live agent at once; see `broadcast_raw_to`.
+    pub async fn broadcast_raw(&self, data: Vec<u8>) -> Result<BroadcastReport> {
+        let agent_ids: Vec<String> = self.agents.iter()
+            .map(|entry| entry.key().clone())
+            .chain(self.plugin_agents.iter().map(|entry| entry.key().clone()))
+            .collect();
+        Ok(self.broadcast_raw_to(agent_ids, &data).await)
+    }
+
+    /// Writes `data` to each of `agent_ids` concurrently, like `send_raw`.
+    /// Raw input skips the command queue, so it goes out ahead of any
+    /// queued commands. One agent failing doesn't stop the others.
+    pub async fn broadcast_raw_to(&self, agent_ids: Vec<String>, data: &[u8]) -> BroadcastReport {
+        let mut targets: Vec<String> = Vec::new();
+        for agent_id in agent_ids.iter().map(|id| self.resolve_agent_id(id)) {
+            if !targets.contains(&agent_id) {
+                targets.push(agent_id);
+            }
+        }
+        let results = futures::future::join_all(targets.into_iter().map(|agent_id| async move {
+            let result = self.send_raw(&agent_id, data).await;
+            (agent_id, result)
+        })).await;
+        
+        let mut report = BroadcastReport::default();
+        for (agent_id, result) in results {
+            match result {
+                Ok(()) => {
+                    report.bytes_sent += data.len();
+                    report.sent_to.push(agent_id);
+                }
+                Err(e) => {
+                    error!("Failed to broadcast raw input to agent {}: {}", agent_id, e);
+                    report.failed.push((agent_id, e.to_string()));
+                }
+            }
+        }
+        info!("📡 Broadcast {} raw bytes to {} agents", data.len(), report.sent_to.len());
+        report
+    }
+
+    /// Brings each live agent's session `commands_sent`, and with it
+    /// `total_commands`, in line with the count the agent's process reports.
+    /// The process count is the source of truth: it only moves once a
+    /// command has been written. Returns the corrected agents with both
+    /// counts.
+    pub async fn reconcile_command_counts(&self) -> Value {
+        let processes: Vec<(String, Arc<dyn AgentProcessTrait>)> = self.agents.iter()
+            .map(|entry| {
+                let process: Arc<dyn AgentProcessTrait> = entry.value().clone();
+                (entry.key().clone(), process)
+            })
+            .chain(self.plugin_agents.iter().map(|entry| (entry.key().clone(), entry.value().clone())))
+            .collect();
+        let mut reported = Vec::new();
+        for (agent_id, process) in processes {
+            // Plugins are not required to report a count
+            if let Some(sent) = process.get_status().await["commands_sent"].as_u64() {
+                reported.push((agent_id, sent as usize));
+            }
+        }
+        
+        let mut session = self.session.write().await;
+        let mut corrected = serde_json::Map::new();
+        for (agent_id, sent) in reported {
+            if let Some(previous) = session.reconcile_commands_sent(&agent_id, sent) {
+                info!("🔁 Reconciled commands sent to agent {}: {} -> {}", agent_id, previous, sent);
+                corrected.insert(agent_id, serde_json::json!({ "session": previous, "process": sent }));
+            }
+        }
+        serde_json::json!({
+            "corrected": corrected,
+            "total_commands": session.total_commands,
+        })
+    }
+}
+
+/// Publishes `event`; having no subscribers is not an error.
+fn publish_event(ipc_bridge: &IpcBridge, event: AgentEvent) {
+    if let Err(e) = ipc_bridge.send_event(event) {
+        debug!("No IPC subscribers for agent event: {}", e);
+    }
+}
+
+/// Publishes a native agent's output as `AgentEvent::Output` until its
+/// process exits, then `Exited` and the final status. Output volume and
+/// errors also go to `stats`, and output volume to `bytes_received`,
+/// counted from the agent's meter so chunks skipped on lag count too. A
+/// process `reset_agent` replaced in `agents` leaves its exit to the new
+/// one.
+async fn forward_agent_events(
+    agent: Arc<AgentProcess>,
+    agents: Arc<DashMap<String, Arc<AgentProcess>>>,
+    ipc_bridge: Arc<IpcBridge>,
+    stats: Arc<tokio::sync::Mutex<StatsHistory>>,
+    bytes_received: Arc<AtomicU64>,
+) {
+    let agent_id = agent.id.clone();
+    let mut counted = 0;
+    let mut count_received = || {
+        let total = agent.bytes_received();
+        let received = total - counted;
+        bytes_received.fetch_add(received, Ordering::Relaxed);
+        counted = total;
+        received
+    };
+    let mut output = agent.subscribe_output();
+    let mut errors = agent.subscribe_errors();
+    let exit = agent.wait();
+    tokio::pin!(exit);
+    
+    let code = loop {
+        tokio::select! {
+            Ok(message) = errors.recv() => {
+                stats.lock().await.record_error(&agent_id, &message);
+                publish_event(&ipc_bridge, AgentEvent::Error {
+                    agent_id: agent_id.clone(),
+                    message,
+                });
+            },
+            received = output.recv() => match received {
+                Ok(bytes) => {
+                    let received = count_received();
+                    stats.lock().await.record_output(received);
+                    publish_event(&ipc_bridge, AgentEvent::Output {
+                        agent_id: agent_id.clone(),
+                        bytes,
+                    });
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    let received = count_received();
+                    stats.lock().await.record_output(received);
+                    debug!("Event forwarder for agent {} skipped {} chunks", agent_id, skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+            },
+            exited = &mut exit => match exited {
+                Ok(ExitStatus::Exited { code }) => break code,
+                Ok(ExitStatus::Timeout | ExitStatus::Unsupported) => return,
+                Err(e) => {
+                    let message = format!("Failed to wait for process: {}", e);
+                    stats.lock().await.record_error(&agent_id, &message);
+                    publish_event(&ipc_bridge, AgentEvent::Error {
+                        agent_id: agent_id.clone(),
+                        message,
+                    });
+                    return;
+                }
+            },
+        }
+    };
+    // Output no listener was sent, like filtered lines, before the exit
+    let received = count_received();
+    stats.lock().await.record_output(received);
+    if agents.get(&agent_id).is_some_and(|current| !Arc::ptr_eq(current.value(), &agent)) {
+        return;
+    }
+    publish_event(&ipc_bridge, AgentEvent::Exited { agent_id: agent_id.clone(), code });
+    publish_event(&ipc_bridge, AgentEvent::StatusChanged {
+        agent_id,
+        status: agent.status_dto().await,
+    });
+}
+
+/// Emits a `response_timeout` event, and counts it in the agent's status,
+/// if no output arrives within `timeout` of a command. Each command gets
+/// its own watch; output or the agent exiting ends it. On a terminal, the
+/// lines echoing `echo`, the command as sent, don't count as output.
+async fn watch_response(
+    agent: Arc<AgentProcess>,
+    timeout: Duration,
+    mut output: tokio::sync::broadcast::Receiver<Vec<u8>>,
+    echo: Option<String>,
+    ipc_bridge: Arc<IpcBridge>,
+) {
+    let started = Instant::now();
+    let echo = echo.unwrap_or_default();
+    let echo_lines: Vec<&str> = echo.lines().filter(|line| !line.trim().is_empty()).collect();
+    let mut echoed = 0;
+    let mut lines = LineSplitter::default();
+    let responded = async {
+        loop {
+            let chunk = match output.recv().await {
+                Ok(chunk) => chunk,
+                // Lagging also means output arrived
+                Err(_) => return,
+            };
+            for line in lines.lines(&chunk) {
+                match echo_lines.get(echoed) {
+                    Some(sent) if patterns::is_echo(&line, sent) => echoed += 1,
+                    _ => return,
+                }
+            }
+            if echoed == echo_lines.len() && lines.has_partial_text() {
+                return;
+            }
+        }
+    };
+    tokio::select! {
+        _ = responded => {}
+        _ = agent.wait() => {}
+        _ = tokio::time::sleep(timeout) => {
+            agent.record_response_timeout().await;
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            info!("⏳ Agent {} has not responded after {}ms", agent.id, elapsed_ms);
+            let event = IpcMessage {
+                agent_id: agent.id.clone(),
+                message_type: MessageType::SystemEvent,
+                payload: serde_json::json!({ "event": "response_timeout", "elapsed_ms": elapsed_ms }),
+                timestamp: chrono::Utc::now(),
+                batch_id: None,
+                correlation_id: None,
+            };
+            if let Err(e) = ipc_bridge.send_message(event) {
+                debug!("No IPC subscribers for response_timeout event: {}", e);
+            }
+        }
+    }
+}
+
+/// Forwards one pipe's lines until either agent exits, the target stops
+/// accepting commands, or the orchestrator goes away; then removes the pipe.
+async fn run_pipe(
+    orchestrator: Weak<AgentOrchestrator>,
+    info: PipeHandle,
+    filter: Option<Regex>,
+    mut output: tokio::sync::broadcast::Receiver<Vec<u8>>,
+    from: Arc<AgentProcess>,
+    to: Option<Arc<AgentProcess>>,
+) {
+    let from_exit = from.wait();
+    tokio::pin!(from_exit);
+    let to_exit = async {
+        match &to {
+            Some(to) => to.wait().await,
+            // Plugin targets are noticed when queueing fails
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(to_exit);
+    
+    let mut line = Vec::new();
+    let mut window_start = tokio::time::Instant::now();
+    let mut forwarded = 0;
+    loop {
+        let chunk = tokio::select! {
+            received = output.recv() => match received {
+                Ok(chunk) => chunk,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Pipe {} skipped {} chunks", info.id, skipped);
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            },
+            _ = &mut from_exit => break,
+            _ = &mut to_exit => break,
+        };
+        let Some(orchestrator) = orchestrator.upgrade() else {
+            return;
+        };
+        
+        line.extend_from_slice(&chunk);
+        while let Some(end) = line.iter().position(|&b| b == b'\n') {
+            let complete: Vec<u8> = line.drain(..=end).collect();
+            let Some(command) = pipe::piped_line(&complete, filter.as_ref()) else {
+                continue;
+            };
+            if window_start.elapsed() >= PIPE_RATE_WINDOW {
+                window_start = tokio::time::Instant::now();
+                forwarded = 0;
+            }
+            if forwarded >= MAX_PIPED_LINES_PER_WINDOW {
+                debug!("Pipe {} over its rate cap, dropped a line", info.id);
+                continue;
+            }
+            forwarded += 1;
+            if let Err(e) = orchestrator.queue_command(&info.to_id, command).await {
+                error!("Pipe {} into agent {} failed: {}", info.id, info.to_id, e);
+                orchestrator.pipes.remove(&info.id);
+                return;
+            }
+        }
+        if line.len() > MAX_PIPED_LINE_BYTES {
+            line.clear();
+        }
+    }
+    
+    if let Some(orchestrator) = orchestrator.upgrade() {
+        info!("🔗 Pipe {} closed: an agent exited", info.id);
+        orchestrator.pipes.remove(&info.id);
+    }
+}
+
+/// Dispatches one schedule until it completes, is cancelled, or the
+/// orchestrator goes away.
+async fn run_schedule(orchestrator: Weak<AgentOrchestrator>, scheduled: ScheduledCommand) {
+    let interval = match scheduled.schedule {
+        Schedule::Once(at) => {
+            tokio::time::sleep((at - chrono::Utc::now()).to_std().unwrap_or_default()).await;
+            None
+        }
+        Schedule::Every(interval) => Some(interval),
+    };
+    let mut ticker = interval.map(|interval| {
+        tokio::time::interval_at(tokio::time::Instant::now() + interval, interval)
+    });
+    
+    loop {
+        if let Some(ticker) = ticker.as_mut() {
+            ticker.tick().await;
+        }
+        let Some(orchestrator) = orchestrator.upgrade() else {
+            return;
+        };
+        if let Err(e) = orchestrator
+            .dispatch_command(&scheduled.agent_id, &scheduled.command, true)
+            .await
+        {
+            error!("Scheduled command {} for agent {} failed: {}", scheduled.id, scheduled.agent_id, e);
+        }
+        if ticker.is_none() {
+            orchestrator.schedules.remove(&scheduled.id);
+            return;
+        }
+    }
 }
 
 impl Drop for AgentOrchestrator {
     fn drop(&mut self) {
         info!("Shutting down Agent Orchestrator...");
+        for entry in self.schedules.iter() {
+            entry.value().task.abort();
+        }
         // Agents will be cleaned up by their Drop implementations
     }
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+// Output Observers - Compiled-in hooks on agent output and IPC events
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use super::ipc_bridge::{IpcMessage, MessageType};
+
+/// Custom Rust logic run on agent output, e.g. forwarding lines to a
+/// database or raising a notification on a keyword. Unlike regex hooks,
+/// observers are compiled in and registered with
+/// `AgentOrchestrator::add_output_observer`.
+///
+/// Observers are shared across threads, hence `Send + Sync`. They are
+/// called inline: `on_line` from each agent's blocking output reader and
+/// `on_event` while the IPC bridge sends, so they must return quickly and
+/// never block; hand slow work to a thread or channel. They must not
+/// panic either, as release builds abort on panic.
+pub trait OutputObserver: Send + Sync {
+    /// A complete line of a native agent's output, escape sequences and
+    /// the trailing `\r` removed, after the agent's output filter.
+    fn on_line(&self, _agent_id: &str, _line: &str) {}
+
+    /// Every message published on the IPC bridge.
+    fn on_event(&self, _ev: &IpcMessage) {}
+}
+
+/// The observers of one orchestrator, shared by its IPC bridge and the
+/// output readers of the agents it spawns. Observers added later also see
+/// agents already running.
+#[derive(Clone, Default)]
+pub struct OutputObservers {
+    observers: Arc<RwLock<Vec<Arc<dyn OutputObserver>>>>,
+}
+
+impl OutputObservers {
+    pub fn add(&self, observer: Arc<dyn OutputObserver>) {
+        self.observers.write().unwrap_or_else(|e| e.into_inner()).push(observer);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+
+    pub(crate) fn line(&self, agent_id: &str, line: &str) {
+        for observer in self.observers.read().unwrap_or_else(|e| e.into_inner()).iter() {
+            observer.on_line(agent_id, line);
+        }
+    }
+
+    pub(crate) fn event(&self, ev: &IpcMessage) {
+        for observer in self.observers.read().unwrap_or_else(|e| e.into_inner()).iter() {
+            observer.on_event(ev);
+        }
+    }
+}
+
+/// Shortest time between two notifications for the same agent, so an
+/// agent printing a burst of errors raises one.
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Raises a desktop notification for agent errors, such as output lines
+/// matching an error pattern. Uses `notify-send` on Linux and `osascript`
+/// on macOS; elsewhere errors are only logged.
+#[derive(Default)]
+pub struct ErrorNotifier {
+    last_notified: Mutex<HashMap<String, Instant>>,
+}
+
+impl ErrorNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn notify(&self, agent_id: &str, message: &str) {
+        let now = Instant::now();
+        let mut last_notified = self.last_notified.lock().unwrap_or_else(|e| e.into_inner());
+        if last_notified.get(agent_id).is_some_and(|&at| now.duration_since(at) < NOTIFY_INTERVAL) {
+            return;
+        }
+        last_notified.insert(agent_id.to_string(), now);
+        drop(last_notified);
+
+        let title = format!("Agent {} error", agent_id);
+        let Some(mut command) = notification_command(&title, message) else {
+            debug!("No desktop notifications on this platform: {}: {}", title, message);
+            return;
+        };
+        match command.spawn() {
+            // Reaped off the caller's thread, which must not block
+            Ok(mut child) => {
+                std::thread::spawn(move || child.wait());
+            }
+            Err(e) => warn!("Failed to raise notification for agent {}: {}", agent_id, e),
+        }
+    }
+}
+
+impl OutputObserver for ErrorNotifier {
+    fn on_event(&self, ev: &IpcMessage) {
+        if ev.message_type != MessageType::Error {
+            return;
+        }
+        if let Some(message) = ev.payload["message"].as_str() {
+            self.notify(&ev.agent_id, message);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn notification_command(title: &str, body: &str) -> Option<Command> {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name", "Conductor Max", title, body]);
+    Some(command)
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, body: &str) -> Option<Command> {
+    let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut command = Command::new("osascript");
+    command.args(["-e", &format!("display notification {} with title {}", quote(body), quote(title))]);
+    Some(command)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn notification_command(_title: &str, _body: &str) -> Option<Command> {
+    None
+}
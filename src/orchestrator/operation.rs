@@ -0,0 +1,55 @@
+// Operations - Cancellable handles for long-running orchestrator calls
+use anyhow::Result;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::error::OperationError;
+
+/// A registered long-running call. Cancelled through
+/// `AgentOrchestrator::cancel_operation`; unregistered when dropped.
+pub struct Operation {
+    id: String,
+    token: CancellationToken,
+    registry: Arc<DashMap<String, CancellationToken>>,
+}
+
+impl Operation {
+    /// Registers an operation under `id`, or a fresh id if none is given.
+    /// Callers pick the id up front so they can cancel before the call
+    /// returns.
+    pub(crate) fn register(
+        registry: &Arc<DashMap<String, CancellationToken>>,
+        id: Option<String>,
+    ) -> Result<Self> {
+        let id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let token = CancellationToken::new();
+        match registry.entry(id.clone()) {
+            Entry::Occupied(_) => anyhow::bail!("Operation {} is already running", id),
+            Entry::Vacant(slot) => {
+                slot.insert(token.clone());
+            }
+        }
+        Ok(Self { id, token, registry: registry.clone() })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub(crate) fn cancelled(&self) -> anyhow::Error {
+        OperationError::Cancelled { operation_id: self.id.clone() }.into()
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        self.registry.remove(&self.id);
+    }
+}
@@ -0,0 +1,242 @@
+// Output Buffer - Line-oriented scrollback retained for each agent
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::ops::RangeInclusive;
+
+/// Lines kept per agent before the oldest are evicted.
+pub const DEFAULT_SCROLLBACK_LINES: usize = 10_000;
+
+/// What `OutputBuffer::redact_lines` leaves in place of a rejected line.
+pub const REDACTED_LINE: &str = "[redacted]";
+
+/// Scrollback assembled from raw PTY chunks. Only the stored copy is shaped
+/// here; the byte stream forwarded to the terminal emulator is untouched.
+#[derive(Debug)]
+pub struct OutputBuffer {
+    lines: VecDeque<String>,
+    /// Text after the last line terminator.
+    partial: String,
+    /// A `\r` was seen at the end of a chunk; whether it starts `\r\n` or an
+    /// in-place update depends on the next byte.
+    pending_cr: bool,
+    /// The last stored line was terminated by a bare `\r`.
+    last_was_cr: bool,
+    /// Trailing bytes of an incomplete UTF-8 sequence.
+    utf8_carry: Vec<u8>,
+    /// Lines dropped from the front, so line numbers stay stable as the
+    /// buffer trims.
+    evicted: usize,
+    max_lines: usize,
+    collapse_cr_updates: bool,
+}
+
+impl OutputBuffer {
+    pub fn new(max_lines: usize, collapse_cr_updates: bool) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            partial: String::new(),
+            pending_cr: false,
+            last_was_cr: false,
+            utf8_carry: Vec::new(),
+            evicted: 0,
+            max_lines: max_lines.max(1),
+            collapse_cr_updates,
+        }
+    }
+
+    pub fn set_collapse_cr_updates(&mut self, enabled: bool) {
+        self.collapse_cr_updates = enabled;
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        let mut bytes = std::mem::take(&mut self.utf8_carry);
+        bytes.extend_from_slice(data);
+
+        let text = match std::str::from_utf8(&bytes) {
+            Ok(text) => text.to_string(),
+            Err(e) if e.error_len().is_none() => {
+                // Incomplete sequence at the end; keep it for the next chunk
+                let valid = e.valid_up_to();
+                self.utf8_carry = bytes[valid..].to_vec();
+                String::from_utf8_lossy(&bytes[..valid]).into_owned()
+            }
+            Err(_) => String::from_utf8_lossy(&bytes).into_owned(),
+        };
+
+        for c in text.chars() {
+            if self.pending_cr {
+                self.pending_cr = false;
+                if c == '\n' {
+                    self.finish_line(false);
+                    continue;
+                }
+                self.finish_line(true);
+            }
+            match c {
+                '\n' => self.finish_line(false),
+                '\r' => self.pending_cr = true,
+                _ => self.partial.push(c),
+            }
+        }
+    }
+
+    /// Stores the current partial line. `in_place` marks a bare `\r`, i.e.
+    /// the terminal will overwrite this line with whatever comes next.
+    fn finish_line(&mut self, in_place: bool) {
+        let line = std::mem::take(&mut self.partial);
+        if in_place && line.is_empty() {
+            // A bare `\r` at the start of a line just rewinds the cursor
+            return;
+        }
+
+        if self.collapse_cr_updates && self.last_was_cr {
+            if let Some(last) = self.lines.back_mut() {
+                *last = line;
+                self.last_was_cr = in_place;
+                return;
+            }
+        }
+
+        self.lines.push_back(line);
+        self.last_was_cr = in_place;
+        while self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+            self.evicted += 1;
+        }
+    }
+
+    /// The last `n` complete lines, oldest first.
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let skip = self.lines.len().saturating_sub(n);
+        self.lines.iter().skip(skip).cloned().collect()
+    }
+
+    /// The last `rows` lines as a terminal would show them: complete lines
+    /// followed by the unterminated one, if any.
+    pub fn screen(&self, rows: usize) -> Vec<String> {
+        if self.partial.is_empty() {
+            return self.tail(rows);
+        }
+        let mut lines = self.tail(rows.saturating_sub(1));
+        lines.push(self.partial.clone());
+        lines
+    }
+
+    /// The unterminated line, numbered `total_lines` in `range`.
+    pub fn partial(&self) -> &str {
+        &self.partial
+    }
+
+    /// Replaces the complete lines `keep` rejects with `REDACTED_LINE` and
+    /// returns how many were replaced. Every line keeps its number, so line
+    /// cursors into the buffer stay valid.
+    pub fn redact_lines(&mut self, keep: impl Fn(&str) -> bool) -> usize {
+        let mut redacted = 0;
+        for line in self.lines.iter_mut().filter(|line| *line != REDACTED_LINE) {
+            if !keep(line) {
+                *line = REDACTED_LINE.to_string();
+                redacted += 1;
+            }
+        }
+        redacted
+    }
+
+    /// Lines `start..end`, numbered from the first line the agent ever
+    /// produced. The range is clamped to the lines still retained.
+    pub fn range(&self, start: usize, end: usize) -> OutputRange {
+        let total_lines = self.evicted + self.lines.len();
+        let start = start.clamp(self.evicted, total_lines);
+        let end = end.clamp(start, total_lines);
+        OutputRange {
+            start,
+            lines: self.lines
+                .range(start - self.evicted..end - self.evicted)
+                .cloned()
+                .collect(),
+            first_line: self.evicted,
+            total_lines,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputRange {
+    /// Line number of `lines[0]` after clamping.
+    pub start: usize,
+    pub lines: Vec<String>,
+    /// Oldest line number still retained.
+    pub first_line: usize,
+    /// Lines produced so far, including evicted ones.
+    pub total_lines: usize,
+}
+
+/// Terminal state captured for repainting a view later. Without a VT grid the
+/// state is the assembled scrollback, so restoring replays it line by line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalSnapshot {
+    pub lines: Vec<String>,
+    pub rows: u16,
+    pub cols: u16,
+    pub taken_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl TerminalSnapshot {
+    /// Bytes that clear the terminal and repaint the captured lines.
+    pub fn replay_bytes(&self) -> Vec<u8> {
+        let mut replay = b"\x1b[H\x1b[2J".to_vec();
+        replay.extend_from_slice(self.lines.join("\r\n").as_bytes());
+        replay
+    }
+}
+
+/// Distinct characters reported in `OutputStats::char_frequencies`.
+const MAX_TRACKED_CHARS: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputStats {
+    pub total_chars: u64,
+    pub total_lines: u64,
+    pub avg_line_length: f64,
+    /// The most frequent characters (at most `MAX_TRACKED_CHARS`).
+    pub char_frequencies: HashMap<char, u64>,
+    /// Line counts for lengths 0-9, 10-79, 80-199 and 200+ characters.
+    pub line_length_buckets: Vec<(RangeInclusive<usize>, u64)>,
+}
+
+impl OutputStats {
+    pub fn from_lines(lines: &[String]) -> Self {
+        let mut buckets: Vec<(RangeInclusive<usize>, u64)> = vec![
+            (0..=9, 0),
+            (10..=79, 0),
+            (80..=199, 0),
+            (200..=usize::MAX, 0),
+        ];
+        let mut frequencies: HashMap<char, u64> = HashMap::new();
+        let mut total_chars = 0u64;
+
+        for line in lines {
+            let mut length = 0usize;
+            for c in line.chars() {
+                *frequencies.entry(c).or_insert(0) += 1;
+                length += 1;
+            }
+            total_chars += length as u64;
+            if let Some((_, count)) = buckets.iter_mut().find(|(range, _)| range.contains(&length)) {
+                *count += 1;
+            }
+        }
+
+        let mut ranked: Vec<(char, u64)> = frequencies.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(MAX_TRACKED_CHARS);
+
+        let total_lines = lines.len() as u64;
+        Self {
+            total_chars,
+            total_lines,
+            avg_line_length: if total_lines == 0 { 0.0 } else { total_chars as f64 / total_lines as f64 },
+            char_frequencies: ranked.into_iter().collect(),
+            line_length_buckets: buckets,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Output Filter - Drops unwanted agent output lines before they are stored
+use regex::Regex;
+use std::sync::Arc;
+
+/// Decides whether a line of output is kept. Receives the line without its
+/// `\n` or `\r` terminator, ANSI sequences included.
+pub type LinePredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Longest unterminated line held while waiting for its end; beyond this
+/// it is judged as if it had ended.
+const MAX_FILTER_LINE_BYTES: usize = 64 * 1024;
+
+pub struct FilterFactory;
+
+impl FilterFactory {
+    pub fn exclude_matching(pattern: Regex) -> LinePredicate {
+        Arc::new(move |line| !pattern.is_match(line))
+    }
+
+    pub fn include_only(pattern: Regex) -> LinePredicate {
+        Arc::new(move |line| pattern.is_match(line))
+    }
+
+    pub fn and(a: LinePredicate, b: LinePredicate) -> LinePredicate {
+        Arc::new(move |line| a(line) && b(line))
+    }
+
+    pub fn or(a: LinePredicate, b: LinePredicate) -> LinePredicate {
+        Arc::new(move |line| a(line) || b(line))
+    }
+}
+
+/// Filtering state of one output stream. `\n`, `\r\n` and a lone `\r` each
+/// end a line, so spinner and progress redraws are judged one frame at a
+/// time.
+///
+/// Output that doesn't end a line is held until it does, so a predicate
+/// always sees the whole line and a redaction can't leak its start. On a
+/// filtered stream a prompt therefore shows up once its line ends.
+#[derive(Default)]
+pub(crate) struct FilterStage {
+    line: Vec<u8>,
+    /// Set after a line ended with `\r` at the end of a chunk, to whether it
+    /// was kept: a `\n` starting the next chunk completes its `\r\n`.
+    after_cr: Option<bool>,
+}
+
+impl FilterStage {
+    /// Returns the part of `data` to keep. Without a predicate everything
+    /// passes unchanged, after any line held back so far.
+    pub fn apply(&mut self, data: &[u8], predicate: Option<&LinePredicate>) -> Vec<u8> {
+        let Some(predicate) = predicate else {
+            self.after_cr = None;
+            let mut kept = std::mem::take(&mut self.line);
+            kept.extend_from_slice(data);
+            return kept;
+        };
+        
+        let mut kept = Vec::new();
+        let mut rest = data;
+        if let Some(line_kept) = self.after_cr.take() {
+            if let Some(after) = rest.strip_prefix(b"\n") {
+                if line_kept {
+                    kept.push(b'\n');
+                }
+                rest = after;
+            }
+        }
+        while !rest.is_empty() {
+            let Some(end) = rest.iter().position(|&b| b == b'\n' || b == b'\r') else {
+                self.line.extend_from_slice(rest);
+                if self.line.len() >= MAX_FILTER_LINE_BYTES {
+                    self.judge_line(predicate, &mut kept);
+                }
+                break;
+            };
+            let mut len = end + 1;
+            if rest[end] == b'\r' && rest.get(len) == Some(&b'\n') {
+                len += 1;
+            }
+            self.line.extend_from_slice(&rest[..len]);
+            rest = &rest[len..];
+            
+            let lone_cr = self.line.ends_with(b"\r");
+            let line_kept = self.judge_line(predicate, &mut kept);
+            if lone_cr && rest.is_empty() {
+                self.after_cr = Some(line_kept);
+            }
+        }
+        kept
+    }
+    
+    /// Forwards the held line into `kept` if `predicate` passes it, then
+    /// starts a new one.
+    fn judge_line(&mut self, predicate: &LinePredicate, kept: &mut Vec<u8>) -> bool {
+        let text = String::from_utf8_lossy(&self.line);
+        let keep = predicate(text.trim_end_matches(['\n', '\r']));
+        if keep {
+            kept.extend_from_slice(&self.line);
+        }
+        self.line.clear();
+        keep
+    }
+}
@@ -0,0 +1,68 @@
+// Output Meter - Lock-free byte totals and rolling throughput per agent
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Length of the sliding window used for `rate_bps`, in seconds.
+const WINDOW_SECS: usize = 5;
+
+/// Counts output bytes in one-second slots so the PTY reader never takes a
+/// lock. Each slot remembers which second it belongs to and is recycled when
+/// the reader moves past it.
+pub struct OutputMeter {
+    started: Instant,
+    total: AtomicU64,
+    slot_bytes: [AtomicU64; WINDOW_SECS],
+    slot_secs: [AtomicU64; WINDOW_SECS],
+}
+
+impl Default for OutputMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputMeter {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            total: AtomicU64::new(0),
+            slot_bytes: Default::default(),
+            slot_secs: Default::default(),
+        }
+    }
+
+    pub fn record(&self, bytes: usize) {
+        let bytes = bytes as u64;
+        self.total.fetch_add(bytes, Ordering::Relaxed);
+
+        let now = self.started.elapsed().as_secs();
+        let slot = now as usize % WINDOW_SECS;
+        let previous = self.slot_secs[slot].swap(now, Ordering::AcqRel);
+        if previous == now {
+            self.slot_bytes[slot].fetch_add(bytes, Ordering::Relaxed);
+        } else {
+            self.slot_bytes[slot].store(bytes, Ordering::Relaxed);
+        }
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Average output rate over the last `WINDOW_SECS` seconds (or since
+    /// spawn, if the agent is younger than that).
+    pub fn rate_bps(&self) -> f64 {
+        let elapsed = self.started.elapsed();
+        let now = elapsed.as_secs();
+        let in_window: u64 = (0..WINDOW_SECS)
+            .filter(|&slot| {
+                let sec = self.slot_secs[slot].load(Ordering::Acquire);
+                sec <= now && now - sec < WINDOW_SECS as u64
+            })
+            .map(|slot| self.slot_bytes[slot].load(Ordering::Relaxed))
+            .sum();
+
+        let window = elapsed.as_secs_f64().clamp(1.0, WINDOW_SECS as f64);
+        in_window as f64 / window
+    }
+}
@@ -0,0 +1,229 @@
+// Agent Patterns - What readiness, an input prompt and a failure look like per agent type
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use super::agent_manager::AgentType;
+use super::colorize::plain_text;
+
+/// Markers of a failure in any agent's output: an `ERROR` log level, Python
+/// tracebacks, Rust panics, JavaScript exceptions and reported non-zero
+/// exits.
+const DEFAULT_ERROR_PATTERNS: &[&str] = &[
+    r"\bERROR\b",
+    r"^Traceback \(most recent call last\):",
+    r"^thread '.*' panicked at",
+    r"^(Uncaught )?[A-Z]\w*Error: ",
+    r"(?i)\bexit(ed)?( with)? (code|status) [1-9]",
+];
+
+/// Longest line a `LineSplitter` assembles; the rest of a longer line is
+/// dropped.
+const MAX_SPLIT_LINE_BYTES: usize = 64 * 1024;
+
+/// Most input lines a `PendingEchoes` waits on; older ones are forgotten,
+/// as for commands a full-screen program never echoed.
+const MAX_PENDING_ECHOES: usize = 32;
+
+/// Regexes matched against an agent's output, one line at a time with
+/// escape sequences removed.
+/// `None` means the agent type has no known pattern.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentPatterns {
+    /// Output showing the agent has finished starting up.
+    #[serde(default)]
+    pub readiness: Option<String>,
+    /// Output showing the agent is waiting for input.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Output lines that mark a failure, reported as the agent's
+    /// `last_error`. An empty list turns error matching off.
+    #[serde(default)]
+    pub errors: Option<Vec<String>>,
+}
+
+/// `AgentPatterns` with the regexes compiled.
+#[derive(Debug, Clone)]
+pub struct CompiledPatterns {
+    pub readiness: Option<Regex>,
+    pub prompt: Option<Regex>,
+    pub errors: Vec<Regex>,
+}
+
+impl AgentPatterns {
+    /// Fills the patterns missing here from `defaults`.
+    pub fn or(self, defaults: AgentPatterns) -> AgentPatterns {
+        AgentPatterns {
+            readiness: self.readiness.or(defaults.readiness),
+            prompt: self.prompt.or(defaults.prompt),
+            errors: self.errors.or(defaults.errors),
+        }
+    }
+
+    pub fn compile(&self) -> Result<CompiledPatterns> {
+        let compile = |name: &str, pattern: &Option<String>| -> Result<Option<Regex>> {
+            pattern.as_deref()
+                .map(|p| Regex::new(p).with_context(|| format!("Invalid {} pattern {:?}", name, p)))
+                .transpose()
+        };
+        Ok(CompiledPatterns {
+            readiness: compile("readiness", &self.readiness)?,
+            prompt: compile("prompt", &self.prompt)?,
+            errors: self.errors.iter().flatten()
+                .map(|p| Regex::new(p).with_context(|| format!("Invalid error pattern {:?}", p)))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl AgentType {
+    /// Built-in patterns, matched against the CLIs' interactive UIs. They
+    /// track UI text, not a stable interface, so re-check them when a CLI
+    /// redraws its input area:
+    ///
+    /// - `claude`: once the input box accepts text, its footer shows the
+    ///   `? for shortcuts` hint; the box itself starts with `> `.
+    /// - `gemini`: the empty input box shows the placeholder
+    ///   `Type your message or @path/to/file`, after a `> `.
+    /// - `bash`: the default `$ ` / `# ` prompt ending the output; a bash
+    ///   agent is ready when it first shows it.
+    /// - Plugin agents: none; plugins can't be assumed to draw a prompt.
+    ///
+    /// Native agents share `DEFAULT_ERROR_PATTERNS`.
+    pub fn default_patterns(&self) -> AgentPatterns {
+        let patterns = |readiness: &str, prompt: &str| AgentPatterns {
+            readiness: Some(readiness.to_string()),
+            prompt: Some(prompt.to_string()),
+            errors: Some(DEFAULT_ERROR_PATTERNS.iter().map(|p| p.to_string()).collect()),
+        };
+        match self {
+            AgentType::Claude => patterns(r"\? for shortcuts", r"(?m)^[│|]? *> "),
+            AgentType::Gemini => patterns(r"Type your message", r"(?m)^[│|]? *> "),
+            AgentType::Bash => patterns(r"[$#] $", r"[$#] $"),
+            AgentType::Plugin(_) => AgentPatterns::default(),
+        }
+    }
+}
+
+/// Assembles one output stream into lines, with escape sequences and
+/// surrounding `\r`s removed.
+#[derive(Default)]
+pub(crate) struct LineSplitter {
+    partial: Vec<u8>,
+}
+
+impl LineSplitter {
+    /// The lines completed by `data`.
+    pub fn lines(&mut self, data: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut rest = data;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            self.extend_partial(&rest[..end]);
+            let line = plain_text(&String::from_utf8_lossy(&self.partial));
+            lines.push(line.trim_matches('\r').to_string());
+            self.partial.clear();
+            rest = &rest[end + 1..];
+        }
+        self.extend_partial(rest);
+        lines
+    }
+
+    /// Whether text other than escape sequences and whitespace is waiting
+    /// for the end of its line.
+    pub fn has_partial_text(&self) -> bool {
+        !plain_text(&String::from_utf8_lossy(&self.partial)).trim().is_empty()
+    }
+
+    fn extend_partial(&mut self, bytes: &[u8]) {
+        let room = MAX_SPLIT_LINE_BYTES.saturating_sub(self.partial.len());
+        self.partial.extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+}
+
+/// Whether `line` is a terminal's echo of one line of input, `sent`: it
+/// ends with it, so a prompt in front of it doesn't matter, and it starts
+/// the line or follows whitespace or a prompt character (`$`, `#`, `>`),
+/// so output that merely ends with the same letters isn't taken for it.
+pub(crate) fn is_echo(line: &str, sent: &str) -> bool {
+    let sent = sent.trim();
+    let Some(before) = line.trim_end().strip_suffix(sent) else {
+        return false;
+    };
+    !sent.is_empty()
+        && before.chars().next_back().is_none_or(|c| c.is_whitespace() || matches!(c, '$' | '#' | '>'))
+}
+
+/// Input lines sent to a terminal whose echo hasn't come back yet, oldest
+/// first, and those echoed bare whose readline redraw is still to come.
+#[derive(Clone, Default)]
+pub(crate) struct PendingEchoes {
+    state: Arc<Mutex<(VecDeque<String>, VecDeque<String>)>>,
+}
+
+impl PendingEchoes {
+    pub fn push(&self, input: &str) {
+        let (pending, _) = &mut *self.lock();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            if pending.len() == MAX_PENDING_ECHOES {
+                pending.pop_front();
+            }
+            pending.push_back(line.to_string());
+        }
+    }
+
+    /// Whether `line` echoes pending input. That input, and any sent
+    /// before it, is no longer pending. Input echoed bare, without a
+    /// prompt, is echoed once more, as readline redraws input the terminal
+    /// echoed before the prompt appeared; the first later line repeating it
+    /// counts too. Output that ends with the input after that, or after an
+    /// echo behind a prompt, is output.
+    pub fn take(&self, line: &str) -> bool {
+        let (pending, redraws) = &mut *self.lock();
+        if let Some(index) = pending.iter().position(|sent| is_echo(line, sent)) {
+            let sent = pending.drain(..=index).next_back().unwrap_or_default();
+            if line.trim() == sent.trim() {
+                if redraws.len() == MAX_PENDING_ECHOES {
+                    redraws.pop_front();
+                }
+                redraws.push_back(sent);
+            }
+            return true;
+        }
+        if let Some(index) = redraws.iter().position(|sent| is_echo(line, sent)) {
+            redraws.drain(..=index);
+            return true;
+        }
+        false
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, (VecDeque<String>, VecDeque<String>)> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Picks out the lines of one output stream that match an error pattern.
+/// Lines echoing `echoes` are input, not output, and never match.
+pub(crate) struct ErrorMatcher {
+    patterns: Vec<Regex>,
+    echoes: Option<PendingEchoes>,
+    lines: LineSplitter,
+}
+
+impl ErrorMatcher {
+    pub fn new(patterns: Vec<Regex>, echoes: Option<PendingEchoes>) -> Self {
+        Self { patterns, echoes, lines: LineSplitter::default() }
+    }
+
+    /// The lines completed by `data` that match.
+    pub fn matches(&mut self, data: &[u8]) -> Vec<String> {
+        if self.patterns.is_empty() {
+            return Vec::new();
+        }
+        self.lines.lines(data).into_iter()
+            .filter(|line| !self.echoes.as_ref().is_some_and(|echoes| echoes.take(line)))
+            .filter(|line| self.patterns.iter().any(|pattern| pattern.is_match(line)))
+            .collect()
+    }
+}
@@ -0,0 +1,50 @@
+// Agent Pipes - One agent's output lines queued as another agent's commands
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use super::colorize::plain_text;
+
+/// Lines a pipe forwards per `PIPE_RATE_WINDOW`; further lines in the same
+/// window are dropped, so two agents answering each other can't flood
+/// their queues.
+pub const MAX_PIPED_LINES_PER_WINDOW: usize = 20;
+pub const PIPE_RATE_WINDOW: Duration = Duration::from_secs(1);
+/// Longest unterminated line a pipe holds; longer ones are discarded.
+pub const MAX_PIPED_LINE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipeHandle {
+    pub id: String,
+    pub from_id: String,
+    pub to_id: String,
+    /// Pattern a line must match to be forwarded, if any.
+    pub filter: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A registered pipe and the task that forwards its lines.
+pub(crate) struct PipeEntry {
+    pub info: PipeHandle,
+    pub filter: Option<Regex>,
+    pub task: JoinHandle<()>,
+}
+
+/// What a line of output forwards as, with escape sequences removed: the
+/// whole line, or with a filter that has a capture group, the first group.
+/// `None` for blank lines and lines the filter rejects.
+pub(crate) fn piped_line(line: &[u8], filter: Option<&Regex>) -> Option<String> {
+    let line = plain_text(&String::from_utf8_lossy(line));
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.trim().is_empty() {
+        return None;
+    }
+    let Some(filter) = filter else {
+        return Some(line.to_string());
+    };
+    let captures = filter.captures(line)?;
+    let piped = captures.get(1).or_else(|| captures.get(0))?;
+    Some(piped.as_str().to_string())
+}
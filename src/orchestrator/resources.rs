@@ -0,0 +1,241 @@
+// Process Resources - Memory, descriptor and CPU usage of agent processes
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Usage of an agent's top-level process (the shell or CLI it spawned, not
+/// its descendants).
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessResources {
+    pub pid: u32,
+    pub rss_kb: u64,
+    /// Open file descriptors; open handles on Windows.
+    pub open_fds: u32,
+    /// CPU time since the previous snapshot as a share of one core, so a
+    /// busy multi-threaded process can exceed 100. Zero on the first one.
+    pub cpu_percent: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub per_agent: HashMap<String, ProcessResources>,
+    /// Sums over `per_agent`.
+    pub total_rss_kb: u64,
+    pub total_cpu_percent: f32,
+    /// The agent using the most memory, the first to consider killing
+    /// under memory pressure.
+    pub heaviest_agent: Option<String>,
+}
+
+/// Counters read from the OS for one process.
+struct ProcessSample {
+    rss_kb: u64,
+    open_fds: u32,
+    cpu_time: Duration,
+}
+
+/// Takes snapshots, remembering each process's CPU time so `cpu_percent`
+/// covers the period since the previous one.
+#[derive(Default)]
+pub(crate) struct ResourceSampler {
+    previous: Mutex<HashMap<u32, (Duration, Instant)>>,
+}
+
+impl ResourceSampler {
+    /// Samples each `(agent_id, pid)`. Processes that can't be read, e.g.
+    /// because they just exited, are left out.
+    pub fn sample(&self, processes: Vec<(String, u32)>) -> ResourceSnapshot {
+        let mut previous = self.previous.lock().unwrap_or_else(|e| e.into_inner());
+        let mut current = HashMap::new();
+        let mut per_agent = HashMap::new();
+        
+        for (agent_id, pid) in processes {
+            let sample = match read_process(pid) {
+                Ok(sample) => sample,
+                Err(e) => {
+                    debug!("Could not read resources of agent {} (pid {}): {}", agent_id, pid, e);
+                    continue;
+                }
+            };
+            let (resources, reading) = measure(&previous, pid, sample);
+            current.insert(pid, reading);
+            per_agent.insert(agent_id, resources);
+        }
+        
+        // Only keep processes that are still around
+        *previous = current;
+        ResourceSnapshot {
+            taken_at: Utc::now(),
+            total_rss_kb: per_agent.values().map(|resources| resources.rss_kb).sum(),
+            total_cpu_percent: per_agent.values().map(|resources| resources.cpu_percent).sum(),
+            heaviest_agent: per_agent.iter()
+                .max_by_key(|(_, resources)| resources.rss_kb)
+                .map(|(agent_id, _)| agent_id.clone()),
+            per_agent,
+        }
+    }
+    
+    /// Samples a single process, leaving what is remembered about others
+    /// alone.
+    pub fn sample_one(&self, pid: u32) -> io::Result<ProcessResources> {
+        let sample = read_process(pid)?;
+        let mut previous = self.previous.lock().unwrap_or_else(|e| e.into_inner());
+        let (resources, reading) = measure(&previous, pid, sample);
+        previous.insert(pid, reading);
+        Ok(resources)
+    }
+}
+
+/// Turns `sample` into usage, with CPU measured against the previous
+/// reading of `pid`, and returns the reading to remember for next time.
+fn measure(
+    previous: &HashMap<u32, (Duration, Instant)>,
+    pid: u32,
+    sample: ProcessSample,
+) -> (ProcessResources, (Duration, Instant)) {
+    let now = Instant::now();
+    let cpu_percent = match previous.get(&pid) {
+        Some((cpu_time, at)) if now > *at => {
+            let busy = sample.cpu_time.saturating_sub(*cpu_time).as_secs_f32();
+            busy / now.duration_since(*at).as_secs_f32() * 100.0
+        }
+        _ => 0.0,
+    };
+    let resources = ProcessResources {
+        pid,
+        rss_kb: sample.rss_kb,
+        open_fds: sample.open_fds,
+        cpu_percent,
+    };
+    (resources, (sample.cpu_time, now))
+}
+
+/// `USER_HZ`, the unit of the CPU times in `/proc/{pid}/stat`. Fixed at 100
+/// by the kernel ABI regardless of the configured tick rate.
+#[cfg(target_os = "linux")]
+const CLOCK_TICKS_PER_SEC: u64 = 100;
+
+#[cfg(target_os = "linux")]
+fn read_process(pid: u32) -> io::Result<ProcessSample> {
+    let proc_dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+    
+    let status = std::fs::read_to_string(proc_dir.join("status"))?;
+    // Absent for zombies and kernel threads
+    let rss_kb = status.lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+        .unwrap_or(0);
+    
+    let open_fds = std::fs::read_dir(proc_dir.join("fd"))?.count() as u32;
+    
+    // The command name in field 2 may contain spaces, so split after its
+    // closing parenthesis; utime and stime are then at index 11 and 12
+    let stat = std::fs::read_to_string(proc_dir.join("stat"))?;
+    let fields: Vec<&str> = stat.rsplit_once(')')
+        .map(|(_, rest)| rest.split_whitespace().collect())
+        .unwrap_or_default();
+    let ticks = |index: usize| fields.get(index).and_then(|f| f.parse::<u64>().ok()).unwrap_or(0);
+    let cpu_ticks = ticks(11) + ticks(12);
+    
+    Ok(ProcessSample {
+        rss_kb,
+        open_fds,
+        cpu_time: Duration::from_millis(cpu_ticks * 1000 / CLOCK_TICKS_PER_SEC),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read_process(pid: u32) -> io::Result<ProcessSample> {
+    use libproc::libproc::bsd_info::BSDInfo;
+    use libproc::libproc::file_info::ListFDs;
+    use libproc::libproc::proc_pid::{listpidinfo, pidinfo};
+    use libproc::libproc::task_info::TaskInfo;
+    
+    let to_io = |e: String| io::Error::other(e);
+    let task = pidinfo::<TaskInfo>(pid as i32, 0).map_err(to_io)?;
+    let bsd = pidinfo::<BSDInfo>(pid as i32, 0).map_err(to_io)?;
+    let fds = listpidinfo::<ListFDs>(pid as i32, bsd.pbi_nfiles as usize).map_err(to_io)?;
+    
+    Ok(ProcessSample {
+        rss_kb: task.pti_resident_size / 1024,
+        open_fds: fds.len() as u32,
+        cpu_time: mach_ticks_to_duration(task.pti_total_user + task.pti_total_system),
+    })
+}
+
+/// Converts Mach absolute time units to a duration. They are nanoseconds
+/// on Intel Macs but not on Apple silicon, where a tick is 125/3 ns.
+#[cfg(target_os = "macos")]
+fn mach_ticks_to_duration(ticks: u64) -> Duration {
+    use std::sync::OnceLock;
+    
+    #[repr(C)]
+    struct MachTimebaseInfo {
+        numer: u32,
+        denom: u32,
+    }
+    extern "C" {
+        fn mach_timebase_info(info: *mut MachTimebaseInfo) -> i32;
+    }
+    
+    static TIMEBASE: OnceLock<(u64, u64)> = OnceLock::new();
+    let (numer, denom) = *TIMEBASE.get_or_init(|| {
+        let mut info = MachTimebaseInfo { numer: 0, denom: 0 };
+        // SAFETY: `info` is a live, correctly laid out out pointer
+        let status = unsafe { mach_timebase_info(&mut info) };
+        if status != 0 || info.denom == 0 {
+            return (1, 1);
+        }
+        (u64::from(info.numer), u64::from(info.denom))
+    });
+    Duration::from_nanos((u128::from(ticks) * u128::from(numer) / u128::from(denom)) as u64)
+}
+
+#[cfg(windows)]
+fn read_process(pid: u32) -> io::Result<ProcessSample> {
+    use std::mem::{size_of, zeroed};
+    use windows_sys::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows_sys::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows_sys::Win32::System::Threading::{
+        GetProcessHandleCount, GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    
+    // SAFETY: the handle is checked before use and closed once; every out
+    // pointer refers to a live, correctly sized local
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let mut memory: PROCESS_MEMORY_COUNTERS = zeroed();
+        let mut handles = 0u32;
+        let (mut created, mut exited): (FILETIME, FILETIME) = (zeroed(), zeroed());
+        let (mut kernel, mut user): (FILETIME, FILETIME) = (zeroed(), zeroed());
+        let ok = GetProcessMemoryInfo(handle, &mut memory, size_of::<PROCESS_MEMORY_COUNTERS>() as u32) != 0
+            && GetProcessHandleCount(handle, &mut handles) != 0
+            && GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user) != 0;
+        let error = io::Error::last_os_error();
+        CloseHandle(handle);
+        if !ok {
+            return Err(error);
+        }
+        
+        // FILETIME counts 100 ns intervals
+        let hundred_ns = |t: FILETIME| (u64::from(t.dwHighDateTime) << 32) | u64::from(t.dwLowDateTime);
+        Ok(ProcessSample {
+            rss_kb: memory.WorkingSetSize as u64 / 1024,
+            open_fds: handles,
+            cpu_time: Duration::from_nanos((hundred_ns(kernel) + hundred_ns(user)) * 100),
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn read_process(_pid: u32) -> io::Result<ProcessSample> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "resource usage is not available on this platform"))
+}
@@ -0,0 +1,120 @@
+// Agent Sandbox - Linux namespace isolation for agent processes via unshare
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+use tracing::warn;
+
+/// How long `agent_pid` waits for `unshare` to fork the agent.
+#[cfg(target_os = "linux")]
+const FORK_WAIT: Duration = Duration::from_secs(2);
+
+/// Namespaces an agent runs in. They are created inside a new user
+/// namespace, mapping the caller to root there, so no privileges are
+/// needed where the kernel allows unprivileged user namespaces.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// Run in a fresh network namespace, without access to the host's
+    /// interfaces.
+    #[serde(default)]
+    pub isolate_network: bool,
+    /// Run in a fresh PID namespace with its own `/proc`, so other
+    /// processes are not visible.
+    #[serde(default)]
+    pub isolate_pid: bool,
+    /// Interfaces usable inside an isolated network namespace. Only `lo` is
+    /// supported: a new namespace has no other interfaces, and moving host
+    /// ones into it needs privileges over the host network.
+    #[serde(default)]
+    pub allowed_interfaces: Vec<String>,
+}
+
+impl SandboxConfig {
+    pub(crate) fn isolates(&self) -> bool {
+        self.isolate_network || self.isolate_pid
+    }
+}
+
+/// Whether sandboxing works on this platform, for `get_sandbox_support`.
+pub fn support(unshare_found: bool) -> Value {
+    let supported = cfg!(target_os = "linux");
+    json!({
+        "platform": std::env::consts::OS,
+        "supported": supported,
+        "unshare_found": unshare_found,
+        "available": supported && unshare_found,
+    })
+}
+
+/// The command starting `program`, wrapped in `unshare` as `sandbox` asks.
+#[cfg(target_os = "linux")]
+pub(crate) fn command(sandbox: &SandboxConfig, program: &str) -> CommandBuilder {
+    if !sandbox.isolates() {
+        return CommandBuilder::new(program);
+    }
+    
+    // The agent is forked, so it dies with unshare rather than outliving a
+    // kill; see `agent_pid` for its pid
+    let mut cmd = CommandBuilder::new("unshare");
+    cmd.args(["--user", "--map-root-user", "--fork", "--kill-child"]);
+    if sandbox.isolate_network {
+        cmd.arg("--net");
+    }
+    if sandbox.isolate_pid {
+        // --mount-proc so tools reading /proc see only the new namespace
+        cmd.args(["--pid", "--mount-proc"]);
+    }
+    
+    let unsupported: Vec<&str> = sandbox.allowed_interfaces.iter()
+        .map(String::as_str)
+        .filter(|interface| *interface != "lo")
+        .collect();
+    if !unsupported.is_empty() {
+        warn!("Sandboxed agents can only use the loopback interface; ignoring {}", unsupported.join(", "));
+    }
+    if sandbox.isolate_network && sandbox.allowed_interfaces.iter().any(|i| i == "lo") {
+        // Loopback starts down in a new namespace; bring it up, then exec
+        cmd.args(["sh", "-c", "ip link set lo up && exec \"$0\"", program]);
+    } else {
+        cmd.arg(program);
+    }
+    cmd
+}
+
+/// The pid of the agent `unshare` forked, given the pid of `unshare`
+/// itself (`launcher`), which signals and resource sampling must skip.
+/// `launcher` when not sandboxed, or if no child shows up in time.
+#[cfg(target_os = "linux")]
+pub(crate) async fn agent_pid(sandbox: Option<&SandboxConfig>, launcher: u32) -> u32 {
+    if !sandbox.is_some_and(SandboxConfig::isolates) {
+        return launcher;
+    }
+    let children = format!("/proc/{0}/task/{0}/children", launcher);
+    let deadline = tokio::time::Instant::now() + FORK_WAIT;
+    loop {
+        let child = std::fs::read_to_string(&children).ok()
+            .and_then(|pids| pids.split_whitespace().next().and_then(|pid| pid.parse().ok()));
+        if let Some(child) = child {
+            return child;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            warn!("unshare {} forked no agent within {:?}; reporting its own pid", launcher, FORK_WAIT);
+            return launcher;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) async fn agent_pid(_sandbox: Option<&SandboxConfig>, launcher: u32) -> u32 {
+    launcher
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn command(sandbox: &SandboxConfig, program: &str) -> CommandBuilder {
+    if sandbox.isolates() {
+        warn!("Agent sandboxing needs Linux namespaces; starting {} without it", program);
+    }
+    CommandBuilder::new(program)
+}
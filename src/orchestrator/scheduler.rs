@@ -0,0 +1,44 @@
+// Command Scheduler - One-shot and recurring commands for agents
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Shortest interval accepted for `Schedule::Every`.
+pub const MIN_SCHEDULE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    Once(DateTime<Utc>),
+    /// Serialized as whole seconds.
+    Every(#[serde(with = "duration_secs")] Duration),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledCommand {
+    pub id: String,
+    pub agent_id: String,
+    pub command: String,
+    pub schedule: Schedule,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A registered schedule and the task that dispatches it.
+pub(crate) struct ScheduleEntry {
+    pub info: ScheduledCommand,
+    pub task: JoinHandle<()>,
+}
+
+mod duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
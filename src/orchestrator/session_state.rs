@@ -1,43 +1,117 @@
 // Session State Management
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::path::Path;
 use chrono::{DateTime, Utc};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::agent_manager::{AgentConfig, AgentManager};
+use super::error::SessionError;
+use super::session_template::{SessionTemplate, TemplateAgent};
+
+/// Schema version written by this build. Bump it together with a new
+/// `MIGRATIONS` step when the persisted shape changes.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Upgrades of a saved session document by one schema version; the step
+/// at index `n` turns a v`n + 1` document into v`n + 2`. None are needed
+/// yet, as every field added so far has a serde default.
+const MIGRATIONS: &[fn(&mut Value)] = &[];
+
+// A version bump without its step fails the build rather than an import
+const _: () = assert!(MIGRATIONS.len() + 1 == CURRENT_SCHEMA_VERSION as usize);
+
+/// Longest command prefix used as a key in command analytics.
+const COMMAND_KEY_MAX_CHARS: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SessionState {
+    /// Files saved before versioning was introduced have no field and are v1
+    #[serde(default = "first_schema_version")]
+    pub schema_version: u32,
     pub id: String,
     pub started_at: DateTime<Utc>,
     pub agents: HashMap<String, AgentSession>,
     pub task_history: Vec<TaskRecord>,
     pub total_commands: usize,
+    /// Bytes of every command sent, for cost estimates. Sessions saved
+    /// before these totals were kept start at 0.
+    #[serde(default)]
+    pub total_bytes_sent: u64,
+    /// Bytes of output read from native agents.
+    #[serde(default)]
+    pub total_bytes_received: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AgentSession {
     pub id: String,
     pub agent_type: String,
     pub started_at: DateTime<Utc>,
     pub commands_sent: usize,
     pub last_activity: DateTime<Utc>,
+    /// `task_history` records kept for this agent; `None` is unlimited.
+    /// New commands let it grow to twice as many before the oldest are
+    /// trimmed back to it.
+    #[serde(default)]
+    pub history_limit: Option<usize>,
+    /// The config the agent was spawned with, redacted. Sessions saved
+    /// before configs were recorded have none.
+    #[serde(default)]
+    pub config: Option<AgentConfig>,
+    /// Records this agent has in `task_history`, kept up to date so trimming
+    /// needn't count them. Recounted when a session is loaded, as older
+    /// ones lack it.
+    #[serde(default)]
+    history_len: usize,
+    /// Set for agents spawned by this process. Never persisted, so agents
+    /// from a reloaded session are history-only.
+    #[serde(skip)]
+    pub live: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaskRecord {
     pub id: String,
     pub agent_id: String,
     pub command: String,
     pub timestamp: DateTime<Utc>,
     pub v_level: Option<u8>,
+    /// Dispatched by the scheduler rather than sent by a user.
+    #[serde(default)]
+    pub scheduled: bool,
+    /// Time until the agent's response was complete, for commands whose
+    /// response was awaited.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    /// Number of scrollback lines the agent had produced when the command
+    /// was sent, so where its response starts. Only native agents have one.
+    #[serde(default)]
+    pub output_line: Option<usize>,
+    /// `start_time` of the process `output_line` counts the lines of, so
+    /// lines of a process since reset or respawned aren't taken for it.
+    #[serde(default)]
+    pub process_started_at: Option<DateTime<Utc>>,
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SessionState {
     pub fn new() -> Self {
         Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
             id: uuid::Uuid::new_v4().to_string(),
             started_at: Utc::now(),
             agents: HashMap::new(),
             task_history: Vec::new(),
             total_commands: 0,
+            total_bytes_sent: 0,
+            total_bytes_received: 0,
         }
     }
     
@@ -50,18 +124,52 @@ impl SessionState {
                 started_at: Utc::now(),
                 commands_sent: 0,
                 last_activity: Utc::now(),
+                history_limit: None,
+                config: None,
+                history_len: 0,
+                live: true,
             },
         );
     }
     
+    /// Records the config `agent_id` was spawned with, redacted first.
+    pub fn set_agent_config(&mut self, agent_id: &str, config: &AgentConfig) {
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.config = Some(config.redacted());
+        }
+    }
+    
     pub fn unregister_agent(&mut self, agent_id: &str) {
         self.agents.remove(agent_id);
     }
     
+    pub fn is_history_only(&self, agent_id: &str) -> bool {
+        self.agents.get(agent_id).is_some_and(|agent| !agent.live)
+    }
+    
+    /// Marks input that is not a command, like raw keystrokes, as activity.
+    pub fn record_activity(&mut self, agent_id: &str) {
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.last_activity = Utc::now();
+        }
+    }
+    
     pub fn log_command(&mut self, agent_id: &str, command: &str) {
+        self.record_command(agent_id, command, false);
+    }
+    
+    pub fn log_scheduled_command(&mut self, agent_id: &str, command: &str) {
+        self.record_command(agent_id, command, true);
+    }
+    
+    fn record_command(&mut self, agent_id: &str, command: &str, scheduled: bool) {
+        let mut over_limit = None;
         if let Some(agent) = self.agents.get_mut(agent_id) {
             agent.commands_sent += 1;
             agent.last_activity = Utc::now();
+            agent.history_len += 1;
+            // Trimmed in batches, as each trim is a pass over all records
+            over_limit = agent.history_limit.filter(|&limit| agent.history_len > limit.saturating_mul(2));
         }
         
         self.task_history.push(TaskRecord {
@@ -70,12 +178,296 @@ impl SessionState {
             command: command.to_string(),
             timestamp: Utc::now(),
             v_level: None,
+            scheduled,
+            duration_ms: None,
+            output_line: None,
+            process_started_at: None,
         });
         
+        // Counts every command, including ones whose records get evicted
         self.total_commands += 1;
+        self.total_bytes_sent += command.len() as u64;
+        
+        if let Some(limit) = over_limit {
+            self.trim_agent_history(agent_id, limit);
+        }
+    }
+    
+    /// Records how long the response to `agent_id`'s latest command took,
+    /// counted from when it was logged. Only the latest command is matched,
+    /// and only once.
+    pub fn record_response_time(&mut self, agent_id: &str) {
+        if let Some(record) = self.task_history.iter_mut().rev().find(|r| r.agent_id == agent_id) {
+            if record.duration_ms.is_none() {
+                let elapsed = Utc::now().signed_duration_since(record.timestamp);
+                record.duration_ms = Some(elapsed.num_milliseconds().max(0) as u64);
+            }
+        }
+    }
+    
+    /// Records where in the scrollback the response to `agent_id`'s latest
+    /// command starts.
+    pub fn record_output_line(&mut self, agent_id: &str, output_line: usize, process_started_at: DateTime<Utc>) {
+        if let Some(record) = self.task_history.iter_mut().rev().find(|r| r.agent_id == agent_id) {
+            record.output_line = Some(output_line);
+            record.process_started_at = Some(process_started_at);
+        }
+    }
+    
+    /// Average `duration_ms` of `agent_id`'s timed records, or of all timed
+    /// records if it has none. `None` when nothing was timed.
+    pub fn average_duration_ms(&self, agent_id: &str) -> Option<u64> {
+        let average = |records: Vec<u64>| {
+            (!records.is_empty()).then(|| records.iter().sum::<u64>() / records.len() as u64)
+        };
+        let timed = || self.task_history.iter().filter_map(|r| r.duration_ms.map(|ms| (r, ms)));
+        average(timed().filter(|(r, _)| r.agent_id == agent_id).map(|(_, ms)| ms).collect())
+            .or_else(|| average(timed().map(|(_, ms)| ms).collect()))
+    }
+    
+    pub fn record_bytes_received(&mut self, bytes: usize) {
+        self.total_bytes_received += bytes as u64;
+    }
+    
+    /// Counts and byte totals of the whole session.
+    pub fn summary(&self) -> Value {
+        serde_json::json!({
+            "id": self.id,
+            "started_at": self.started_at,
+            "agents": self.agents.len(),
+            "live_agents": self.agents.values().filter(|agent| agent.live).count(),
+            "total_commands": self.total_commands,
+            "total_bytes_sent": self.total_bytes_sent,
+            "total_bytes_received": self.total_bytes_received,
+        })
+    }
+    
+    /// Sets `agent_id`'s `commands_sent` to `sent`, the count its process
+    /// reports, and moves `total_commands` by the same amount. Returns the
+    /// previous count if it was different.
+    pub fn reconcile_commands_sent(&mut self, agent_id: &str, sent: usize) -> Option<usize> {
+        let agent = self.agents.get_mut(agent_id)?;
+        if agent.commands_sent == sent {
+            return None;
+        }
+        let previous = std::mem::replace(&mut agent.commands_sent, sent);
+        self.total_commands = (self.total_commands + sent).saturating_sub(previous);
+        Some(previous)
+    }
+    
+    /// Caps the records kept for `agent_id` (0 removes the cap) and trims
+    /// existing ones to fit, oldest first.
+    pub fn set_history_limit(&mut self, agent_id: &str, limit: usize) -> Result<()> {
+        let agent = self.agents.get_mut(agent_id)
+            .ok_or_else(|| anyhow::anyhow!("Agent {} not found", agent_id))?;
+        agent.history_limit = (limit > 0).then_some(limit);
+        if limit > 0 {
+            self.trim_agent_history(agent_id, limit);
+        }
+        Ok(())
+    }
+    
+    /// Removes the oldest records of `agent_id` beyond `limit` in one pass.
+    fn trim_agent_history(&mut self, agent_id: &str, limit: usize) {
+        let Some(agent) = self.agents.get_mut(agent_id) else {
+            return;
+        };
+        let mut excess = agent.history_len.saturating_sub(limit);
+        if excess == 0 {
+            return;
+        }
+        agent.history_len = limit;
+        self.task_history.retain(|record| {
+            if excess > 0 && record.agent_id == agent_id {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+    
+    /// Keeps only the `keep_last` most recent records across all agents and
+    /// returns how many were removed. Counters like `total_commands` and
+    /// `commands_sent` are cumulative and stay as they are.
+    pub fn prune_history(&mut self, keep_last: usize) -> usize {
+        let excess = self.task_history.len().saturating_sub(keep_last);
+        // Run per command under `auto_prune_history`. Only the removed
+        // records are visited; the kept ones move down in a single copy
+        for record in self.task_history.drain(..excess) {
+            if let Some(agent) = self.agents.get_mut(&record.agent_id) {
+                agent.history_len = agent.history_len.saturating_sub(1);
+            }
+        }
+        excess
+    }
+    
+    /// Removes records older than `cutoff` and returns how many were removed.
+    pub fn prune_before(&mut self, cutoff: DateTime<Utc>) -> usize {
+        let before = self.task_history.len();
+        self.task_history.retain(|record| record.timestamp >= cutoff);
+        let removed = before - self.task_history.len();
+        if removed > 0 {
+            self.recount_history();
+        }
+        removed
+    }
+    
+    /// Recounts each agent's records after the history changed wholesale.
+    pub(crate) fn recount_history(&mut self) {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for record in &self.task_history {
+            *counts.entry(record.agent_id.as_str()).or_insert(0) += 1;
+        }
+        for (agent_id, agent) in self.agents.iter_mut() {
+            agent.history_len = counts.get(agent_id.as_str()).copied().unwrap_or(0);
+        }
+    }
+    
+    /// Rearranges `agent_id`'s records into `new_order`, a list of record
+    /// ids. The records keep the agent's original positions in the history,
+    /// so other agents' records don't move; records left out of `new_order`
+    /// are removed.
+    pub fn reorder_agent_history(&mut self, agent_id: &str, new_order: &[String]) -> Result<()> {
+        let mut owned: HashMap<&str, usize> = HashMap::new();
+        for (index, record) in self.task_history.iter().enumerate() {
+            if record.agent_id == agent_id {
+                owned.insert(record.id.as_str(), index);
+            }
+        }
+        let mut order = Vec::with_capacity(new_order.len());
+        for record_id in new_order {
+            let index = owned.remove(record_id.as_str())
+                .ok_or_else(|| SessionError::RecordNotFound { record_id: record_id.clone() })?;
+            order.push(index);
+        }
+        
+        let slots: Vec<bool> = self.task_history.iter()
+            .map(|record| record.agent_id == agent_id)
+            .collect();
+        let mut records: Vec<Option<TaskRecord>> = std::mem::take(&mut self.task_history)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut reordered = order.into_iter();
+        for (index, is_slot) in slots.into_iter().enumerate() {
+            let source = if is_slot { reordered.next() } else { Some(index) };
+            if let Some(record) = source.and_then(|source| records[source].take()) {
+                self.task_history.push(record);
+            }
+        }
+        self.recount_history();
+        Ok(())
+    }
+    
+    /// Removes one record from the history and returns it.
+    pub fn delete_record(&mut self, record_id: &str) -> Result<TaskRecord> {
+        let index = self.task_history.iter()
+            .position(|record| record.id == record_id)
+            .ok_or_else(|| SessionError::RecordNotFound { record_id: record_id.to_string() })?;
+        let record = self.task_history.remove(index);
+        if let Some(agent) = self.agents.get_mut(&record.agent_id) {
+            agent.history_len = agent.history_len.saturating_sub(1);
+        }
+        Ok(record)
+    }
+    
+    /// Counts how often each command was sent, optionally for one agent.
+    /// Keys are truncated to `COMMAND_KEY_MAX_CHARS` characters.
+    pub fn command_frequency(&self, agent_id: Option<&str>) -> HashMap<String, usize> {
+        let mut histogram = HashMap::new();
+        for record in &self.task_history {
+            if agent_id.is_some_and(|id| id != record.agent_id) {
+                continue;
+            }
+            let key: String = record.command.chars().take(COMMAND_KEY_MAX_CHARS).collect();
+            *histogram.entry(key).or_insert(0) += 1;
+        }
+        histogram
+    }
+    
+    /// The agents with a recorded config, oldest first, each with the
+    /// first command it was sent beyond its startup commands (scheduled
+    /// commands aside) as its initial prompt.
+    pub fn to_template(&self) -> SessionTemplate {
+        let mut agents: Vec<&AgentSession> = self.agents.values()
+            .filter(|agent| agent.config.is_some())
+            .collect();
+        agents.sort_by_key(|agent| agent.started_at);
+        
+        let agents = agents.into_iter()
+            .filter_map(|agent| {
+                let config = agent.config.clone()?;
+                let mut startup = config.startup_commands.iter();
+                let initial_prompt = self.task_history.iter()
+                    .filter(|record| record.agent_id == agent.id && !record.scheduled)
+                    .find(|record| !startup.next().is_some_and(|command| {
+                        AgentManager::redact_command(&record.command) == *command
+                    }))
+                    .map(|record| AgentManager::redact_command(&record.command));
+                Some(TemplateAgent {
+                    config: AgentConfig { agent_id: None, ..config },
+                    initial_prompt,
+                })
+            })
+            .collect();
+        SessionTemplate { created_at: Utc::now(), agents }
     }
     
     pub fn export(&self) -> serde_json::Value {
         serde_json::to_value(self).unwrap_or_else(|_| serde_json::json!({}))
     }
-}
\ No newline at end of file
+    
+    /// Loads a saved session file through `migrate`.
+    pub fn load_from(path: &Path) -> Result<SessionState> {
+        let json = std::fs::read_to_string(path)?;
+        Self::import_from_str(&json)
+    }
+    
+    #[deprecated(note = "use `SessionState::load_from`")]
+    pub fn import_from_file(path: &Path) -> Result<SessionState> {
+        Self::load_from(path)
+    }
+    
+    /// Parses a saved session, upgrading older schema versions through
+    /// `migrate` before the strict deserialize.
+    pub fn import_from_str(json: &str) -> Result<SessionState> {
+        Self::migrate(serde_json::from_str(json)?)
+    }
+    
+    /// Upgrades a saved session document from its `schema_version` to the
+    /// current one, then deserializes it. Fields added since a version was
+    /// written take their serde defaults.
+    pub fn migrate(mut value: Value) -> Result<SessionState> {
+        // Indexing anything else below would panic
+        if !value.is_object() {
+            anyhow::bail!("A session must be a JSON object");
+        }
+        let found = match value.get("schema_version") {
+            None => first_schema_version(),
+            Some(version) => version.as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| anyhow::anyhow!("schema_version must be a non-negative integer"))?,
+        };
+        if found == 0 || found > CURRENT_SCHEMA_VERSION {
+            return Err(SessionError::UnsupportedSchemaVersion {
+                found,
+                supported: CURRENT_SCHEMA_VERSION,
+            }.into());
+        }
+        
+        for version in found..CURRENT_SCHEMA_VERSION {
+            let step = MIGRATIONS.get(version as usize - 1)
+                .ok_or_else(|| anyhow::anyhow!("No migration from session schema v{}", version))?;
+            step(&mut value);
+        }
+        value["schema_version"] = Value::from(CURRENT_SCHEMA_VERSION);
+        
+        let mut session: SessionState = serde_json::from_value(value)?;
+        session.recount_history();
+        Ok(session)
+    }
+}
+
+fn first_schema_version() -> u32 {
+    1
+}
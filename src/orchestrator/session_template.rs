@@ -0,0 +1,35 @@
+// Session Templates - Reusable agent setups derived from a session
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::agent_manager::AgentConfig;
+
+/// The agents of a session, as `spawn_from_template` starts them again.
+/// Saved as `<name>.json` in the orchestrator's `template_dir`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTemplate {
+    pub created_at: DateTime<Utc>,
+    /// In the order the session spawned them.
+    pub agents: Vec<TemplateAgent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateAgent {
+    /// Redacted, and without an `agent_id` so every spawn gets a fresh one.
+    pub config: AgentConfig,
+    /// The first command sent after the startup commands, sent again once
+    /// the agent is up.
+    #[serde(default)]
+    pub initial_prompt: Option<String>,
+}
+
+/// Template names become file names, so they are limited to letters,
+/// digits, `-`, `_` and inner dots.
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    if name.is_empty() || name.starts_with('.') || !name.chars().all(allowed) {
+        bail!("Invalid template name {:?}: use letters, digits, '-', '_' and '.'", name);
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+// Shutdown Sequence - The input `kill` sends to ask an agent to quit
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::agent_manager::AgentType;
+
+/// One step of an agent's shutdown. `kill` runs the steps in order, stops
+/// early once the process has exited, and force-kills it if it is still
+/// running afterwards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownStep {
+    /// Written as is, e.g. `[3]` for Ctrl+C.
+    Raw(Vec<u8>),
+    /// `AgentProcess::send_interrupt`, or a written Ctrl+C where the agent
+    /// can't be signalled.
+    Interrupt,
+    /// Written with a trailing newline; the command wrapper is not applied.
+    Command(String),
+    /// Serialized as milliseconds.
+    Wait(#[serde(with = "duration_millis")] Duration),
+    /// Kills the process; any later steps are skipped. Without a PTY, stdin
+    /// is closed first.
+    ForceKill,
+}
+
+impl AgentType {
+    /// Built-in shutdown sequences. The chat CLIs get an interrupt to
+    /// abandon the current turn, then their own quit command; bash and
+    /// plugin agents get an interrupt then `^D`. Each ends with a grace
+    /// period before the force kill.
+    pub fn default_shutdown_sequence(&self) -> Vec<ShutdownStep> {
+        let interrupt = ShutdownStep::Interrupt;
+        let pause = ShutdownStep::Wait(Duration::from_millis(500));
+        match self {
+            AgentType::Claude => vec![
+                interrupt,
+                pause,
+                ShutdownStep::Command("/exit".to_string()),
+                ShutdownStep::Wait(Duration::from_secs(1)),
+            ],
+            AgentType::Gemini => vec![
+                interrupt,
+                pause,
+                ShutdownStep::Command("/quit".to_string()),
+                ShutdownStep::Wait(Duration::from_secs(1)),
+            ],
+            AgentType::Bash | AgentType::Plugin(_) => vec![
+                interrupt,
+                pause.clone(),
+                ShutdownStep::Raw(b"\x04".to_vec()),
+                pause,
+            ],
+        }
+    }
+}
+
+mod duration_millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
@@ -0,0 +1,45 @@
+// Workflow Simulation - Checking a command sequence without running it
+use serde::{Serialize, Serializer};
+
+use super::dry_run::DryRunResult;
+use super::error::AgentError;
+
+/// One command of a simulated sequence, as `AgentOrchestrator::simulate`
+/// would send it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationStep {
+    /// Position in the simulated sequence.
+    pub index: usize,
+    pub agent_id: String,
+    pub command: String,
+    #[serde(flatten)]
+    pub preview: DryRunResult,
+    /// The agent's average response time in the session, or the baseline.
+    pub estimated_duration_ms: u64,
+}
+
+/// Steps for the commands whose agent exists, and the problems found. Errors
+/// carry the index of the command they belong to and serialize as
+/// `{"index": ..., "error": "..."}`.
+#[derive(Debug, Serialize)]
+pub struct SimulationResult {
+    pub steps: Vec<SimulationStep>,
+    #[serde(serialize_with = "serialize_errors")]
+    pub errors: Vec<(usize, AgentError)>,
+    /// Summed over `steps`; the commands are assumed to run one after the
+    /// other.
+    pub total_duration_estimate_ms: u64,
+}
+
+impl SimulationResult {
+    /// Whether the sequence would run without a known problem.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn serialize_errors<S: Serializer>(errors: &[(usize, AgentError)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(errors.iter().map(|(index, error)| {
+        serde_json::json!({ "index": index, "error": error.to_string() })
+    }))
+}
@@ -0,0 +1,141 @@
+// Orchestrator Stats - Time-windowed counts of agent activity
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use super::session_state::TaskRecord;
+use super::timeline::TimelineEvent;
+
+/// Activity from `since` until now, as `AgentOrchestrator::stats_since`
+/// reports it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrchestratorStats {
+    pub since: DateTime<Utc>,
+    pub commands_in_window: usize,
+    /// Bytes of the commands in the window.
+    pub bytes_sent_in_window: u64,
+    pub bytes_received_in_window: u64,
+    pub agents_spawned_in_window: usize,
+    pub agents_killed_in_window: usize,
+    pub errors_in_window: usize,
+}
+
+/// How many events of each kind, and seconds of output volume, a
+/// `StatsHistory` keeps. Older ones are dropped first.
+pub const MAX_STATS_EVENTS: usize = 10_000;
+
+/// Timestamped events kept for windowed stats and the session timeline,
+/// each with the agent id it concerns. Commands come from the session's
+/// task history instead. Each history holds the newest `MAX_STATS_EVENTS`.
+#[derive(Debug, Default)]
+pub struct StatsHistory {
+    pub spawn_history: VecDeque<(DateTime<Utc>, String)>,
+    pub kill_history: VecDeque<(DateTime<Utc>, String)>,
+    /// With the error message.
+    pub error_history: VecDeque<(DateTime<Utc>, String, String)>,
+    pub completion_history: VecDeque<(DateTime<Utc>, String)>,
+    /// Agents `reset_agent` killed and spawned again.
+    pub restart_history: VecDeque<(DateTime<Utc>, String)>,
+    /// Output bytes of all agents, summed per second.
+    output_history: VecDeque<(DateTime<Utc>, u64)>,
+}
+
+/// Appends `event` to `history`, dropping the oldest past the cap.
+fn push_capped<T>(history: &mut VecDeque<T>, event: T) {
+    if history.len() == MAX_STATS_EVENTS {
+        history.pop_front();
+    }
+    history.push_back(event);
+}
+
+impl StatsHistory {
+    pub fn record_spawn(&mut self, agent_id: &str) {
+        push_capped(&mut self.spawn_history, (Utc::now(), agent_id.to_string()));
+    }
+
+    pub fn record_kill(&mut self, agent_id: &str) {
+        push_capped(&mut self.kill_history, (Utc::now(), agent_id.to_string()));
+    }
+
+    pub fn record_error(&mut self, agent_id: &str, message: &str) {
+        push_capped(&mut self.error_history, (Utc::now(), agent_id.to_string(), message.to_string()));
+    }
+
+    pub fn record_completion(&mut self, agent_id: &str) {
+        push_capped(&mut self.completion_history, (Utc::now(), agent_id.to_string()));
+    }
+
+    pub fn record_restart(&mut self, agent_id: &str) {
+        push_capped(&mut self.restart_history, (Utc::now(), agent_id.to_string()));
+    }
+
+    pub fn record_output(&mut self, bytes: u64) {
+        if bytes == 0 {
+            return;
+        }
+        let second = Utc::now().duration_trunc(TimeDelta::seconds(1)).unwrap_or_else(|_| Utc::now());
+        match self.output_history.back_mut() {
+            Some((at, total)) if *at == second => *total += bytes,
+            _ => push_capped(&mut self.output_history, (second, bytes)),
+        }
+    }
+
+    /// Output is bucketed by second, so the second `since` falls in counts
+    /// in full.
+    pub fn window(&self, since: DateTime<Utc>, task_history: &[TaskRecord]) -> OrchestratorStats {
+        let count = |history: &VecDeque<(DateTime<Utc>, String)>| {
+            history.iter().filter(|(at, _)| *at >= since).count()
+        };
+        let second = since.duration_trunc(TimeDelta::seconds(1)).unwrap_or(since);
+        let commands = || task_history.iter().filter(|record| record.timestamp >= since);
+        OrchestratorStats {
+            since,
+            commands_in_window: commands().count(),
+            bytes_sent_in_window: commands().map(|record| record.command.len() as u64).sum(),
+            bytes_received_in_window: self.output_history.iter()
+                .filter(|(at, _)| *at >= second)
+                .map(|(_, bytes)| bytes)
+                .sum(),
+            agents_spawned_in_window: count(&self.spawn_history),
+            agents_killed_in_window: count(&self.kill_history),
+            errors_in_window: self.error_history.iter().filter(|(at, ..)| *at >= since).count(),
+        }
+    }
+
+    /// Everything recorded here and in `task_history`, oldest first. Events
+    /// with the same timestamp keep the order they are listed in here.
+    pub fn timeline(&self, task_history: &[TaskRecord]) -> Vec<TimelineEvent> {
+        let mut events: Vec<TimelineEvent> = self.spawn_history.iter()
+            .map(|(timestamp, agent_id)| TimelineEvent::AgentSpawned {
+                timestamp: *timestamp,
+                agent_id: agent_id.clone(),
+            })
+            .chain(task_history.iter().map(|record| TimelineEvent::CommandSent {
+                timestamp: record.timestamp,
+                agent_id: record.agent_id.clone(),
+                record_id: record.id.clone(),
+                command: record.command.clone(),
+                scheduled: record.scheduled,
+            }))
+            .chain(self.completion_history.iter().map(|(timestamp, agent_id)| {
+                TimelineEvent::CommandCompleted { timestamp: *timestamp, agent_id: agent_id.clone() }
+            }))
+            .chain(self.error_history.iter().map(|(timestamp, agent_id, message)| {
+                TimelineEvent::ErrorOccurred {
+                    timestamp: *timestamp,
+                    agent_id: agent_id.clone(),
+                    message: message.clone(),
+                }
+            }))
+            .chain(self.kill_history.iter().map(|(timestamp, agent_id)| TimelineEvent::AgentKilled {
+                timestamp: *timestamp,
+                agent_id: agent_id.clone(),
+            }))
+            .chain(self.restart_history.iter().map(|(timestamp, agent_id)| {
+                TimelineEvent::AgentRestarted { timestamp: *timestamp, agent_id: agent_id.clone() }
+            }))
+            .collect();
+        events.sort_by_key(TimelineEvent::timestamp);
+        events
+    }
+}
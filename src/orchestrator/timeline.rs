@@ -0,0 +1,51 @@
+// Session Timeline - Agent lifecycle, commands and errors in one ordered list
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// One entry of `AgentOrchestrator::get_session_timeline`. Serialized with
+/// a `type` tag, e.g. `{"type": "agent_spawned", "timestamp": "...",
+/// "agent_id": "..."}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineEvent {
+    AgentSpawned { timestamp: DateTime<Utc>, agent_id: String },
+    AgentKilled { timestamp: DateTime<Utc>, agent_id: String },
+    /// A `task_history` record.
+    CommandSent {
+        timestamp: DateTime<Utc>,
+        agent_id: String,
+        record_id: String,
+        command: String,
+        scheduled: bool,
+    },
+    /// The agent came back to its prompt after a command, as awaited by
+    /// `await_agent_response` or a workflow step.
+    CommandCompleted { timestamp: DateTime<Utc>, agent_id: String },
+    ErrorOccurred { timestamp: DateTime<Utc>, agent_id: String, message: String },
+    /// Killed and spawned again by `reset_agent`.
+    AgentRestarted { timestamp: DateTime<Utc>, agent_id: String },
+}
+
+impl TimelineEvent {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            TimelineEvent::AgentSpawned { timestamp, .. }
+            | TimelineEvent::AgentKilled { timestamp, .. }
+            | TimelineEvent::CommandSent { timestamp, .. }
+            | TimelineEvent::CommandCompleted { timestamp, .. }
+            | TimelineEvent::ErrorOccurred { timestamp, .. }
+            | TimelineEvent::AgentRestarted { timestamp, .. } => *timestamp,
+        }
+    }
+
+    pub fn agent_id(&self) -> &str {
+        match self {
+            TimelineEvent::AgentSpawned { agent_id, .. }
+            | TimelineEvent::AgentKilled { agent_id, .. }
+            | TimelineEvent::CommandSent { agent_id, .. }
+            | TimelineEvent::CommandCompleted { agent_id, .. }
+            | TimelineEvent::ErrorOccurred { agent_id, .. }
+            | TimelineEvent::AgentRestarted { agent_id, .. } => agent_id,
+        }
+    }
+}
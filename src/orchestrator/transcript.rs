@@ -0,0 +1,162 @@
+// Agent Transcripts - One agent's metadata, commands and output as a document
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::colorize::{ColorScheme, SgrParser};
+use super::patterns::is_echo;
+use super::session_state::{AgentSession, TaskRecord};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    /// Plain text, escape sequences removed.
+    Txt,
+    /// Markdown with the commands and output in code blocks, escape
+    /// sequences removed.
+    Md,
+    /// asciicast v2, replaying the scrollback as a single frame.
+    Cast,
+}
+
+/// What a transcript is rendered from.
+pub struct Transcript<'a> {
+    pub agent: &'a AgentSession,
+    pub records: &'a [TaskRecord],
+    /// Scrollback lines as received, escape sequences included.
+    pub output: &'a [String],
+    pub rows: u16,
+    pub cols: u16,
+    /// Leave out output lines that only echo a recorded command (txt and md
+    /// only; a cast is a raw terminal view and keeps them).
+    pub suppress_echo: bool,
+}
+
+impl Transcript<'_> {
+    pub fn render(&self, format: TranscriptFormat) -> String {
+        match format {
+            TranscriptFormat::Txt => self.render_txt(),
+            TranscriptFormat::Md => self.render_md(),
+            TranscriptFormat::Cast => self.render_cast(),
+        }
+    }
+
+    fn commands(&self) -> String {
+        self.records.iter()
+            .map(|record| format!("[{}] {}\n", record.timestamp.to_rfc3339(), record.command))
+            .collect()
+    }
+
+    fn plain_output(&self) -> String {
+        let mut parser = SgrParser::new();
+        let scheme = ColorScheme::default();
+        let lines: Vec<String> = self.output.iter()
+            .map(|line| {
+                parser.push(line.as_bytes(), &scheme)
+                    .into_iter()
+                    .map(|chunk| chunk.text)
+                    .collect()
+            })
+            .collect();
+        let echoes = if self.suppress_echo {
+            let commands: Vec<&str> = self.records.iter().map(|r| r.command.as_str()).collect();
+            echo_lines(&lines, &commands)
+        } else {
+            vec![false; lines.len()]
+        };
+        lines.into_iter()
+            .zip(echoes)
+            .filter(|(_, echo)| !echo)
+            .map(|(line, _)| line + "\n")
+            .collect()
+    }
+
+    fn render_txt(&self) -> String {
+        format!(
+            "Agent {} ({})\nStarted: {}\nLast activity: {}\nCommands sent: {}\n\n\
+             == Commands ==\n{}\n== Output ==\n{}",
+            self.agent.id,
+            self.agent.agent_type,
+            self.agent.started_at.to_rfc3339(),
+            self.agent.last_activity.to_rfc3339(),
+            self.agent.commands_sent,
+            self.commands(),
+            self.plain_output(),
+        )
+    }
+
+    fn render_md(&self) -> String {
+        format!(
+            "# Agent {}\n\n- **Type:** {}\n- **Started:** {}\n- **Last activity:** {}\n\
+             - **Commands sent:** {}\n\n## Commands\n\n{}\n## Output\n\n{}",
+            self.agent.id,
+            self.agent.agent_type,
+            self.agent.started_at.to_rfc3339(),
+            self.agent.last_activity.to_rfc3339(),
+            self.agent.commands_sent,
+            code_block(&self.commands()),
+            code_block(&self.plain_output()),
+        )
+    }
+
+    fn render_cast(&self) -> String {
+        let header = json!({
+            "version": 2,
+            "width": self.cols,
+            "height": self.rows,
+            "timestamp": self.agent.started_at.timestamp(),
+            "title": format!("{} ({})", self.agent.id, self.agent.agent_type),
+        });
+        let frame = json!([0.0, "o", self.output.join("\r\n")]);
+        format!("{}\n{}\n", header, frame)
+    }
+}
+
+/// Commands before the newest unmatched one that an output line may echo,
+/// so one command whose echo never shows up doesn't stop matching the rest.
+const ECHO_LOOKAHEAD: usize = 4;
+
+/// Flags the output lines that echo `commands`. Lines are matched from the
+/// newest back, as the scrollback may have dropped the echoes of the first
+/// commands but keeps those of the latest. A line is an echo when it ends
+/// with one of the newest few commands not yet matched, as `is_echo` has
+/// it, so a prompt in front of it doesn't matter. Lines next to the
+/// echo of the last matched command that repeat it count too, as readline
+/// redraws input typed before the prompt appeared.
+///
+/// This only finds echoes that land on a single line. It misses commands
+/// the terminal wrapped or that span several lines, and input typed into a
+/// pager or other full-screen program, which redraws the screen instead of
+/// echoing. A command mentioned in output at the end of a line, after its
+/// own echo, is taken for the echo.
+fn echo_lines(lines: &[String], commands: &[&str]) -> Vec<bool> {
+    let mut echoes = vec![false; lines.len()];
+    let mut unmatched = commands;
+    let mut last_matched: Option<&str> = None;
+    for (index, line) in lines.iter().enumerate().rev() {
+        if last_matched.is_some_and(|command| is_echo(line, command)) {
+            echoes[index] = true;
+            continue;
+        }
+        let window = unmatched.len().saturating_sub(ECHO_LOOKAHEAD);
+        let found = unmatched[window..].iter()
+            .rposition(|command| is_echo(line, command));
+        match found {
+            Some(offset) => {
+                last_matched = Some(unmatched[window + offset]);
+                unmatched = &unmatched[..window + offset];
+                echoes[index] = true;
+            }
+            None => last_matched = None,
+        }
+    }
+    echoes
+}
+
+/// Fences `text` with more backticks than it contains in a row, so output
+/// that itself holds code fences can't end the block early.
+fn code_block(text: &str) -> String {
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let newline = if text.ends_with('\n') || text.is_empty() { "" } else { "\n" };
+    format!("{}text\n{}{}{}\n", fence, text, newline, fence)
+}
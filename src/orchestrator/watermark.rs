@@ -0,0 +1,109 @@
+// Output Watermarks - Knowing when an agent's output has passed a point
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use super::colorize::plain_text;
+use super::output_filter::LinePredicate;
+
+const SENTINEL_PREFIX: &str = "__WATERMARK_";
+const SENTINEL_SUFFIX: &str = "__";
+
+/// The line an agent prints for the watermark `marker`.
+pub(crate) fn sentinel(marker: &str) -> String {
+    format!("{}{}{}", SENTINEL_PREFIX, marker, SENTINEL_SUFFIX)
+}
+
+/// The shell command printing the sentinel of `marker`. It is printed in
+/// two parts, so a terminal's echo of the command doesn't contain it.
+pub(crate) fn sentinel_command(marker: &str) -> String {
+    format!("printf '%s%s\\n' {} {}{}", SENTINEL_PREFIX, marker, SENTINEL_SUFFIX)
+}
+
+/// Markers go into a shell command unquoted, so only letters, digits, `-`
+/// and `_` are accepted.
+pub(crate) fn is_valid_marker(marker: &str) -> bool {
+    !marker.is_empty() && marker.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Sentinels of one agent's watermarks, each with the sender that resolves
+/// it. A resolved sentinel stays until its handle is dropped, so readers
+/// that see its line later still drop it.
+#[derive(Clone, Default)]
+pub(crate) struct Watermarks {
+    pending: Arc<Mutex<HashMap<String, Option<oneshot::Sender<()>>>>>,
+}
+
+impl Watermarks {
+    pub fn register(&self, marker: &str) -> WatermarkHandle {
+        let (reached_tx, reached) = oneshot::channel();
+        let sentinel = sentinel(marker);
+        self.lock().insert(sentinel.clone(), Some(reached_tx));
+        WatermarkHandle {
+            id: marker.to_string(),
+            sentinel,
+            reached,
+            watermarks: self.clone(),
+        }
+    }
+
+    /// `filter` extended to drop sentinel lines and resolve their
+    /// watermarks; `filter` itself while no watermark is pending. Only
+    /// lines that are exactly a registered sentinel are dropped; the reader
+    /// holds back partial lines, so a sentinel is never seen in pieces.
+    pub fn filter(&self, filter: Option<LinePredicate>) -> Option<LinePredicate> {
+        if self.lock().is_empty() {
+            return filter;
+        }
+        let watermarks = self.clone();
+        Some(Arc::new(move |line: &str| {
+            let text = plain_text(line);
+            if let Some(reached) = watermarks.lock().get_mut(text.trim()) {
+                // Already taken when another reader saw the line first
+                if let Some(reached) = reached.take() {
+                    let _ = reached.send(());
+                }
+                return false;
+            }
+            filter.as_ref().is_none_or(|filter| filter(line))
+        }))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Option<oneshot::Sender<()>>>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Resolves once an agent's output has passed the point where
+/// `AgentProcess::output_watermark` was set. Dropping it forgets the
+/// watermark.
+pub struct WatermarkHandle {
+    id: String,
+    sentinel: String,
+    reached: oneshot::Receiver<()>,
+    watermarks: Watermarks,
+}
+
+impl WatermarkHandle {
+    /// The marker the watermark was created with.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Waits up to `timeout` for the sentinel to show up in the output.
+    pub async fn wait(mut self, timeout: Duration) -> Result<()> {
+        match tokio::time::timeout(timeout, &mut self.reached).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(_)) => bail!("Watermark {} was discarded before it was reached", self.id),
+            Err(_) => bail!("Watermark {} not reached within {} ms", self.id, timeout.as_millis()),
+        }
+    }
+}
+
+impl Drop for WatermarkHandle {
+    fn drop(&mut self) {
+        self.watermarks.lock().remove(&self.sentinel);
+    }
+}
@@ -0,0 +1,211 @@
+// Workflows - Declarative command plans spanning several agents
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// How long a step waits for its agent's response unless it sets
+/// `timeout_ms`.
+pub const DEFAULT_STEP_TIMEOUT_MS: u64 = 300_000;
+/// Characters of a step's response kept in `StepResult::output`.
+pub const OUTPUT_SNIPPET_CHARS: usize = 2_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowSpec {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// A command sent to one agent once the steps it depends on have
+/// succeeded. The step's output is the agent's response up to its next
+/// prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    pub id: String,
+    pub agent_id_or_alias: String,
+    /// `{{name}}` is replaced with the output of the step whose
+    /// `output_var` is `name`; that step must be among this one's direct or
+    /// indirect dependencies.
+    pub command_template: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub output_var: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    /// A dependency did not succeed.
+    Skipped,
+    /// Running or not yet started when the run was cancelled.
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub status: StepStatus,
+    /// The start of the response, with escape sequences removed.
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowStatus {
+    Succeeded,
+    /// At least one step failed or was skipped.
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowRun {
+    pub run_id: String,
+    pub name: Option<String>,
+    pub status: WorkflowStatus,
+    pub step_results: HashMap<String, StepResult>,
+    /// Step ids in the order they were planned.
+    pub order: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl StepResult {
+    pub(crate) fn succeeded(output: &str, duration_ms: u64) -> Self {
+        Self {
+            status: StepStatus::Succeeded,
+            output: Some(output.chars().take(OUTPUT_SNIPPET_CHARS).collect()),
+            error: None,
+            duration_ms,
+        }
+    }
+
+    pub(crate) fn ended(status: StepStatus, error: Option<String>, duration_ms: u64) -> Self {
+        Self { status, output: None, error, duration_ms }
+    }
+}
+
+impl WorkflowSpec {
+    /// Validates the spec and orders its steps so each comes after its
+    /// dependencies, otherwise keeping the order they are declared in.
+    pub fn plan(&self) -> Result<Vec<&WorkflowStep>> {
+        if self.steps.is_empty() {
+            bail!("Workflow has no steps");
+        }
+        let mut index = HashMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if index.insert(step.id.as_str(), i).is_some() {
+                bail!("Duplicate step id {}", step.id);
+            }
+        }
+        let mut setters = HashMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if let Some(var) = &step.output_var {
+                if setters.insert(var.as_str(), i).is_some() {
+                    bail!("Output variable {} is set by more than one step", var);
+                }
+            }
+        }
+
+        let mut dependents = vec![Vec::new(); self.steps.len()];
+        let mut waiting_on = vec![0usize; self.steps.len()];
+        for (i, step) in self.steps.iter().enumerate() {
+            for dep in &step.depends_on {
+                let &d = index.get(dep.as_str())
+                    .ok_or_else(|| anyhow!("Step {} depends on unknown step {}", step.id, dep))?;
+                if d == i {
+                    bail!("Step {} depends on itself", step.id);
+                }
+                dependents[d].push(i);
+                waiting_on[i] += 1;
+            }
+        }
+
+        let mut ready: VecDeque<usize> = (0..self.steps.len()).filter(|&i| waiting_on[i] == 0).collect();
+        let mut order = Vec::with_capacity(self.steps.len());
+        while let Some(i) = ready.pop_front() {
+            order.push(i);
+            for &j in &dependents[i] {
+                waiting_on[j] -= 1;
+                if waiting_on[j] == 0 {
+                    ready.push_back(j);
+                }
+            }
+        }
+        if order.len() < self.steps.len() {
+            let cycle: Vec<&str> = (0..self.steps.len())
+                .filter(|&i| waiting_on[i] > 0)
+                .map(|i| self.steps[i].id.as_str())
+                .collect();
+            bail!("Steps {} form a dependency cycle", cycle.join(", "));
+        }
+
+        let mut ancestors: Vec<HashSet<usize>> = vec![HashSet::new(); self.steps.len()];
+        for &i in &order {
+            let mut found = HashSet::new();
+            for dep in &self.steps[i].depends_on {
+                let d = index[dep.as_str()];
+                found.insert(d);
+                found.extend(ancestors[d].iter().copied());
+            }
+            ancestors[i] = found;
+        }
+        for &i in &order {
+            let step = &self.steps[i];
+            for part in parse_template(&step.command_template)? {
+                let TemplatePart::Var(var) = part else {
+                    continue;
+                };
+                match setters.get(var) {
+                    None => bail!("Step {} uses {{{{{}}}}}, which no step sets", step.id, var),
+                    Some(setter) if !ancestors[i].contains(setter) => bail!(
+                        "Step {} uses {{{{{}}}}} but does not depend on step {}, which sets it",
+                        step.id, var, self.steps[*setter].id
+                    ),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        Ok(order.into_iter().map(|i| &self.steps[i]).collect())
+    }
+}
+
+enum TemplatePart<'a> {
+    Text(&'a str),
+    Var(&'a str),
+}
+
+fn parse_template(template: &str) -> Result<Vec<TemplatePart<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        let close = rest[open..].find("}}")
+            .ok_or_else(|| anyhow!("Unterminated placeholder in {:?}", template))?;
+        parts.push(TemplatePart::Text(&rest[..open]));
+        parts.push(TemplatePart::Var(rest[open + 2..open + close].trim()));
+        rest = &rest[open + close + 2..];
+    }
+    parts.push(TemplatePart::Text(rest));
+    Ok(parts)
+}
+
+/// Fills a validated command template in from earlier steps' outputs.
+pub(crate) fn render(template: &str, vars: &HashMap<String, String>) -> Result<String> {
+    parse_template(template)?
+        .into_iter()
+        .map(|part| match part {
+            TemplatePart::Text(text) => Ok(text),
+            TemplatePart::Var(var) => vars.get(var)
+                .map(String::as_str)
+                .ok_or_else(|| anyhow!("Output variable {} is not set", var)),
+        })
+        .collect()
+}
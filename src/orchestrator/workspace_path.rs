@@ -0,0 +1,69 @@
+// Workspace Paths - Templates expanded into an agent's working directory
+use anyhow::{anyhow, bail, Result};
+use std::path::Path;
+
+use super::agent_manager::AgentType;
+
+/// Expands a `workspace_path` template and resolves it to an existing
+/// absolute directory. Supported, in this order:
+///
+/// - a leading `~` or `~/`, for `$HOME`;
+/// - `$NAME` and `${NAME}`, for environment variables, which must be set;
+/// - `{agent_id}`, `{agent_type}` (e.g. `claude`, `plugin:name`) and
+///   `{date}` (today, `YYYY-MM-DD` in local time).
+///
+/// A `$` not followed by a variable name is kept as is; any other `{...}`
+/// is an error.
+pub fn expand(template: &str, agent_id: &str, agent_type: &AgentType) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = template;
+    if rest == "~" || rest.starts_with("~/") {
+        expanded.push_str(&env_var("HOME")?);
+        rest = &rest[1..];
+    }
+
+    while let Some(i) = rest.find(['$', '{']) {
+        expanded.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if rest.as_bytes()[i] == b'{' {
+            let end = after.find('}')
+                .ok_or_else(|| anyhow!("Unterminated placeholder in workspace path {:?}", template))?;
+            match &after[..end] {
+                "agent_id" => expanded.push_str(agent_id),
+                "agent_type" => expanded.push_str(&agent_type.to_string()),
+                "date" => expanded.push_str(&chrono::Local::now().format("%Y-%m-%d").to_string()),
+                other => bail!("Unknown placeholder {{{}}} in workspace path {:?}", other, template),
+            }
+            rest = &after[end + 1..];
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}')
+                .ok_or_else(|| anyhow!("Unterminated variable in workspace path {:?}", template))?;
+            expanded.push_str(&env_var(&braced[..end])?);
+            rest = &braced[end + 1..];
+        } else {
+            let len = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            if len == 0 || after.as_bytes()[0].is_ascii_digit() {
+                expanded.push('$');
+            } else {
+                expanded.push_str(&env_var(&after[..len])?);
+            }
+            rest = &after[len..];
+        }
+    }
+    expanded.push_str(rest);
+
+    let resolved = Path::new(&expanded).canonicalize().map_err(|e| {
+        anyhow!("Workspace {} (from {:?}) does not exist: {}", expanded, template, e)
+    })?;
+    if !resolved.is_dir() {
+        bail!("Workspace {} (from {:?}) is not a directory", resolved.display(), template);
+    }
+    resolved.into_os_string().into_string()
+        .map_err(|path| anyhow!("Workspace {} is not valid UTF-8", Path::new(&path).display()))
+}
+
+fn env_var(name: &str) -> Result<String> {
+    std::env::var(name)
+        .map_err(|_| anyhow!("Environment variable {} in workspace path is not set", name))
+}
@@ -0,0 +1,112 @@
+// Workspace Tree - Listing the files in an agent's workspace
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use super::error::AgentError;
+
+/// Levels below the listed directory `get_workspace_file_tree` descends
+/// unless `OrchestratorConfig::workspace_tree_depth` says otherwise.
+pub const DEFAULT_TREE_DEPTH: usize = 3;
+
+/// Entries listed at most, so a huge workspace can't stall the caller.
+pub const MAX_TREE_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// Relative to the workspace, with `/` separators.
+    pub path: String,
+    /// 0 for directories.
+    pub size_bytes: u64,
+    pub modified_at: DateTime<Utc>,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceTree {
+    /// Sorted by path.
+    pub entries: Vec<FileEntry>,
+    /// Set when the listing stopped at `MAX_TREE_ENTRIES`.
+    pub truncated: bool,
+}
+
+/// Resolves `relative` inside `workspace`, following symlinks, and rejects
+/// anything that ends up outside it.
+pub(crate) fn resolve(agent_id: &str, workspace: &Path, relative: &str) -> Result<PathBuf> {
+    let root = workspace.canonicalize()
+        .with_context(|| format!("Workspace {} of agent {} is not accessible", workspace.display(), agent_id))?;
+    let denied = || AgentError::WorkspaceAccessDenied {
+        agent_id: agent_id.to_string(),
+        path: relative.to_string(),
+    };
+    if Path::new(relative).is_absolute() {
+        return Err(denied().into());
+    }
+    let resolved = root.join(relative).canonicalize()
+        .with_context(|| format!("{} is not in the workspace of agent {}", relative, agent_id))?;
+    if !resolved.starts_with(&root) {
+        return Err(denied().into());
+    }
+    Ok(resolved)
+}
+
+/// Lists `dir` and its subdirectories down to `max_depth` levels, with
+/// paths relative to `root`. Symlinks are listed but never followed, so
+/// nothing outside the workspace is read. Only `dir` itself has to be
+/// readable; subdirectories that can't be read are listed but not entered.
+pub(crate) async fn list(root: &Path, dir: &Path, max_depth: usize) -> Result<WorkspaceTree> {
+    let root = root.canonicalize()?;
+    let mut entries = Vec::new();
+    let mut truncated = false;
+    let mut pending = vec![(dir.to_path_buf(), 1)];
+    'walk: while let Some((path, depth)) = pending.pop() {
+        let mut read_dir = match tokio::fs::read_dir(&path).await {
+            Ok(read_dir) => read_dir,
+            Err(e) if path == dir => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+            Err(e) => {
+                debug!("Skipping unreadable {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        loop {
+            let entry = match read_dir.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(e) => {
+                    debug!("Stopped reading {}: {}", path.display(), e);
+                    break;
+                }
+            };
+            if entries.len() >= MAX_TREE_ENTRIES {
+                debug!("Workspace listing of {} stopped at {} entries", root.display(), MAX_TREE_ENTRIES);
+                truncated = true;
+                break 'walk;
+            }
+            // Not followed; a vanished entry is simply skipped
+            let Ok(metadata) = tokio::fs::symlink_metadata(entry.path()).await else {
+                continue;
+            };
+            let path = entry.path();
+            let relative = path.strip_prefix(&root).unwrap_or(&path);
+            let is_dir = metadata.is_dir();
+            entries.push(FileEntry {
+                path: relative.components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                size_bytes: if is_dir { 0 } else { metadata.len() },
+                modified_at: metadata.modified().map(DateTime::<Utc>::from).unwrap_or_default(),
+                is_dir,
+            });
+            if is_dir && depth < max_depth {
+                pending.push((path, depth + 1));
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(WorkspaceTree { entries, truncated })
+}
@@ -0,0 +1,142 @@
+// Workspace Watcher - Debounced filesystem change notifications per agent
+use anyhow::{Context, Result};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use super::ipc_bridge::{IpcBridge, IpcMessage, MessageType};
+
+/// Changes arriving within this long of the first change of a batch are
+/// reported together as one event.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Change {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// Watches an agent's workspace and emits `SystemEvent` messages listing
+/// created, modified and deleted paths. Dropping it stops the watcher and
+/// its debounce thread.
+pub struct WorkspaceWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl WorkspaceWatcher {
+    pub fn start(agent_id: String, workspace: &Path, ipc_bridge: Arc<IpcBridge>) -> Result<Self> {
+        let root = workspace.canonicalize()
+            .with_context(|| format!("Cannot watch workspace {}", workspace.display()))?;
+        
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+        
+        let thread_root = root.clone();
+        std::thread::spawn(move || debounce_loop(agent_id, thread_root, rx, ipc_bridge));
+        
+        info!("👀 Watching workspace {}", root.display());
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+/// Batches raw events for `DEBOUNCE` from the first one, then reports the
+/// batch; later events don't extend the window, so a steady stream of
+/// changes is reported every `DEBOUNCE`. Exits once the watcher (and with it
+/// the sender) is dropped.
+fn debounce_loop(
+    agent_id: String,
+    root: PathBuf,
+    rx: mpsc::Receiver<notify::Result<Event>>,
+    ipc_bridge: Arc<IpcBridge>,
+) {
+    while let Ok(first) = rx.recv() {
+        let mut changes = BTreeMap::new();
+        record_event(&root, first, &mut changes);
+        
+        let deadline = Instant::now() + DEBOUNCE;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(event) => record_event(&root, event, &mut changes),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        
+        if changes.is_empty() {
+            continue;
+        }
+        let listed = |kind: Change| -> Vec<String> {
+            changes.iter()
+                .filter(|(_, change)| **change == kind)
+                .map(|(path, _)| path.to_string_lossy().into_owned())
+                .collect()
+        };
+        let message = IpcMessage {
+            agent_id: agent_id.clone(),
+            message_type: MessageType::SystemEvent,
+            payload: serde_json::json!({
+                "event": "workspace_changed",
+                "root": root.to_string_lossy(),
+                "created": listed(Change::Created),
+                "modified": listed(Change::Modified),
+                "deleted": listed(Change::Deleted),
+            }),
+            timestamp: chrono::Utc::now(),
+            batch_id: None,
+            correlation_id: None,
+        };
+        if let Err(e) = ipc_bridge.send_message(message) {
+            debug!("No IPC subscribers for workspace change of {}: {}", agent_id, e);
+        }
+    }
+}
+
+/// Folds one raw event into `changes`, keyed by path relative to `root`.
+/// Each path keeps its latest change, except that a modification doesn't
+/// turn a creation into one.
+fn record_event(root: &Path, event: notify::Result<Event>, changes: &mut BTreeMap<PathBuf, Change>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            debug!("Workspace watcher error: {}", e);
+            return;
+        }
+    };
+    
+    let mut note = |path: &PathBuf, change: Change| {
+        let Ok(relative) = path.strip_prefix(root) else {
+            return;
+        };
+        let entry = changes.entry(relative.to_path_buf()).or_insert(change);
+        // A file created within the window and then modified is still new
+        if !(*entry == Change::Created && change == Change::Modified) {
+            *entry = change;
+        }
+    };
+    
+    match event.kind {
+        EventKind::Create(_) => event.paths.iter().for_each(|p| note(p, Change::Created)),
+        EventKind::Remove(_) => event.paths.iter().for_each(|p| note(p, Change::Deleted)),
+        EventKind::Modify(ModifyKind::Name(mode)) => match (mode, event.paths.as_slice()) {
+            (RenameMode::Both, [from, to]) => {
+                note(from, Change::Deleted);
+                note(to, Change::Created);
+            }
+            (RenameMode::From, paths) => paths.iter().for_each(|p| note(p, Change::Deleted)),
+            (RenameMode::To, paths) => paths.iter().for_each(|p| note(p, Change::Created)),
+            (_, paths) => paths.iter().for_each(|p| note(p, Change::Modified)),
+        },
+        EventKind::Modify(_) => event.paths.iter().for_each(|p| note(p, Change::Modified)),
+        _ => {}
+    }
+}
@@ -0,0 +1,156 @@
+// 🔌 Plugin API - Native Rust agent extensions loaded at runtime
+//
+// A plugin is a `cdylib` exporting `conductor_plugin_init`, which hands back a
+// boxed `AgentPlugin`. The trait objects cross the library boundary with the
+// Rust ABI, so plugins must be built with the same compiler and against the
+// same version of this crate as the host. See `examples/hello_plugin`.
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use libloading::Library;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::orchestrator::AgentConfig;
+
+/// Environment variable holding the directories that are scanned for plugins.
+pub const PLUGIN_DIR_ENV: &str = "CONDUCTOR_PLUGIN_DIR";
+
+/// Symbol every plugin library must export.
+pub const PLUGIN_INIT_SYMBOL: &[u8] = b"conductor_plugin_init";
+
+/// Signature of the exported `conductor_plugin_init` function.
+pub type PluginInitFn = fn() -> Box<dyn AgentPlugin>;
+
+/// What a plugin's agents can do beyond plain line-based commands.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PluginFeatures {
+    pub raw_input: bool,
+    pub resize: bool,
+    pub streaming_output: bool,
+}
+
+/// The operations the orchestrator needs from a running agent, whether it is
+/// a native PTY agent or one provided by a plugin.
+#[async_trait]
+pub trait AgentProcessTrait: Send + Sync {
+    fn id(&self) -> &str;
+    async fn send_command(&self, command: &str) -> Result<()>;
+    async fn send_raw(&self, data: &[u8]) -> Result<()>;
+    async fn resize(&self, rows: u16, cols: u16) -> Result<()>;
+    async fn get_output(&self) -> Option<Vec<u8>>;
+    async fn kill(&self) -> Result<()>;
+    async fn get_status(&self) -> serde_json::Value;
+}
+
+pub trait AgentPlugin: Send + Sync {
+    fn name(&self) -> &str;
+    fn spawn(&self, config: &AgentConfig) -> Result<Box<dyn AgentProcessTrait>>;
+    fn supported_features(&self) -> PluginFeatures;
+}
+
+struct LoadedPlugin {
+    // Declared before `_library` so the plugin is dropped while its code is
+    // still mapped.
+    plugin: Box<dyn AgentPlugin>,
+    path: PathBuf,
+    _library: Library,
+}
+
+#[derive(Default)]
+pub struct PluginManager {
+    plugins: HashMap<String, LoadedPlugin>,
+}
+
+impl PluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every plugin found in the directories listed in
+    /// `CONDUCTOR_PLUGIN_DIR`. Broken plugins are logged and skipped.
+    pub fn from_env() -> Self {
+        let mut manager = Self::new();
+        if let Some(dirs) = std::env::var_os(PLUGIN_DIR_ENV) {
+            for dir in std::env::split_paths(&dirs) {
+                manager.load_dir(&dir);
+            }
+        }
+        manager
+    }
+
+    pub fn load_dir(&mut self, dir: &Path) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Cannot read plugin directory {}: {}", dir.display(), e);
+                return;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !Self::is_plugin_library(&path) {
+                continue;
+            }
+            if let Err(e) = self.load(&path) {
+                warn!("Skipping plugin {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Opens a single plugin library and registers the plugin it exports.
+    pub fn load(&mut self, path: &Path) -> Result<String> {
+        // SAFETY: loading a library runs its initializers and trusts the
+        // exported symbol to match `PluginInitFn`; plugins are trusted code
+        // the user explicitly placed in the plugin directory.
+        let (library, plugin) = unsafe {
+            let library = Library::new(path)?;
+            let init = library.get::<PluginInitFn>(PLUGIN_INIT_SYMBOL)?;
+            let plugin = init();
+            (library, plugin)
+        };
+
+        let name = plugin.name().to_string();
+        if self.plugins.contains_key(&name) {
+            return Err(anyhow!("a plugin named {} is already registered", name));
+        }
+
+        info!("🔌 Loaded plugin {} from {}", name, path.display());
+        self.plugins.insert(name.clone(), LoadedPlugin {
+            plugin,
+            path: path.to_path_buf(),
+            _library: library,
+        });
+        Ok(name)
+    }
+
+    pub fn spawn(&self, name: &str, config: &AgentConfig) -> Result<Box<dyn AgentProcessTrait>> {
+        let loaded = self.plugins.get(name)
+            .ok_or_else(|| anyhow!("Plugin {} not loaded", name))?;
+        loaded.plugin.spawn(config)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.plugins.contains_key(name)
+    }
+
+    pub fn list(&self) -> Vec<serde_json::Value> {
+        self.plugins.values()
+            .map(|loaded| serde_json::json!({
+                "name": loaded.plugin.name(),
+                "path": loaded.path.display().to_string(),
+                "features": loaded.plugin.supported_features(),
+            }))
+            .collect()
+    }
+
+    fn is_plugin_library(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(OsStr::to_str),
+            Some("so") | Some("dylib") | Some("dll")
+        )
+    }
+}
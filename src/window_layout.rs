@@ -0,0 +1,66 @@
+// Window Layout - Placement of agent windows on the primary monitor
+use serde::Deserialize;
+
+/// Default agent window size, in logical pixels.
+pub const AGENT_WINDOW_SIZE: (f64, f64) = (1024.0, 768.0);
+/// Offset between successive windows in a cascade.
+const CASCADE_STEP: f64 = 32.0;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    Tile,
+    Cascade,
+}
+
+/// A window's position and size, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Positions for `count` windows within `screen`, in window order.
+pub fn arrange(layout: Layout, count: usize, screen: Rect) -> Vec<Rect> {
+    match layout {
+        Layout::Tile => tile(count, screen),
+        Layout::Cascade => (0..count).map(|i| cascade_slot(i, screen)).collect(),
+    }
+}
+
+/// Near-square grid filling the screen.
+fn tile(count: usize, screen: Rect) -> Vec<Rect> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let cols = (count as f64).sqrt().ceil() as usize;
+    let rows = count.div_ceil(cols);
+    let width = screen.width / cols as f64;
+    let height = screen.height / rows as f64;
+    (0..count)
+        .map(|i| Rect {
+            x: screen.x + (i % cols) as f64 * width,
+            y: screen.y + (i / cols) as f64 * height,
+            width,
+            height,
+        })
+        .collect()
+}
+
+/// The `index`-th cascade position, wrapping back to the top-left once a
+/// window would run off the screen.
+pub fn cascade_slot(index: usize, screen: Rect) -> Rect {
+    let width = AGENT_WINDOW_SIZE.0.min(screen.width);
+    let height = AGENT_WINDOW_SIZE.1.min(screen.height);
+    let room = ((screen.width - width).min(screen.height - height) / CASCADE_STEP).floor();
+    let steps = (room as usize).max(1);
+    let offset = (index % steps) as f64 * CASCADE_STEP;
+    Rect {
+        x: screen.x + offset,
+        y: screen.y + offset,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,112 @@
+// A/B tests of two agents judged by a third.
+use conductor_max::orchestrator::{AbVerdict, AgentConfig, AgentOrchestrator, AgentType};
+
+/// A bash agent that echoes each prompt after `prefix`.
+async fn echo_agent(orchestrator: &AgentOrchestrator, prefix: &str) -> String {
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.command_prefix = Some(format!("echo {}", prefix));
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    orchestrator.resize(&agent_id, 24, 400).await.unwrap();
+    agent_id
+}
+
+/// A bash agent that reads each question as a heredoc and prefers the
+/// response starting with `long`. The judging is a script on its PATH
+/// rather than a startup command, whose prompt could still be due when the
+/// first question is asked and be taken for the end of its answer.
+async fn judge_agent(orchestrator: &AgentOrchestrator) -> String {
+    let dir = std::env::temp_dir().join(format!("conductor-judge-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("judge");
+    std::fs::write(&script, "#!/bin/sh\ncase \"$(cat)\" in *\"A: long\"*) echo A;; *\"B: long\"*) echo B;; *) echo tie;; esac\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.path_prepend = vec![dir];
+    config.command_prefix = Some("judge <<'EOF'\n".to_string());
+    config.command_suffix = Some("\nEOF".to_string());
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    orchestrator.resize(&agent_id, 24, 400).await.unwrap();
+    agent_id
+}
+
+#[tokio::test]
+async fn judge_decides_each_round() {
+    let orchestrator = AgentOrchestrator::new();
+    let long = echo_agent(&orchestrator, "long-").await;
+    let short = echo_agent(&orchestrator, "").await;
+    let same = echo_agent(&orchestrator, "").await;
+    let judge = judge_agent(&orchestrator).await;
+    let prompts = vec!["first".to_string(), "second".to_string()];
+
+    let operation = orchestrator.begin_operation(None).unwrap();
+    let result = orchestrator.run_ab_test(&long, &short, prompts.clone(), judge.clone(), &operation).await.unwrap();
+    assert_eq!((result.a_wins, result.b_wins, result.ties), (2, 0, 0));
+    assert_eq!(result.per_prompt[0].prompt, "first");
+    assert_eq!(result.per_prompt[0].response_a, "long-first");
+    assert_eq!(result.per_prompt[1].response_b, "second");
+
+    let result = orchestrator.run_ab_test(&short, &long, prompts.clone(), judge.clone(), &operation).await.unwrap();
+    assert_eq!((result.a_wins, result.b_wins, result.ties), (0, 2, 0));
+    assert!(result.per_prompt.iter().all(|round| round.verdict == AbVerdict::B));
+
+    let result = orchestrator.run_ab_test(&short, &same, prompts, judge, &operation).await.unwrap();
+    assert_eq!(result.ties, 2);
+}
+
+#[tokio::test]
+async fn queued_commands_go_first() {
+    let orchestrator = AgentOrchestrator::new();
+    let long = echo_agent(&orchestrator, "long-").await;
+    let short = echo_agent(&orchestrator, "").await;
+    let judge = judge_agent(&orchestrator).await;
+    orchestrator.queue_command(&long, "queued".to_string()).await.unwrap();
+
+    let operation = orchestrator.begin_operation(None).unwrap();
+    let result = orchestrator.run_ab_test(&long, &short, vec!["asked".to_string()], judge, &operation).await.unwrap();
+    assert_eq!(result.per_prompt[0].response_a, "long-asked");
+    assert_eq!(orchestrator.get_pending_queue_depth(&long).unwrap(), 0);
+    let history = orchestrator.get_task_history(Some(&long)).await;
+    let commands: Vec<&str> = history.iter().map(|record| record.command.as_str()).collect();
+    assert_eq!(commands, ["queued", "asked"]);
+}
+
+#[tokio::test]
+async fn cancelled_or_invalid_tests_fail() {
+    let orchestrator = AgentOrchestrator::new();
+    let a = echo_agent(&orchestrator, "").await;
+    let b = echo_agent(&orchestrator, "").await;
+    let judge = judge_agent(&orchestrator).await;
+
+    let operation = orchestrator.begin_operation(Some("ab-run".to_string())).unwrap();
+    orchestrator.cancel_operation("ab-run").unwrap();
+    let error = orchestrator.run_ab_test(&a, &b, vec!["x".to_string()], judge.clone(), &operation).await.unwrap_err();
+    assert!(error.to_string().contains("cancelled"), "{}", error);
+
+    let operation = orchestrator.begin_operation(None).unwrap();
+    assert!(orchestrator.run_ab_test(&a, &a, vec!["x".to_string()], judge.clone(), &operation).await.is_err());
+    assert!(orchestrator.run_ab_test(&a, "missing", vec!["x".to_string()], judge, &operation).await.is_err());
+}
+
+#[test]
+fn verdicts_read_the_stated_choice() {
+    let question = "A: one\nB: two\nWhich is better?";
+    let cases = [
+        ("A is better than B", AbVerdict::A),
+        ("Response B beats A here", AbVerdict::B),
+        ("b", AbVerdict::B),
+        ("It's a tie, though A is shorter", AbVerdict::Tie),
+        ("B, it is not a tie", AbVerdict::B),
+        ("A is clearer.\nVerdict: b", AbVerdict::B),
+        ("**VERDICT:** TIE", AbVerdict::Tie),
+        ("Both are fine", AbVerdict::Tie),
+        // The echoed question doesn't count
+        ("> Which is better?\nA", AbVerdict::A),
+    ];
+    for (judgement, verdict) in cases {
+        assert_eq!(AbVerdict::parse(judgement, question), verdict, "{:?}", judgement);
+    }
+}
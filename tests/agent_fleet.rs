@@ -0,0 +1,42 @@
+// Exporting the running agents' configs and spawning them again.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, TtyType};
+
+mod common;
+
+#[tokio::test]
+async fn exported_fleet_imports_into_a_new_orchestrator() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut ids = Vec::new();
+    for prefix in ["# first ", "# second "] {
+        let mut config = common::pipe_bash_config();
+        config.command_prefix = Some(prefix.to_string());
+        config.startup_commands = vec!["true".to_string()];
+        ids.push(orchestrator.spawn_agent(config).await.unwrap());
+    }
+
+    let fleet = orchestrator.export_agents_config().await.unwrap();
+    let json = serde_json::to_string(&fleet).unwrap();
+    let fleet: Vec<AgentConfig> = serde_json::from_str(&json).unwrap();
+    assert_eq!(fleet.len(), 2);
+    assert!(fleet.iter().all(|config| config.agent_id.is_none()));
+    assert_eq!(fleet[1].command_prefix.as_deref(), Some("# second "));
+
+    // Fresh ids, so the fleet imports alongside the agents it came from
+    let imported = orchestrator.import_agents_config(fleet.clone()).await.unwrap();
+    assert_eq!(imported.len(), 2);
+    assert!(imported.iter().all(|id| !ids.contains(id)));
+
+    let restored = AgentOrchestrator::new();
+    restored.import_agents_config(fleet.clone()).await.unwrap();
+    let configs = restored.export_agents_config().await.unwrap();
+    assert_eq!(configs[1].startup_commands, ["true"]);
+    assert_eq!(configs[1].tty_type, TtyType::Pipe);
+
+    // An id already taken fails the import, which spawns nothing
+    let mut clashing = fleet;
+    clashing[1].agent_id = Some("taken".to_string());
+    let fresh = AgentOrchestrator::new();
+    fresh.spawn_agent(AgentConfig { agent_id: Some("taken".to_string()), ..clashing[0].clone() }).await.unwrap();
+    assert!(fresh.import_agents_config(clashing).await.is_err());
+    assert_eq!(fresh.list_agents().await.len(), 1);
+}
@@ -0,0 +1,79 @@
+// The PATH agents are spawned with.
+use conductor_max::orchestrator::{AgentConfig, AgentManager, AgentOrchestrator};
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod common;
+
+/// A directory holding an executable `conductor-path-tool`.
+fn tool_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("conductor-path-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let tool = dir.join("conductor-path-tool");
+    std::fs::write(&tool, "#!/bin/sh\necho tool ran\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    dir
+}
+
+fn bash_config(path_prepend: Vec<PathBuf>, inherit_path: bool) -> AgentConfig {
+    let mut config = common::pipe_bash_config();
+    config.path_prepend = path_prepend;
+    config.inherit_path = inherit_path;
+    config
+}
+
+/// Sends `command` and waits for a line starting with `prefix`.
+async fn output_line(orchestrator: &AgentOrchestrator, agent_id: &str, command: &str, prefix: &str) -> String {
+    orchestrator.send_command(agent_id, command).await.unwrap();
+    for _ in 0..50 {
+        let scrollback = orchestrator.get_scrollback(agent_id, None).await.unwrap();
+        if let Some(line) = scrollback.into_iter().find(|line| line.starts_with(prefix)) {
+            return line;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("no output starting with {:?}", prefix);
+}
+
+#[tokio::test]
+async fn prepended_directories_come_first() {
+    let dir = tool_dir();
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(bash_config(vec![dir.clone()], true)).await.unwrap();
+
+    let path = output_line(&orchestrator, &agent_id, "echo PATH=$PATH", "PATH=").await;
+    let entries: Vec<PathBuf> = std::env::split_paths(path.trim_start_matches("PATH=")).collect();
+    assert_eq!(entries[0], dir);
+    // The inherited PATH follows
+    let inherited: Vec<PathBuf> = std::env::split_paths(&std::env::var_os("PATH").unwrap()).collect();
+    assert!(entries.ends_with(&inherited), "{:?}", entries);
+    assert_eq!(output_line(&orchestrator, &agent_id, "conductor-path-tool", "tool").await, "tool ran");
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn without_inheritance_only_prepended_directories_remain() {
+    let dir = tool_dir();
+    let path_prepend = vec![dir.clone(), PathBuf::from("/bin"), PathBuf::from("/usr/bin")];
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(bash_config(path_prepend.clone(), false)).await.unwrap();
+
+    let path = output_line(&orchestrator, &agent_id, "echo PATH=$PATH", "PATH=").await;
+    let expected = std::env::join_paths(&path_prepend).unwrap();
+    assert_eq!(path, format!("PATH={}", expected.to_string_lossy()));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+
+    // Nothing left to find the shell on
+    assert!(AgentManager::validate(&bash_config(Vec::new(), false)).is_err());
+}
+
+#[test]
+fn path_settings_default_to_inheriting() {
+    let config: AgentConfig = serde_json::from_str(r#"{"agent_type": "bash"}"#).unwrap();
+    assert!(config.inherit_path);
+    assert!(config.path_prepend.is_empty());
+}
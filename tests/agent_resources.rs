@@ -0,0 +1,42 @@
+// Resource usage of agent processes.
+use conductor_max::orchestrator::AgentOrchestrator;
+
+mod common;
+
+#[tokio::test]
+async fn reports_usage_until_the_process_exits() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+
+    let usage = orchestrator.get_agent_resources(&agent_id).await.unwrap().expect("running agent has usage");
+    assert!(usage.rss_kb > 0);
+    assert!(usage.open_fds > 0);
+    assert_eq!(usage.cpu_percent, 0.0);
+
+    orchestrator.send_command(&agent_id, "exit").await.unwrap();
+    for _ in 0..50 {
+        if orchestrator.get_agent_resources(&agent_id).await.unwrap().is_none() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(orchestrator.get_agent_resources(&agent_id).await.unwrap().is_none());
+    assert!(orchestrator.get_agent_resources("missing").await.is_err());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn snapshots_summarize_all_agents() {
+    let orchestrator = AgentOrchestrator::new();
+    let first = common::spawn_pipe_bash(&orchestrator).await;
+    let second = common::spawn_pipe_bash(&orchestrator).await;
+
+    let snapshot = orchestrator.get_resource_snapshot();
+    assert_eq!(snapshot.per_agent.len(), 2);
+    assert_eq!(snapshot.total_rss_kb, snapshot.per_agent.values().map(|usage| usage.rss_kb).sum::<u64>());
+    let heaviest = snapshot.heaviest_agent.expect("an agent uses the most memory");
+    assert!(snapshot.per_agent.values().all(|usage| usage.rss_kb <= snapshot.per_agent[&heaviest].rss_kb));
+
+    orchestrator.kill_agent(&first).await.unwrap();
+    orchestrator.kill_agent(&second).await.unwrap();
+}
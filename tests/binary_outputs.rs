@@ -0,0 +1,82 @@
+// Binary output kept apart from the scrollback by MIME detection.
+use base64::Engine;
+use conductor_max::orchestrator::AgentOrchestrator;
+use serde_json::Value;
+use std::time::Duration;
+
+mod common;
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\xff";
+
+async fn detecting_agent(orchestrator: &AgentOrchestrator) -> String {
+    let mut config = common::pipe_bash_config();
+    config.output_mime_type_detection = true;
+    orchestrator.spawn_agent(config).await.unwrap()
+}
+
+async fn wait_for_binary(orchestrator: &AgentOrchestrator, agent_id: &str, count: usize) -> Vec<Value> {
+    for _ in 0..100 {
+        let outputs = orchestrator.get_binary_outputs(agent_id, 0).await.unwrap();
+        if outputs.len() >= count {
+            return outputs;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("no binary output from {}: {:?}", agent_id, orchestrator.get_scrollback(agent_id, None).await);
+}
+
+/// A command that writes `bytes` in one go, as a program printing a file
+/// would; printf may split its output at escapes.
+fn cat(bytes: &[u8]) -> String {
+    let path = std::env::temp_dir().join(format!("conductor-binary-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, bytes).unwrap();
+    format!("cat {}", path.display())
+}
+
+fn data(output: &Value) -> Vec<u8> {
+    base64::engine::general_purpose::STANDARD.decode(output["data"].as_str().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn keeps_signatures_out_of_the_scrollback() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = detecting_agent(&orchestrator).await;
+
+    orchestrator.send_command(&agent_id, &cat(b"before\n%PDF-1.4\n%\xe2\xe3\xcf\xd3\n")).await.unwrap();
+    let outputs = wait_for_binary(&orchestrator, &agent_id, 1).await;
+    assert_eq!(outputs[0]["index"], 0);
+    assert_eq!(outputs[0]["mime_type"], "application/pdf");
+    assert_eq!(data(&outputs[0]), b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n");
+
+    // Text that merely starts like a signature stays text
+    orchestrator.send_command(&agent_id, "echo '%PDF is a format'").await.unwrap();
+    assert!(common::wait_for_line(&orchestrator, &agent_id, "%PDF is a format").await);
+    let scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+    assert!(scrollback.contains(&"before".to_string()), "{:?}", scrollback);
+    assert!(!scrollback.iter().any(|line| line.starts_with("%PDF-")), "{:?}", scrollback);
+    assert_eq!(orchestrator.get_binary_outputs(&agent_id, 0).await.unwrap().len(), 1);
+    assert!(orchestrator.get_binary_outputs(&agent_id, 1).await.unwrap().is_empty());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn stderr_does_not_continue_an_output_opened_on_stdout() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = detecting_agent(&orchestrator).await;
+
+    orchestrator.send_command(&agent_id, &cat(PNG)).await.unwrap();
+    wait_for_binary(&orchestrator, &agent_id, 1).await;
+    // Not UTF-8, so the stdout reader would take it for more of the image
+    orchestrator.send_command(&agent_id, &format!("{} >&2", cat(b"caf\xe9 on stderr\n"))).await.unwrap();
+    assert!(common::wait_for_line(&orchestrator, &agent_id, "caf\u{fffd} on stderr").await);
+    let scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+    orchestrator.send_command(&agent_id, "echo done").await.unwrap();
+    assert!(common::wait_for_line(&orchestrator, &agent_id, "done").await);
+
+    let outputs = orchestrator.get_binary_outputs(&agent_id, 0).await.unwrap();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0]["mime_type"], "image/png");
+    assert_eq!(data(&outputs[0]), PNG);
+    assert!(!scrollback.iter().any(|line| line.contains("PNG")), "{:?}", scrollback);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
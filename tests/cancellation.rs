@@ -0,0 +1,70 @@
+// Cancelling long-running orchestrator calls.
+use conductor_max::orchestrator::{AgentOrchestrator, OperationError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod common;
+
+fn cancel_after(orchestrator: &Arc<AgentOrchestrator>, operation_id: &str, delay: Duration) {
+    let (orchestrator, operation_id) = (orchestrator.clone(), operation_id.to_string());
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        orchestrator.cancel_operation(&operation_id).unwrap();
+    });
+}
+
+fn assert_cancelled(error: anyhow::Error, operation_id: &str) {
+    match error.downcast_ref::<OperationError>() {
+        Some(OperationError::Cancelled { operation_id: id }) => assert_eq!(id, operation_id),
+        None => panic!("expected a Cancelled error, got: {}", error),
+    }
+}
+
+#[tokio::test]
+async fn cancelled_wait_for_all_returns_promptly() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    
+    let operation = orchestrator.begin_operation(Some("wait".into())).unwrap();
+    cancel_after(&orchestrator, "wait", Duration::from_millis(100));
+    let started = Instant::now();
+    let error = orchestrator.wait_for_all(Duration::from_secs(30), &operation).await.unwrap_err();
+    
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_cancelled(error, "wait");
+    // The agent is still running and registered
+    assert!(orchestrator.get_process(&agent_id).is_some());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn cancelled_send_command_ack_returns_promptly() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    
+    let operation = orchestrator.begin_operation(Some("ack".into())).unwrap();
+    cancel_after(&orchestrator, "ack", Duration::from_millis(50));
+    let started = Instant::now();
+    let error = orchestrator
+        .send_command_ack(&agent_id, "true", Some(5_000), &operation)
+        .await
+        .unwrap_err();
+    
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_cancelled(error, "ack");
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn finished_operations_are_unregistered() {
+    let orchestrator = AgentOrchestrator::new();
+    
+    let operation = orchestrator.begin_operation(Some("op".into())).unwrap();
+    assert!(orchestrator.begin_operation(Some("op".into())).is_err());
+    let results = orchestrator.wait_for_all(Duration::from_secs(1), &operation).await.unwrap();
+    assert!(results.is_empty());
+    drop(operation);
+    
+    assert!(orchestrator.cancel_operation("op").is_err());
+    assert!(orchestrator.begin_operation(Some("op".into())).is_ok());
+}
@@ -0,0 +1,59 @@
+// Keeping session and agent command counters in agreement.
+use conductor_max::orchestrator::{AgentOrchestrator, SessionState};
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn failed_sends_are_not_counted_anywhere() {
+    let orchestrator = AgentOrchestrator::new();
+    let live = common::spawn_pipe_bash(&orchestrator).await;
+    let exiting = common::spawn_pipe_bash(&orchestrator).await;
+
+    orchestrator.send_command(&live, "true").await.unwrap();
+    orchestrator.send_command(&exiting, "exit").await.unwrap();
+    assert!(orchestrator.send_command("missing", "true").await.is_err());
+
+    // Once bash has exited, writes to its stdin fail
+    let mut failed = false;
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        if orchestrator.send_command(&exiting, "true").await.is_err() {
+            failed = true;
+            break;
+        }
+    }
+    assert!(failed, "writes to the exited agent kept succeeding");
+    orchestrator.broadcast_to_strategy("true").await.unwrap();
+
+    let session = orchestrator.export_session().await;
+    let mut total = 0;
+    for agent_id in [&live, &exiting] {
+        let status = orchestrator.get_agent_status(agent_id).await.unwrap();
+        assert_eq!(status["commands_sent"], session["agents"][agent_id]["commands_sent"]);
+        total += status["commands_sent"].as_u64().unwrap();
+    }
+    assert_eq!(session["agents"][&live]["commands_sent"], 2);
+    assert_eq!(session["total_commands"], total);
+
+    let report = orchestrator.reconcile_command_counts().await;
+    assert_eq!(report["corrected"], serde_json::json!({}));
+    assert_eq!(report["total_commands"], total);
+}
+
+#[test]
+fn reconcile_moves_total_with_the_agent_count() {
+    let mut session = SessionState::new();
+    session.register_agent("a".into(), "bash".into());
+    session.register_agent("b".into(), "bash".into());
+    for agent_id in ["a", "a", "a", "b"] {
+        session.log_command(agent_id, "true");
+    }
+
+    assert_eq!(session.reconcile_commands_sent("a", 1), Some(3));
+    assert_eq!(session.reconcile_commands_sent("b", 1), None);
+    assert_eq!(session.reconcile_commands_sent("missing", 1), None);
+    assert_eq!(session.agents["a"].commands_sent, 1);
+    assert_eq!(session.total_commands, 2);
+    assert_eq!(session.task_history.len(), 4);
+}
@@ -0,0 +1,23 @@
+// Previewing commands with `command_dry_run`.
+use conductor_max::orchestrator::AgentOrchestrator;
+
+mod common;
+
+#[tokio::test]
+async fn checks_shell_syntax_without_sending() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+
+    let ok = orchestrator.command_dry_run(&agent_id, "echo hello".into()).await.unwrap();
+    assert!(ok.syntax_ok);
+    assert_eq!(ok.estimated_tokens, 3);
+
+    let broken = orchestrator.command_dry_run(&agent_id, "echo (".into()).await.unwrap();
+    assert!(!broken.syntax_ok);
+    assert!(!broken.warnings.is_empty());
+
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!(status["commands_sent"], 0);
+    assert_eq!(orchestrator.export_session().await["total_commands"], 0);
+    assert!(orchestrator.command_dry_run("missing", "true".into()).await.is_err());
+}
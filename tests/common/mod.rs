@@ -0,0 +1,31 @@
+// Helpers shared by the integration tests. Each test binary compiles its
+// own copy and uses only some of them.
+#![allow(dead_code)]
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, TtyType};
+use std::time::Duration;
+
+/// A bash agent on pipes rather than a PTY: its output has no prompts or
+/// echoes, and it reads stdin until it is killed.
+pub fn pipe_bash_config() -> AgentConfig {
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.tty_type = TtyType::Pipe;
+    config
+}
+
+/// Spawns a bash agent from `pipe_bash_config` and returns its id.
+pub async fn spawn_pipe_bash(orchestrator: &AgentOrchestrator) -> String {
+    orchestrator.spawn_agent(pipe_bash_config()).await.unwrap()
+}
+
+/// Waits up to five seconds for a scrollback line equal to `line`, ignoring
+/// trailing whitespace.
+pub async fn wait_for_line(orchestrator: &AgentOrchestrator, agent_id: &str, line: &str) -> bool {
+    for _ in 0..50 {
+        let scrollback = orchestrator.get_scrollback(agent_id, None).await.unwrap();
+        if scrollback.iter().any(|l| l.trim_end() == line) {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    false
+}
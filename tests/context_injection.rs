@@ -0,0 +1,58 @@
+// Spawning agents with context files injected before their id is handed out.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, OrchestratorConfig};
+use std::path::PathBuf;
+
+mod common;
+
+fn context_file(contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("conductor-context-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn returns_once_the_agent_is_ready_after_the_files() {
+    let orchestrator = AgentOrchestrator::new();
+    let files = vec![context_file("first file\n"), context_file("second file\n")];
+    let agent_id = orchestrator
+        .spawn_agent_with_context(AgentConfig::new(AgentType::Bash), files)
+        .await
+        .unwrap();
+
+    // Both files went in and the shell's prompt came back after them
+    let scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+    for contents in ["first file", "second file"] {
+        assert!(scrollback.iter().any(|line| line.trim_end() == contents), "{:?}", scrollback);
+    }
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn kills_an_agent_that_never_gets_ready() {
+    let orchestrator = AgentOrchestrator::with_config(OrchestratorConfig {
+        context_ready_timeout_ms: 300,
+        ..OrchestratorConfig::default()
+    });
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.patterns.readiness = Some("never shown".to_string());
+    let file = context_file("unsent\n");
+
+    let error = orchestrator.spawn_agent_with_context(config, vec![file]).await.unwrap_err();
+    assert!(error.to_string().contains("was not ready within 300 ms"), "{}", error);
+    assert!(orchestrator.list_agents().await.is_empty());
+}
+
+#[tokio::test]
+async fn agents_on_pipes_are_ready_right_away() {
+    let orchestrator = AgentOrchestrator::with_config(OrchestratorConfig {
+        context_ready_timeout_ms: 300,
+        ..OrchestratorConfig::default()
+    });
+    let file = context_file("piped\n");
+    let agent_id = orchestrator
+        .spawn_agent_with_context(common::pipe_bash_config(), vec![file])
+        .await
+        .unwrap();
+    assert!(common::wait_for_line(&orchestrator, &agent_id, "piped").await);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
@@ -0,0 +1,79 @@
+// Commands paired with the responses that followed them.
+use conductor_max::orchestrator::AgentOrchestrator;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+/// A pipe bash agent past its startup output. That goes to stderr, so the
+/// first command does too, to be read after it.
+async fn spawn_ready(orchestrator: &AgentOrchestrator, scrollback_lines: Option<usize>) -> String {
+    let mut config = common::pipe_bash_config();
+    config.scrollback_lines = scrollback_lines;
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    send_and_wait(orchestrator, &agent_id, "echo ready >&2", "ready").await;
+    agent_id
+}
+
+/// Sends `command` and waits until `last_line` is the newest scrollback line.
+async fn send_and_wait(orchestrator: &AgentOrchestrator, agent_id: &str, command: &str, last_line: &str) {
+    orchestrator.send_command(agent_id, command).await.unwrap();
+    for _ in 0..50 {
+        let scrollback = orchestrator.get_scrollback(agent_id, Some(1)).await.unwrap();
+        if scrollback.last().is_some_and(|line| line == last_line) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let scrollback = orchestrator.get_scrollback(agent_id, None).await.unwrap();
+    panic!("{:?} never printed {:?}: {:?}", command, last_line, scrollback);
+}
+
+#[tokio::test]
+async fn responses_follow_their_commands() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = spawn_ready(&orchestrator, None).await;
+    send_and_wait(&orchestrator, &agent_id, "echo one; echo more", "more").await;
+    send_and_wait(&orchestrator, &agent_id, "echo two", "two").await;
+
+    let turns = orchestrator.get_agent_conversation_history(&agent_id).await.unwrap();
+    let pairs: Vec<(&str, Option<&str>)> = turns[1..].iter()
+        .map(|turn| (turn.record.command.as_str(), turn.response.as_deref()))
+        .collect();
+    assert_eq!(pairs, vec![("echo one; echo more", Some("one\nmore")), ("echo two", Some("two"))]);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn evicted_responses_are_missing() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = spawn_ready(&orchestrator, Some(3)).await;
+    send_and_wait(&orchestrator, &agent_id, "seq 1 5", "5").await;
+    send_and_wait(&orchestrator, &agent_id, "echo last", "last").await;
+
+    let turns = orchestrator.get_agent_conversation_history(&agent_id).await.unwrap();
+    assert_eq!(turns.len(), 3);
+    assert_eq!(turns[1].response, None);
+    assert_eq!(turns[2].response.as_deref(), Some("last"));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+
+    assert!(orchestrator.get_agent_conversation_history("missing").await.is_err());
+}
+
+#[tokio::test]
+async fn turns_before_a_reset_have_no_response() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let agent_id = spawn_ready(&orchestrator, None).await;
+    send_and_wait(&orchestrator, &agent_id, "echo old", "old").await;
+
+    orchestrator.reset_agent(&agent_id).await.unwrap();
+    send_and_wait(&orchestrator, &agent_id, "echo ready >&2", "ready").await;
+    // Longer than the old scrollback, so the old line numbers fall inside it
+    send_and_wait(&orchestrator, &agent_id, "seq 1 10", "10").await;
+
+    let turns = orchestrator.get_agent_conversation_history(&agent_id).await.unwrap();
+    let responses: Vec<Option<&str>> = turns.iter().map(|turn| turn.response.as_deref()).collect();
+    assert_eq!(responses[..2], [None, None]);
+    assert_eq!(responses[3], Some("1\n2\n3\n4\n5\n6\n7\n8\n9\n10"));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
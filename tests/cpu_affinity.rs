@@ -0,0 +1,48 @@
+// Pinning agent processes to CPUs.
+#![cfg(target_os = "linux")]
+use conductor_max::orchestrator::{AgentError, AgentOrchestrator};
+
+mod common;
+
+/// The CPUs `pid` may run on, from `/proc/{pid}/status`.
+fn allowed_cpus(orchestrator: &AgentOrchestrator, agent_id: &str) -> String {
+    let pid = orchestrator.get_resource_snapshot().per_agent[agent_id].pid;
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).unwrap();
+    status.lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+#[tokio::test]
+async fn pins_at_spawn_and_at_runtime() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = common::pipe_bash_config();
+    config.cpu_affinity = Some(vec![0]);
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    assert_eq!(allowed_cpus(&orchestrator, &agent_id), "0");
+
+    let last = std::thread::available_parallelism().unwrap().get() - 1;
+    orchestrator.set_agent_cpu_affinity(&agent_id, vec![last]).await.unwrap();
+    assert_eq!(allowed_cpus(&orchestrator, &agent_id), last.to_string());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_cpus_beyond_the_available_ones() {
+    let orchestrator = AgentOrchestrator::new();
+    let available = std::thread::available_parallelism().unwrap().get();
+    let mut config = common::pipe_bash_config();
+    config.cpu_affinity = Some(vec![available]);
+    let error = orchestrator.spawn_agent(config).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(AgentError::InvalidAffinityMask { available: reported, .. })
+            if reported.len() == available && !reported.contains(&available)
+    ));
+
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    assert!(orchestrator.set_agent_cpu_affinity(&agent_id, Vec::new()).await.is_err());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
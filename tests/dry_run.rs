@@ -0,0 +1,45 @@
+// Dry runs applying the same checks as the orchestrator they mirror.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, OrchestratorConfig};
+
+mod common;
+
+fn bash_config(agent_id: &str) -> AgentConfig {
+    let mut config = common::pipe_bash_config();
+    config.agent_id = Some(agent_id.to_string());
+    config
+}
+
+#[tokio::test]
+async fn refuses_what_the_orchestrator_refuses() {
+    let orchestrator = AgentOrchestrator::with_config(OrchestratorConfig {
+        max_agents: Some(2),
+        max_context_bytes: 4,
+        ..OrchestratorConfig::default()
+    });
+    orchestrator.spawn_agent(bash_config("live")).await.unwrap();
+    let dry_run = orchestrator.dry_run();
+
+    assert_eq!(
+        dry_run.spawn_agent(bash_config("live")).await.unwrap_err().to_string(),
+        orchestrator.spawn_agent(bash_config("live")).await.unwrap_err().to_string(),
+    );
+    let plugin = AgentConfig::new(AgentType::Plugin("missing".to_string()));
+    assert_eq!(
+        dry_run.spawn_agent(plugin.clone()).await.unwrap_err().to_string(),
+        orchestrator.validate_agent(&plugin).unwrap_err().to_string(),
+    );
+
+    dry_run.spawn_agent(bash_config("planned")).await.unwrap();
+    let over = dry_run.spawn_agent(bash_config("extra")).await.unwrap_err();
+    assert_eq!(over.to_string(), "Agent limit reached (2 of 2 running)");
+
+    let path = std::env::temp_dir().join(format!("conductor-dry-run-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "too long").unwrap();
+    let too_big = dry_run.send_file_contents("live", &path).await.unwrap_err();
+    assert!(too_big.to_string().contains("over the 4 byte context limit"), "{}", too_big);
+    std::fs::remove_file(&path).unwrap();
+
+    let methods: Vec<String> = dry_run.report().into_iter().map(|action| action.method).collect();
+    assert_eq!(methods, ["spawn_agent"]);
+    orchestrator.kill_agent("live").await.unwrap();
+}
@@ -0,0 +1,98 @@
+// Elections among agents asked the same question.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, ElectionResult, ElectionStrategy};
+use std::collections::HashMap;
+
+/// Bash agents in their own workspaces, each with `answer` in answer.txt.
+async fn voters(orchestrator: &AgentOrchestrator, answers: &[&str]) -> Vec<String> {
+    let root = std::env::temp_dir().join(format!("conductor-election-{}", uuid::Uuid::new_v4()));
+    let mut agent_ids = Vec::new();
+    for (i, answer) in answers.iter().enumerate() {
+        let workspace = root.join(i.to_string());
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(workspace.join("answer.txt"), format!("{}\n", answer)).unwrap();
+        let mut config = AgentConfig::new(AgentType::Bash);
+        config.workspace_path = Some(workspace.display().to_string());
+        let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+        // The prompt shows the long workspace path; keep the echo on one line
+        orchestrator.resize(&agent_id, 24, 400).await.unwrap();
+        agent_ids.push(agent_id);
+    }
+    agent_ids
+}
+
+async fn election(orchestrator: &AgentOrchestrator, agent_ids: &[String], strategy: ElectionStrategy) -> ElectionResult {
+    orchestrator.hold_election(agent_ids.to_vec(), "cat answer.txt".to_string(), strategy).await.unwrap()
+}
+
+#[tokio::test]
+async fn strategies_pick_their_winner() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_ids = voters(&orchestrator, &[
+        r#"{"answer": "yes", "confidence": 0.6}"#,
+        r#"{"answer": "yes", "confidence": 0.7}"#,
+        r#"{"answer": "no", "confidence": 0.99}"#,
+    ]).await;
+
+    let majority = election(&orchestrator, &agent_ids, ElectionStrategy::MajorityVote).await;
+    assert_eq!(majority.winner, "yes");
+    assert_eq!(majority.votes, HashMap::from([("yes".to_string(), 2), ("no".to_string(), 1)]));
+    assert_eq!(majority.raw_responses.len(), 3);
+    assert!(majority.raw_responses[&agent_ids[2]].contains("0.99"));
+
+    let confident = election(&orchestrator, &agent_ids, ElectionStrategy::HighestConfidence).await;
+    assert_eq!(confident.winner, "no");
+    assert_eq!(confident.votes, majority.votes);
+
+    let first = election(&orchestrator, &agent_ids, ElectionStrategy::FirstResponse).await;
+    assert_eq!(first.raw_responses.len(), 1);
+    assert_eq!(first.votes.values().sum::<usize>(), 1);
+    assert!(first.votes.contains_key(&first.winner));
+
+    for agent_id in agent_ids {
+        orchestrator.kill_agent(&agent_id).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn plain_answers_and_run_election() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_ids = voters(&orchestrator, &["blue", "red", "blue"]).await;
+    let winner = orchestrator
+        .run_election(agent_ids.clone(), "cat answer.txt".to_string(), ElectionStrategy::MajorityVote)
+        .await
+        .unwrap();
+    assert_eq!(winner, "blue");
+
+    // Without confidences there is nothing to compare
+    let error = orchestrator
+        .hold_election(agent_ids.clone(), "cat answer.txt".to_string(), ElectionStrategy::HighestConfidence)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("confidence"), "{}", error);
+
+    for agent_id in agent_ids {
+        orchestrator.kill_agent(&agent_id).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn rejects_missing_voters() {
+    let orchestrator = AgentOrchestrator::new();
+    assert!(orchestrator.run_election(Vec::new(), "q".to_string(), ElectionStrategy::MajorityVote).await.is_err());
+    let missing = vec!["missing".to_string()];
+    assert!(orchestrator.run_election(missing, "q".to_string(), ElectionStrategy::MajorityVote).await.is_err());
+}
+
+#[test]
+fn results_and_strategies_serialize() {
+    let strategy: ElectionStrategy = serde_json::from_str("\"highest_confidence\"").unwrap();
+    assert_eq!(strategy, ElectionStrategy::HighestConfidence);
+
+    let result = ElectionResult {
+        winner: "yes".to_string(),
+        votes: HashMap::from([("yes".to_string(), 2)]),
+        raw_responses: HashMap::from([("agent-1".to_string(), "yes".to_string())]),
+    };
+    let json = serde_json::to_string(&result).unwrap();
+    assert_eq!(serde_json::from_str::<ElectionResult>(&json).unwrap(), result);
+}
@@ -0,0 +1,104 @@
+// Flagging agents whose output matches an error pattern.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, MessageType};
+use std::time::Duration;
+
+mod common;
+
+async fn status_after(orchestrator: &AgentOrchestrator, agent_id: &str, command: &str) -> serde_json::Value {
+    orchestrator.send_command(agent_id, command).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    orchestrator.get_agent_status(agent_id).await.unwrap()
+}
+
+#[tokio::test]
+async fn default_patterns_flag_errors_until_cleared() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    let mut messages = orchestrator.subscribe_ipc().await;
+
+    let status = status_after(&orchestrator, &agent_id, "echo all good").await;
+    assert_eq!(status["error_count"], 0);
+    assert!(status["last_error"].is_null());
+
+    let status = status_after(&orchestrator, &agent_id, "echo 'ERROR: disk full'; echo 'Traceback (most recent call last):'").await;
+    assert_eq!(status["error_count"], 2);
+    assert_eq!(status["last_error"], "Traceback (most recent call last):");
+
+    let mut errors = Vec::new();
+    while let Ok(message) = messages.try_recv() {
+        if message.message_type == MessageType::Error {
+            errors.push(message.payload["message"].as_str().unwrap().to_string());
+        }
+    }
+    assert_eq!(errors, ["ERROR: disk full", "Traceback (most recent call last):"]);
+
+    orchestrator.clear_agent_error(&agent_id).await.unwrap();
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!(status["error_count"], 0);
+    assert!(status["last_error"].is_null());
+}
+
+#[tokio::test]
+async fn patterns_are_overridable_per_agent() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = common::pipe_bash_config();
+    config.patterns.errors = Some(vec!["^FAIL ".to_string()]);
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+
+    let status = status_after(&orchestrator, &agent_id, "echo 'ERROR: ignored'; echo 'FAIL test_login'").await;
+    assert_eq!(status["error_count"], 1);
+    assert_eq!(status["last_error"], "FAIL test_login");
+
+    let mut config = common::pipe_bash_config();
+    config.patterns.errors = Some(Vec::new());
+    let quiet_id = orchestrator.spawn_agent(config).await.unwrap();
+    let status = status_after(&orchestrator, &quiet_id, "echo 'ERROR: ignored'").await;
+    assert_eq!(status["error_count"], 0);
+
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.patterns.errors = Some(vec!["(".to_string()]);
+    assert!(orchestrator.spawn_agent(config).await.is_err());
+}
+
+#[tokio::test]
+async fn terminal_echo_of_a_command_is_not_an_error() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+
+    orchestrator.send_command(&agent_id, "echo 'ERROR: disk full'").await.unwrap();
+    let mut status = serde_json::Value::Null;
+    for _ in 0..30 {
+        status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+        if status["error_count"] != 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    // Give a wrongly counted echo time to show up too
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap_or(status);
+    assert_eq!(status["error_count"], 1);
+    assert_eq!(status["last_error"], "ERROR: disk full");
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn output_ending_with_the_command_is_still_an_error() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.startup_commands = vec![r#"build() { echo "ERROR: nothing to" build; }"#.to_string()];
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+
+    orchestrator.send_command(&agent_id, "build").await.unwrap();
+    let mut status = serde_json::Value::Null;
+    for _ in 0..30 {
+        status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+        if status["error_count"] != 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status["error_count"], 1);
+    assert_eq!(status["last_error"], "ERROR: nothing to build");
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
@@ -0,0 +1,27 @@
+// Ending an output coalescing window early with `flush_output`.
+use conductor_max::orchestrator::AgentManager;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn flush_returns_merged_output_before_the_window_ends() {
+    let mut config = common::pipe_bash_config();
+    config.output_coalesce_ms = Some(30_000);
+    let agent = Arc::new(AgentManager::spawn(config).await.unwrap());
+
+    // Nothing is being merged yet
+    tokio::time::timeout(Duration::from_secs(1), agent.flush_output()).await.unwrap();
+
+    let reader = tokio::spawn({
+        let agent = agent.clone();
+        async move { agent.get_output().await }
+    });
+    agent.send_command("echo flushed").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    tokio::time::timeout(Duration::from_secs(1), agent.flush_output()).await.unwrap();
+    let data = tokio::time::timeout(Duration::from_secs(1), reader).await.unwrap().unwrap().unwrap();
+    assert!(String::from_utf8_lossy(&data).contains("flushed"));
+}
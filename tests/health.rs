@@ -0,0 +1,24 @@
+// The orchestrator's own health report.
+use conductor_max::orchestrator::AgentOrchestrator;
+
+mod common;
+
+#[tokio::test]
+async fn reports_agents_and_queued_commands() {
+    let orchestrator = AgentOrchestrator::new();
+    let first = common::spawn_pipe_bash(&orchestrator).await;
+    let second = common::spawn_pipe_bash(&orchestrator).await;
+    orchestrator.queue_command(&first, "true".to_string()).await.unwrap();
+    orchestrator.queue_command(&first, "true".to_string()).await.unwrap();
+    orchestrator.queue_command(&second, "true".to_string()).await.unwrap();
+
+    let health = orchestrator.health();
+    assert_eq!(health["agent_count"], 2);
+    assert_eq!(health["queued_commands"], 3);
+    assert_eq!(health["version"], env!("CARGO_PKG_VERSION"));
+
+    orchestrator.flush_queue(&first).unwrap();
+    assert_eq!(orchestrator.health()["queued_commands"], 1);
+    orchestrator.kill_agent(&first).await.unwrap();
+    orchestrator.kill_agent(&second).await.unwrap();
+}
@@ -0,0 +1,109 @@
+// Reordering and deleting task history records, and replaying the result.
+use conductor_max::orchestrator::{AgentOrchestrator, SessionState};
+use std::time::Duration;
+
+mod common;
+
+fn ids(session: &SessionState, agent_id: &str) -> Vec<String> {
+    session.task_history.iter()
+        .filter(|record| record.agent_id == agent_id)
+        .map(|record| record.id.clone())
+        .collect()
+}
+
+fn commands(session: &SessionState) -> Vec<(&str, &str)> {
+    session.task_history.iter()
+        .map(|record| (record.agent_id.as_str(), record.command.as_str()))
+        .collect()
+}
+
+fn interleaved_session() -> SessionState {
+    let mut session = SessionState::new();
+    session.register_agent("a".into(), "bash".into());
+    session.register_agent("b".into(), "bash".into());
+    for (agent_id, command) in [("a", "a1"), ("b", "b1"), ("a", "a2"), ("a", "a3"), ("b", "b2")] {
+        session.log_command(agent_id, command);
+    }
+    session
+}
+
+#[test]
+fn reorder_keeps_other_agents_in_place() {
+    let mut session = interleaved_session();
+    let a = ids(&session, "a");
+    
+    session.reorder_agent_history("a", &[a[2].clone(), a[0].clone(), a[1].clone()]).unwrap();
+    assert_eq!(
+        commands(&session),
+        [("a", "a3"), ("b", "b1"), ("a", "a1"), ("a", "a2"), ("b", "b2")]
+    );
+}
+
+#[test]
+fn reorder_drops_records_left_out() {
+    let mut session = interleaved_session();
+    let a = ids(&session, "a");
+    
+    session.reorder_agent_history("a", &[a[2].clone(), a[0].clone()]).unwrap();
+    assert_eq!(commands(&session), [("a", "a3"), ("b", "b1"), ("a", "a1"), ("b", "b2")]);
+    assert_eq!(session.total_commands, 5);
+}
+
+#[test]
+fn reorder_rejects_unknown_and_foreign_ids() {
+    let mut session = interleaved_session();
+    let before = session.clone();
+    let b = ids(&session, "b");
+    
+    let error = session.reorder_agent_history("a", &["missing".into()]).unwrap_err();
+    assert_eq!(error.to_string(), "Task record missing not found");
+    assert!(session.reorder_agent_history("a", &[b[0].clone()]).is_err());
+    assert_eq!(session, before);
+}
+
+#[test]
+fn delete_record_removes_one_record() {
+    let mut session = interleaved_session();
+    let a = ids(&session, "a");
+    
+    let deleted = session.delete_record(&a[1]).unwrap();
+    assert_eq!(deleted.command, "a2");
+    assert_eq!(commands(&session), [("a", "a1"), ("b", "b1"), ("a", "a3"), ("b", "b2")]);
+    assert!(session.delete_record(&a[1]).is_err());
+}
+
+#[tokio::test]
+async fn replay_after_reordering_uses_new_order() {
+    let orchestrator = AgentOrchestrator::new();
+    let source = common::spawn_pipe_bash(&orchestrator).await;
+    let target = common::spawn_pipe_bash(&orchestrator).await;
+    for word in ["one", "two", "three"] {
+        orchestrator.send_command(&source, &format!("echo {}", word)).await.unwrap();
+    }
+    
+    let history: Vec<String> = orchestrator.get_task_history(Some(&source)).await
+        .into_iter()
+        .map(|record| record.id)
+        .collect();
+    orchestrator
+        .reorder_task_history(&source, vec![history[2].clone(), history[0].clone()])
+        .await
+        .unwrap();
+    assert_eq!(orchestrator.replay_commands(&source, Some(&target)).await.unwrap(), 2);
+    
+    let mut output = String::new();
+    while !output.ends_with("one\n") {
+        match tokio::time::timeout(Duration::from_secs(5), orchestrator.get_agent_output(&target)).await {
+            Ok(Ok(Some(chunk))) => output.push_str(&String::from_utf8_lossy(&chunk)),
+            _ => break,
+        }
+    }
+    // Ignore anything the shell itself prints on stderr
+    let echoed: Vec<&str> = output.lines()
+        .filter(|line| ["one", "two", "three"].contains(line))
+        .collect();
+    assert_eq!(echoed, ["three", "one"]);
+    
+    orchestrator.kill_agent(&source).await.unwrap();
+    orchestrator.kill_agent(&target).await.unwrap();
+}
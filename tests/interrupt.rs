@@ -0,0 +1,40 @@
+// Interrupting an agent's running command with SIGINT.
+use conductor_max::orchestrator::{AgentConfig, AgentManager, AgentOrchestrator, AgentType, ExitStatus};
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn interrupts_the_running_job_on_a_pty() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    // Past startup, so the interrupt doesn't land before the job runs
+    orchestrator.send_command(&agent_id, "echo ready").await.unwrap();
+    assert!(common::wait_for_line(&orchestrator, &agent_id, "ready").await);
+    orchestrator.send_command(&agent_id, "sleep 30 && echo slept").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    orchestrator.interrupt_agent(&agent_id).await.unwrap();
+    // The shell itself is still there
+    orchestrator.send_command(&agent_id, "echo alive").await.unwrap();
+    assert!(common::wait_for_line(&orchestrator, &agent_id, "alive").await);
+    assert!(!common::wait_for_line(&orchestrator, &agent_id, "slept").await);
+
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!(status["signals_sent"], 1);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn reaches_agents_without_a_terminal() {
+    let config = common::pipe_bash_config();
+    let agent = AgentManager::spawn(config).await.unwrap();
+    agent.send_command("sleep 30").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // A non-interactive shell exits along with its interrupted command
+    agent.send_interrupt().await.unwrap();
+    let status = tokio::time::timeout(Duration::from_secs(5), agent.wait()).await.unwrap().unwrap();
+    assert!(matches!(status, ExitStatus::Exited { .. }));
+    assert!(agent.send_interrupt().await.is_err());
+}
@@ -0,0 +1,82 @@
+// Request-reply over the IPC bridge, matched by `correlation_id`.
+use conductor_max::orchestrator::{AgentOrchestrator, IpcBridge, IpcMessage, MessageType};
+use serde_json::json;
+use std::time::Duration;
+
+mod common;
+
+fn request(agent_id: &str) -> IpcMessage {
+    IpcMessage {
+        agent_id: agent_id.to_string(),
+        message_type: MessageType::Input,
+        payload: json!({ "command": "status" }),
+        timestamp: chrono::Utc::now(),
+        batch_id: None,
+        correlation_id: None,
+    }
+}
+
+#[tokio::test]
+async fn reply_resolves_the_matching_request() {
+    let bridge = IpcBridge::new();
+    let mut messages = bridge.subscribe().await;
+    let pending = bridge.send_request(request("agent-1"), Duration::from_secs(1));
+
+    let sent = messages.recv().await.unwrap();
+    let correlation_id = sent.correlation_id.clone().expect("request gets a correlation_id");
+    bridge.reply(&correlation_id, json!({ "ok": true })).unwrap();
+
+    let reply = pending.await.unwrap();
+    assert_eq!(reply.correlation_id.as_deref(), Some(correlation_id.as_str()));
+    assert_eq!(reply.agent_id, "agent-1");
+    assert_eq!(reply.payload, json!({ "ok": true }));
+    // Subscribers see the reply too
+    assert_eq!(messages.recv().await.unwrap(), reply);
+
+    // Each request is answered once
+    assert!(bridge.reply(&correlation_id, json!({})).is_err());
+}
+
+#[tokio::test]
+async fn requests_keep_a_given_id_and_work_without_subscribers() {
+    let bridge = IpcBridge::new();
+    let mut message = request("agent-1");
+    message.correlation_id = Some("fixed-id".to_string());
+    let pending = bridge.send_request(message, Duration::from_secs(1));
+    bridge.reply("fixed-id", json!({ "ok": true })).unwrap();
+    assert_eq!(pending.await.unwrap().payload["ok"], true);
+}
+
+#[tokio::test]
+async fn unanswered_requests_time_out() {
+    let bridge = IpcBridge::new();
+    let mut message = request("agent-1");
+    message.correlation_id = Some("never-answered".to_string());
+    let error = bridge.send_request(message, Duration::from_millis(50)).await.unwrap_err();
+    assert!(error.to_string().contains("never-answered"), "{}", error);
+    assert!(bridge.reply("never-answered", json!({})).is_err());
+}
+
+#[tokio::test]
+async fn send_command_is_published_redacted_and_acknowledged() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    let mut messages = orchestrator.subscribe_ipc().await;
+
+    orchestrator.send_command(&agent_id, "export API_TOKEN=hunter2").await.unwrap();
+    let mut request = None;
+    let mut reply = None;
+    while let Ok(message) = messages.try_recv() {
+        match message.message_type {
+            MessageType::Input if message.correlation_id.is_some() => request = Some(message),
+            MessageType::SystemEvent if message.correlation_id.is_some() => reply = Some(message),
+            _ => {}
+        }
+    }
+    let (request, reply) = (request.unwrap(), reply.unwrap());
+    assert_eq!(request.payload["command"], "export API_TOKEN=<redacted>");
+    assert_eq!(reply.correlation_id, request.correlation_id);
+    assert_eq!(reply.agent_id, agent_id);
+    assert_eq!(reply.payload, json!({ "delivered": true }));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
@@ -0,0 +1,55 @@
+// Message counters kept by the IPC bridge.
+use conductor_max::orchestrator::{AgentEvent, IpcBridge, IpcMessage, MessageType};
+use serde_json::json;
+
+fn message(message_type: MessageType) -> IpcMessage {
+    IpcMessage {
+        agent_id: "agent-1".to_string(),
+        message_type,
+        payload: json!({}),
+        timestamp: chrono::Utc::now(),
+        batch_id: None,
+        correlation_id: None,
+    }
+}
+
+#[tokio::test]
+async fn counts_messages_by_type_and_subscribers() {
+    let bridge = IpcBridge::new();
+    let stats = bridge.ipc_stats();
+    assert_eq!((stats.messages_sent, stats.subscribers, stats.lagged_messages), (0, 0, 0));
+    assert_eq!(stats.by_type.len(), 5);
+    assert!(stats.by_type.values().all(|&sent| sent == 0));
+
+    let first = bridge.subscribe().await;
+    let second = bridge.subscribe().await;
+    bridge.send_message(message(MessageType::Status)).unwrap();
+    bridge.send_event(AgentEvent::Error { agent_id: "agent-1".to_string(), message: "boom".to_string() }).unwrap();
+    bridge.send_batch(vec![message(MessageType::Output), message(MessageType::Output)]).unwrap();
+
+    let stats = bridge.ipc_stats();
+    assert_eq!(stats.messages_sent, 4);
+    assert_eq!(stats.subscribers, 2);
+    assert_eq!(stats.by_type["Output"], 2);
+    assert_eq!(stats.by_type["Status"], 1);
+    assert_eq!(stats.by_type["Error"], 1);
+    assert_eq!(stats.by_type["Input"], 0);
+
+    drop((first, second));
+    assert_eq!(bridge.ipc_stats().subscribers, 0);
+}
+
+#[tokio::test]
+async fn reported_lag_accumulates() {
+    let bridge = IpcBridge::with_capacity(2);
+    let mut lagging = bridge.subscribe().await;
+    for _ in 0..5 {
+        bridge.send_message(message(MessageType::Output)).unwrap();
+    }
+    match lagging.recv().await {
+        Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => bridge.record_lag(skipped),
+        other => panic!("expected lag, got {:?}", other),
+    }
+    bridge.record_lag(4);
+    assert_eq!(bridge.ipc_stats().lagged_messages, 7);
+}
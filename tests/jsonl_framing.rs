@@ -0,0 +1,31 @@
+// JSON objects split out of agent output under `OutputFraming::Jsonl`.
+use conductor_max::orchestrator::{AgentManager, OutputFraming};
+use futures::StreamExt;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn objects_go_to_the_json_stream_and_other_lines_stay_text() {
+    let mut config = common::pipe_bash_config();
+    config.output_framing = OutputFraming::Jsonl;
+    let agent = Arc::new(AgentManager::spawn(config).await.unwrap());
+    let mut output = agent.subscribe_output();
+    let mut objects = agent.clone().json_stream();
+
+    agent.send_command(r#"printf '{"type":"start"}\nplain\n{broken\n{"type":"end","n":2}\n'"#).await.unwrap();
+
+    let wait = Duration::from_secs(5);
+    assert_eq!(tokio::time::timeout(wait, objects.next()).await.unwrap(), Some(json!({ "type": "start" })));
+    assert_eq!(tokio::time::timeout(wait, objects.next()).await.unwrap(), Some(json!({ "type": "end", "n": 2 })));
+
+    let mut printed = String::new();
+    while !printed.contains("{broken\n") {
+        let chunk = tokio::time::timeout(wait, output.recv()).await.unwrap().unwrap();
+        printed.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    assert!(printed.contains("plain\n"));
+    assert!(!printed.contains("\"type\""));
+}
@@ -0,0 +1,42 @@
+// Line filters over split chunks and terminal line endings.
+use conductor_max::orchestrator::{AgentConfig, AgentManager, AgentProcess, AgentType, FilterFactory};
+use regex::Regex;
+use std::time::Duration;
+
+mod common;
+
+/// Output from `agent` after `command` until `until` shows up in it.
+async fn run(agent: &AgentProcess, command: &str, until: &str) -> String {
+    let mut output = agent.subscribe_output();
+    agent.send_command(command).await.unwrap();
+    let mut printed = String::new();
+    while !printed.contains(until) {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), output.recv()).await.unwrap().unwrap();
+        printed.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    printed
+}
+
+#[tokio::test]
+async fn crlf_lines_keep_their_endings() {
+    let agent = AgentManager::spawn(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    agent.output_filter(FilterFactory::include_only(Regex::new("ERR|done").unwrap()));
+
+    // The terminal turns each `\n` into `\r\n`
+    let printed = run(&agent, "printf 'ERR one\\nok two\\nERR three\\n'; echo done", "\r\ndone\r\n").await;
+    assert!(printed.contains("ERR one\r\nERR three\r\n"), "{:?}", printed);
+    assert!(!printed.split("\r\n").any(|line| line == "ok two"), "{:?}", printed);
+    agent.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn lines_split_across_chunks_are_judged_whole() {
+    let config = common::pipe_bash_config();
+    let agent = AgentManager::spawn(config).await.unwrap();
+    agent.output_filter(FilterFactory::exclude_matching(Regex::new("secret").unwrap()));
+
+    let printed = run(&agent, "printf 'my sec'; sleep 0.3; printf 'ret=1\\n'; echo done", "done\n").await;
+    assert!(!printed.contains("sec"), "{:?}", printed);
+    assert!(agent.get_scrollback(None).await.iter().all(|line| !line.contains("sec")));
+    agent.kill().await.unwrap();
+}
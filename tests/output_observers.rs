@@ -0,0 +1,74 @@
+// Compiled-in observers of agent output lines and IPC messages.
+use conductor_max::orchestrator::{AgentOrchestrator, IpcMessage, MessageType, OutputObserver};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod common;
+
+#[derive(Default)]
+struct Recorder {
+    lines: Mutex<Vec<(String, String)>>,
+    events: Mutex<Vec<IpcMessage>>,
+}
+
+impl OutputObserver for Recorder {
+    fn on_line(&self, agent_id: &str, line: &str) {
+        self.lines.lock().unwrap().push((agent_id.to_string(), line.to_string()));
+    }
+
+    fn on_event(&self, ev: &IpcMessage) {
+        self.events.lock().unwrap().push(ev.clone());
+    }
+}
+
+/// Waits up to 5s for `done` to hold, so a loaded machine doesn't fail a check.
+async fn eventually(done: impl Fn() -> bool) {
+    for _ in 0..50 {
+        if done() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[tokio::test]
+async fn observers_see_output_lines_and_events() {
+    let orchestrator = AgentOrchestrator::new();
+    let recorder = Arc::new(Recorder::default());
+    orchestrator.add_output_observer(recorder.clone());
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+
+    orchestrator.send_command(&agent_id, "printf 'first\\nsecond\\n'").await.unwrap();
+    eventually(|| recorder.lines.lock().unwrap().iter().any(|(id, line)| *id == agent_id && line == "second")).await;
+
+    let lines: Vec<String> = recorder.lines.lock().unwrap().iter()
+        .filter(|(id, _)| *id == agent_id)
+        .map(|(_, line)| line.clone())
+        .collect();
+    // Bash's startup warnings on stderr may land in between
+    let first = lines.iter().position(|line| line == "first").expect("first line observed");
+    let second = lines.iter().position(|line| line == "second").expect("second line observed");
+    assert!(first < second, "{:?}", lines);
+
+    // No subscriber is needed for observers to get messages
+    let observed = |message_type: MessageType| recorder.events.lock().unwrap().iter()
+        .any(|ev| ev.agent_id == agent_id && ev.message_type == message_type);
+    assert!(observed(MessageType::Input));
+    assert!(observed(MessageType::Output));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn observers_added_later_see_running_agents() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let recorder = Arc::new(Recorder::default());
+    orchestrator.add_output_observer(recorder.clone());
+    orchestrator.send_command(&agent_id, "echo late").await.unwrap();
+    eventually(|| recorder.lines.lock().unwrap().iter().any(|(id, line)| *id == agent_id && line == "late")).await;
+
+    assert!(recorder.lines.lock().unwrap().iter().any(|(id, line)| *id == agent_id && line == "late"));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
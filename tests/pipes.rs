@@ -0,0 +1,69 @@
+// Queueing one agent's output lines as another agent's commands.
+use conductor_max::orchestrator::AgentOrchestrator;
+use regex::Regex;
+use std::sync::{Arc, Barrier};
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn forwards_matching_lines_and_closes_with_its_agents() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let writer = common::spawn_pipe_bash(&orchestrator).await;
+    let reviewer = common::spawn_pipe_bash(&orchestrator).await;
+
+    let pipe = orchestrator
+        .pipe(&writer, &reviewer, Some(Regex::new("^REVIEW: (.*)$").unwrap()))
+        .await
+        .unwrap();
+    assert!(orchestrator.pipe(&reviewer, &writer, None).await.is_err());
+    assert!(orchestrator.pipe(&writer, &writer, None).await.is_err());
+
+    orchestrator.send_command(&writer, "echo skipped; echo 'REVIEW: echo reviewed'").await.unwrap();
+    let mut depth = 0;
+    for _ in 0..100 {
+        depth = orchestrator.get_pending_queue_depth(&reviewer).unwrap();
+        if depth > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(depth, 1);
+    assert!(orchestrator.send_next_queued(&reviewer).await.unwrap());
+    let session = orchestrator.export_session().await;
+    let history = session["task_history"].as_array().unwrap();
+    assert_eq!(history.last().unwrap()["command"], "echo reviewed");
+
+    orchestrator.kill_agent(&reviewer).await.unwrap();
+    assert!(orchestrator.remove_pipe(&pipe.id).is_err());
+    orchestrator.pipe(&writer, &common::spawn_pipe_bash(&orchestrator).await, None).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn only_one_of_two_opposite_pipes_is_accepted() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let first = common::spawn_pipe_bash(&orchestrator).await;
+    let second = common::spawn_pipe_bash(&orchestrator).await;
+
+    for _ in 0..500 {
+        let barrier = Arc::new(Barrier::new(2));
+        let attempts: Vec<_> = [(&first, &second), (&second, &first)]
+            .into_iter()
+            .map(|(from, to)| {
+                let (orchestrator, barrier) = (orchestrator.clone(), barrier.clone());
+                let (from, to) = (from.clone(), to.clone());
+                let runtime = tokio::runtime::Handle::current();
+                tokio::task::spawn_blocking(move || {
+                    barrier.wait();
+                    runtime.block_on(orchestrator.pipe(&from, &to, None))
+                })
+            })
+            .collect();
+        let mut accepted = Vec::new();
+        for attempt in attempts {
+            accepted.extend(attempt.await.unwrap().ok());
+        }
+        assert_eq!(accepted.len(), 1);
+        orchestrator.remove_pipe(&accepted[0].id).unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+// Scrollback of `\r`-driven progress output, collapsed or kept frame by frame.
+use conductor_max::orchestrator::AgentOrchestrator;
+use std::time::Duration;
+
+mod common;
+
+/// Frames of a progress bar, the last one in its own chunk, then `done`.
+const PROGRESS: &str = "printf 'fetch 10%%\\rfetch 50%%\\r'; sleep 0.2; printf 'fetch 100%%\\n'; echo done";
+
+/// The progress lines the agent's scrollback holds once `done` arrives.
+async fn progress_lines(collapse_cr_updates: bool) -> Vec<String> {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = common::pipe_bash_config();
+    config.collapse_cr_updates = collapse_cr_updates;
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    orchestrator.send_command(&agent_id, PROGRESS).await.unwrap();
+
+    let mut scrollback = Vec::new();
+    for _ in 0..50 {
+        scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+        if scrollback.iter().any(|line| line == "done") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+    assert!(scrollback.iter().any(|line| line == "done"), "{:?}", scrollback);
+    scrollback.into_iter().filter(|line| line.starts_with("fetch")).collect()
+}
+
+#[tokio::test]
+async fn progress_updates_collapse_into_one_line() {
+    assert_eq!(progress_lines(true).await, ["fetch 100%"]);
+}
+
+#[tokio::test]
+async fn progress_frames_stay_separate_by_default() {
+    assert_eq!(progress_lines(false).await, ["fetch 10%", "fetch 50%", "fetch 100%"]);
+}
@@ -0,0 +1,31 @@
+// Detecting an agent's prompt from its replies to bare newlines.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType};
+
+#[tokio::test]
+async fn detected_prompt_is_used_for_responses() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = AgentConfig::new(AgentType::Bash);
+    // Not the `$ ` the default bash pattern looks for
+    config.startup_commands = vec!["PS1='conductor> '".to_string()];
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+
+    let pattern = orchestrator.detect_agent_prompt(&agent_id, None).await.unwrap();
+    assert_eq!(pattern, "conductor> $");
+
+    orchestrator.send_command(&agent_id, "echo detected").await.unwrap();
+    let response = orchestrator.await_agent_response(&agent_id, 5000).await.unwrap();
+    assert!(response.lines().any(|line| line.trim_end() == "detected"), "{:?}", response);
+
+    let session = orchestrator.export_session().await;
+    assert_eq!(session["agents"][&agent_id]["config"]["patterns"]["prompt"], "conductor> $");
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_an_empty_sample() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    assert!(orchestrator.detect_agent_prompt(&agent_id, Some(0)).await.is_err());
+    assert!(orchestrator.detect_agent_prompt("missing", None).await.is_err());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
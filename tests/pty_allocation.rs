@@ -0,0 +1,33 @@
+// Spawning when no PTY can be opened. The descriptor limit applies to the
+// whole process, so this file holds a single test.
+#![cfg(target_os = "linux")]
+
+use conductor_max::orchestrator::{AgentConfig, AgentError, AgentOrchestrator, AgentType};
+
+mod common;
+
+fn open_fds() -> u64 {
+    // Less the descriptor read_dir itself holds
+    std::fs::read_dir("/proc/self/fd").unwrap().count() as u64 - 1
+}
+
+#[tokio::test]
+async fn fd_exhaustion_reports_pty_allocation_failure() {
+    let orchestrator = AgentOrchestrator::new();
+    common::spawn_pipe_bash(&orchestrator).await;
+
+    let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    assert_eq!(unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) }, 0);
+    let lowered = libc::rlimit { rlim_cur: open_fds(), rlim_max: limit.rlim_max };
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &lowered) }, 0);
+
+    let result = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await;
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) }, 0);
+
+    let error = result.unwrap_err();
+    match error.downcast_ref::<AgentError>() {
+        Some(AgentError::PtyAllocationFailed { open_agents, .. }) => assert_eq!(*open_agents, Some(1)),
+        _ => panic!("unexpected error: {}", error),
+    }
+    assert!(error.to_string().contains("max_agents"));
+}
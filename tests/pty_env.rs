@@ -0,0 +1,19 @@
+// Environment variables set in an agent's running shell.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType};
+
+mod common;
+
+#[tokio::test]
+async fn variables_are_visible_to_later_commands() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+
+    orchestrator.set_agent_env(&agent_id, "KEY".to_string(), "it's $HOME".to_string()).await.unwrap();
+    orchestrator.send_command(&agent_id, "echo \"[$KEY]\"").await.unwrap();
+    // Quoted, so the value reaches the shell literally
+    assert!(common::wait_for_line(&orchestrator, &agent_id, "[it's $HOME]").await);
+
+    assert!(orchestrator.set_agent_env(&agent_id, "1KEY".to_string(), "x".to_string()).await.is_err());
+    assert!(orchestrator.set_agent_env(&agent_id, "KEY".to_string(), "a\nb".to_string()).await.is_err());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
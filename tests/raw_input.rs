@@ -0,0 +1,83 @@
+// Raw input and resizing through the orchestrator rather than the process.
+use chrono::{DateTime, Utc};
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType};
+use std::time::Duration;
+
+mod common;
+
+async fn session_activity(orchestrator: &AgentOrchestrator, agent_id: &str) -> DateTime<Utc> {
+    let session = orchestrator.export_session().await;
+    serde_json::from_value(session["agents"][agent_id]["last_activity"].clone()).unwrap()
+}
+
+async fn status_activity(orchestrator: &AgentOrchestrator, agent_id: &str) -> DateTime<Utc> {
+    let status = orchestrator.get_agent_status(agent_id).await.unwrap();
+    serde_json::from_value(status["last_activity"].clone()).unwrap()
+}
+
+#[tokio::test]
+async fn raw_sends_update_last_activity() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    let spawned_session = session_activity(&orchestrator, &agent_id).await;
+    let spawned_status = status_activity(&orchestrator, &agent_id).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    orchestrator.send_raw(&agent_id, b"true\n").await.unwrap();
+    assert!(session_activity(&orchestrator, &agent_id).await > spawned_session);
+    assert!(status_activity(&orchestrator, &agent_id).await > spawned_status);
+    // Raw input is not a command
+    let session = orchestrator.export_session().await;
+    assert!(session["task_history"].as_array().unwrap().is_empty());
+
+    assert!(orchestrator.send_raw("missing", b"true\n").await.is_err());
+}
+
+#[tokio::test]
+async fn resize_goes_through_the_orchestrator() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    orchestrator.resize(&agent_id, 40, 120).await.unwrap();
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!((status["rows"].as_u64(), status["cols"].as_u64()), (Some(40), Some(120)));
+    assert!(orchestrator.resize("missing", 40, 120).await.is_err());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn raw_broadcast_reaches_every_agent() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut agent_ids = Vec::new();
+    for _ in 0..2 {
+        agent_ids.push(common::spawn_pipe_bash(&orchestrator).await);
+    }
+
+    let report = orchestrator.broadcast_raw(b"echo broadcast\n".to_vec()).await.unwrap();
+    assert_eq!(report.sent_to.len(), 2);
+    assert!(report.failed.is_empty());
+    assert_eq!(report.bytes_sent, 2 * b"echo broadcast\n".len());
+    for agent_id in &agent_ids {
+        let mut received = false;
+        for _ in 0..50 {
+            let scrollback = orchestrator.get_scrollback(agent_id, None).await.unwrap();
+            if scrollback.iter().any(|line| line == "broadcast") {
+                received = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(received, "agent {} got no broadcast", agent_id);
+    }
+
+    // Targets are deduplicated; unknown ones fail on their own
+    let targets = vec![agent_ids[0].clone(), agent_ids[0].clone(), "missing".to_string()];
+    let report = orchestrator.broadcast_raw_to(targets, b"true\n").await;
+    assert_eq!(report.sent_to, [agent_ids[0].clone()]);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "missing");
+    assert_eq!(report.bytes_sent, b"true\n".len());
+
+    for agent_id in agent_ids {
+        orchestrator.kill_agent(&agent_id).await.unwrap();
+    }
+}
@@ -0,0 +1,79 @@
+// Reapplying an output filter to the retained scrollback.
+use conductor_max::orchestrator::{AgentManager, AgentOrchestrator, FilterFactory, REDACTED_LINE};
+use futures::StreamExt;
+use regex::Regex;
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn refilter_redacts_stored_lines_and_keeps_filtering() {
+    let config = common::pipe_bash_config();
+    let agent = AgentManager::spawn(config).await.unwrap();
+    let mut output = agent.subscribe_output();
+
+    agent.send_command("echo token=abc; echo visible").await.unwrap();
+    let mut printed = String::new();
+    while !printed.contains("visible\n") {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), output.recv()).await.unwrap().unwrap();
+        printed.push_str(&String::from_utf8_lossy(&chunk));
+    }
+
+    let before = agent.get_lines(0, usize::MAX).await;
+    let token_line = before.start + before.lines.iter().position(|line| line == "token=abc").unwrap();
+
+    agent.output_filter(FilterFactory::exclude_matching(Regex::new("token=").unwrap()));
+    assert_eq!(agent.refilter_scrollback().await, 1);
+    let scrollback = agent.get_scrollback(None).await;
+    assert!(scrollback.iter().all(|line| !line.contains("token=")));
+    assert!(scrollback.iter().any(|line| line == "visible"));
+
+    // Every line keeps its number
+    let after = agent.get_lines(0, usize::MAX).await;
+    assert_eq!((after.first_line, after.total_lines), (before.first_line, before.total_lines));
+    assert_eq!(after.lines[token_line - after.start], REDACTED_LINE);
+    assert_eq!(after.lines[token_line - after.start + 1], "visible");
+    assert_eq!(agent.refilter_scrollback().await, 0);
+
+    agent.send_command("echo token=def; echo after").await.unwrap();
+    let mut printed = String::new();
+    while !printed.contains("after\n") {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), output.recv()).await.unwrap().unwrap();
+        printed.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    assert!(!printed.contains("token="));
+    assert!(agent.get_scrollback(None).await.iter().any(|line| line == "after"));
+}
+
+#[tokio::test]
+async fn following_tails_read_on_after_a_refilter() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    // Let anything bash prints on startup reach the scrollback first
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    // Several lines, so a tail thrown off by them would miss new ones
+    orchestrator.send_command(&agent_id, "for i in 1 2 3 4 5; do echo token=$i; done; echo one").await.unwrap();
+    let wait = Duration::from_secs(5);
+
+    // From the first line, as "two" may land before the stream is polled
+    let mut lines = orchestrator.tail(&agent_id, usize::MAX, true).await.unwrap();
+    orchestrator.send_command(&agent_id, "echo two").await.unwrap();
+    let mut seen = Vec::new();
+    while seen.last().is_none_or(|line| line != "two") {
+        seen.push(tokio::time::timeout(wait, lines.next()).await.unwrap().unwrap());
+    }
+
+    orchestrator.set_agent_output_filter(&agent_id, None, Some("token=".to_string())).await.unwrap();
+    assert_eq!(orchestrator.reapply_filters(&agent_id, true).await.unwrap(), 5);
+    orchestrator.send_command(&agent_id, "echo three; echo four").await.unwrap();
+    let mut next = Vec::new();
+    while next.len() < 2 {
+        let line = tokio::time::timeout(wait, lines.next()).await.unwrap().unwrap();
+        // Bash's startup warnings on stderr may land anywhere
+        if !line.contains("setlocale") {
+            next.push(line);
+        }
+    }
+    assert_eq!(next, ["three", "four"]);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
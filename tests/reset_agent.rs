@@ -0,0 +1,148 @@
+// Resetting an agent to a fresh process under the same id.
+use conductor_max::orchestrator::{AgentOrchestrator, MessageType, Schedule};
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn respawns_with_the_same_id_groups_and_aliases() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let mut config = common::pipe_bash_config();
+    config.command_prefix = Some("# ".to_string());
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    orchestrator.add_to_group("reviewers", &agent_id).unwrap();
+    orchestrator.set_alias("reviewer", &agent_id).unwrap();
+    let old_start = orchestrator.get_agent_status(&agent_id).await.unwrap()["start_time"].clone();
+    let mut messages = orchestrator.subscribe_ipc().await;
+
+    orchestrator.reset_agent("reviewer").await.unwrap();
+
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!(status["running"], true);
+    assert_ne!(status["start_time"], old_start);
+    assert_eq!(orchestrator.list_groups()["reviewers"], [agent_id.as_str()]);
+    assert_eq!(orchestrator.resolve_agent_id("reviewer"), agent_id);
+
+    let mut reset_events = 0;
+    while let Ok(message) = messages.try_recv() {
+        if message.message_type == MessageType::SystemEvent && message.payload["event"] == "reset" {
+            assert_eq!(message.agent_id, agent_id);
+            reset_events += 1;
+        }
+    }
+    assert_eq!(reset_events, 1);
+
+    let timeline = orchestrator.get_session_timeline().await;
+    let last = serde_json::to_value(timeline.last().unwrap()).unwrap();
+    assert_eq!(last["type"], "agent_restarted");
+}
+
+#[tokio::test]
+async fn keeps_schedules_pipes_queues_and_history() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let mut config = common::pipe_bash_config();
+    config.max_session_history_per_agent = 3;
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    let reviewer = orchestrator.spawn_agent(common::pipe_bash_config()).await.unwrap();
+    orchestrator.send_command(&agent_id, "true").await.unwrap();
+    orchestrator.send_command(&agent_id, "true").await.unwrap();
+    let schedule = orchestrator
+        .schedule_command(&agent_id, "true".to_string(), Schedule::Every(Duration::from_secs(3600)))
+        .await
+        .unwrap();
+    let pipe = orchestrator.pipe(&agent_id, &reviewer, Some(Regex::new("^REVIEW: (.*)$").unwrap())).await.unwrap();
+    orchestrator.queue_command(&agent_id, "echo queued".to_string()).await.unwrap();
+
+    orchestrator.reset_agent(&agent_id).await.unwrap();
+
+    let scheduled = orchestrator.list_scheduled_commands();
+    assert_eq!(scheduled.len(), 1);
+    assert_eq!(scheduled[0].id, schedule);
+    assert_eq!(orchestrator.get_pending_queue_depth(&agent_id).unwrap(), 1);
+    // The pipe now carries the new process's output
+    orchestrator.send_command(&agent_id, "echo 'REVIEW: echo reviewed'").await.unwrap();
+    let mut depth = 0;
+    for _ in 0..100 {
+        depth = orchestrator.get_pending_queue_depth(&reviewer).unwrap();
+        if depth > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(depth, 1);
+
+    // The records from before the reset count toward the limit, which lets
+    // up to twice as many build up
+    for _ in 0..4 {
+        orchestrator.send_command(&agent_id, "true").await.unwrap();
+    }
+    assert_eq!(orchestrator.get_task_history(Some(&agent_id)).await.len(), 3);
+
+    orchestrator.remove_pipe(&pipe.id).unwrap();
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+    orchestrator.kill_agent(&reviewer).await.unwrap();
+}
+
+#[tokio::test]
+async fn keeps_the_old_process_when_the_spawn_fails() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let workspace = std::env::temp_dir().join(format!("conductor-reset-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&workspace).unwrap();
+    let mut config = common::pipe_bash_config();
+    config.workspace_path = Some(workspace.to_string_lossy().into_owned());
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    orchestrator.add_to_group("kept", &agent_id).unwrap();
+    let old_start = orchestrator.get_agent_status(&agent_id).await.unwrap()["start_time"].clone();
+
+    // The new process can't start in a workspace that is gone
+    std::fs::remove_dir_all(&workspace).unwrap();
+    assert!(orchestrator.reset_agent(&agent_id).await.is_err());
+
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!(status["running"], true);
+    assert_eq!(status["start_time"], old_start);
+    assert_eq!(orchestrator.list_groups()["kept"], [agent_id.as_str()]);
+    orchestrator.send_command(&agent_id, "true").await.unwrap();
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stays_reachable_and_keeps_its_id_while_resetting() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    let agent_id = orchestrator.spawn_agent(common::pipe_bash_config()).await.unwrap();
+    let done = Arc::new(AtomicBool::new(false));
+
+    // Looks the agent up, and tries to take its id, the whole time it resets
+    let watcher = tokio::spawn({
+        let orchestrator = orchestrator.clone();
+        let agent_id = agent_id.clone();
+        let done = done.clone();
+        async move {
+            while !done.load(Ordering::Relaxed) {
+                orchestrator.get_agent_status(&agent_id).await.unwrap();
+                let mut twin = common::pipe_bash_config();
+                twin.agent_id = Some(agent_id.clone());
+                let error = orchestrator.spawn_agent(twin).await.unwrap_err();
+                assert!(error.to_string().contains("already exists"), "{}", error);
+            }
+        }
+    });
+    for _ in 0..5 {
+        orchestrator.reset_agent(&agent_id).await.unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+    watcher.await.unwrap();
+
+    assert_eq!(orchestrator.get_agent_status(&agent_id).await.unwrap()["running"], true);
+    assert_eq!(orchestrator.list_agents().await.len(), 1);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn fails_for_unknown_agents() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    assert!(orchestrator.reset_agent("missing").await.is_err());
+}
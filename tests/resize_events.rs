@@ -0,0 +1,50 @@
+// Resizes announced to every viewer of an agent.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, MessageType};
+
+mod common;
+
+#[tokio::test]
+async fn resize_is_announced_and_reported_in_status() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    let mut messages = orchestrator.subscribe_ipc().await;
+
+    orchestrator.resize(&agent_id, 50, 132).await.unwrap();
+    let mut resized = None;
+    let mut status_size = None;
+    while let Ok(message) = messages.try_recv() {
+        if message.agent_id != agent_id {
+            continue;
+        }
+        match message.message_type {
+            MessageType::SystemEvent if message.payload["event"] == "resized" => {
+                resized = Some((message.payload["rows"].as_u64(), message.payload["cols"].as_u64()));
+            }
+            MessageType::Status if message.payload["status"]["rows"].is_u64() => {
+                let status = &message.payload["status"];
+                status_size = Some((status["rows"].as_u64(), status["cols"].as_u64()));
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(resized, Some((Some(50), Some(132))));
+    assert_eq!(status_size, Some((Some(50), Some(132))));
+
+    // A window opened afterwards reads the current size, not the default
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!((status["rows"].as_u64(), status["cols"].as_u64()), (Some(50), Some(132)));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn failed_resize_is_not_announced() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    let mut messages = orchestrator.subscribe_ipc().await;
+
+    assert!(orchestrator.resize(&agent_id, 50, 132).await.is_err());
+    while let Ok(message) = messages.try_recv() {
+        assert_ne!(message.payload["event"], "resized");
+    }
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
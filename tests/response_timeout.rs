@@ -0,0 +1,68 @@
+// `response_timeout` events for commands that get no output in time.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, MessageType};
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn fires_only_for_commands_without_output() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = common::pipe_bash_config();
+    config.response_timeout = Some(Duration::from_millis(300));
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    let mut messages = orchestrator.subscribe_ipc().await;
+
+    orchestrator.send_command(&agent_id, "echo quick").await.unwrap();
+    // Any output ends a pending timer, so let this one's arrive first
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    orchestrator.send_command(&agent_id, "sleep 2").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1_000)).await;
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!(status["response_timeout_count"], 1);
+
+    let mut timeouts = Vec::new();
+    while let Ok(message) = messages.try_recv() {
+        if message.message_type == MessageType::SystemEvent && message.payload["event"] == "response_timeout" {
+            timeouts.push(message.payload["elapsed_ms"].as_u64().unwrap());
+        }
+    }
+    assert_eq!(timeouts.len(), 1);
+    assert!(timeouts[0] >= 300);
+}
+
+#[tokio::test]
+async fn terminal_echo_is_not_a_response() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.response_timeout = Some(Duration::from_millis(300));
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+
+    // Past startup, so its prompt doesn't end the next timer early
+    orchestrator.send_command(&agent_id, "echo ready").await.unwrap();
+    let mut ready = false;
+    for _ in 0..30 {
+        let scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+        if scrollback.iter().any(|line| line.trim_end() == "ready") {
+            ready = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(ready);
+
+    orchestrator.send_command(&agent_id, "sleep 1").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(800)).await;
+    let status = orchestrator.get_agent_status(&agent_id).await.unwrap();
+    assert_eq!(status["response_timeout_count"], 1);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[test]
+fn timeout_is_configured_in_milliseconds() {
+    let config: AgentConfig = serde_json::from_value(serde_json::json!({
+        "agent_type": "bash",
+        "response_timeout": 1500,
+    })).unwrap();
+    assert_eq!(config.response_timeout, Some(Duration::from_millis(1500)));
+    assert_eq!(serde_json::to_value(&config).unwrap()["response_timeout"], 1500);
+}
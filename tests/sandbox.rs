@@ -0,0 +1,44 @@
+// Running agents in Linux namespaces.
+#![cfg(target_os = "linux")]
+use conductor_max::orchestrator::{AgentManager, SandboxConfig};
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn reports_the_sandboxed_shell_and_kills_it() {
+    let mut config = common::pipe_bash_config();
+    config.sandbox = Some(SandboxConfig { isolate_pid: true, ..SandboxConfig::default() });
+    let agent = AgentManager::spawn(config).await.unwrap();
+
+    // The shell unshare forked, not unshare itself; the fork may not have
+    // exec'd bash yet
+    let pid = agent.pid().unwrap();
+    let mut comm = String::new();
+    for _ in 0..50 {
+        comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).unwrap();
+        if comm.trim() == "bash" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(comm.trim(), "bash");
+
+    let mut output = agent.subscribe_output();
+    agent.send_command("echo $$").await.unwrap();
+    // PID 1 of its own namespace
+    let mut printed = String::new();
+    while !printed.lines().any(|line| line == "1") {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), output.recv()).await.unwrap().unwrap();
+        printed.push_str(&String::from_utf8_lossy(&chunk));
+    }
+
+    agent.kill().await.unwrap();
+    for _ in 0..30 {
+        if !std::path::Path::new(&format!("/proc/{}", pid)).exists() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("sandboxed shell {} outlived its agent", pid);
+}
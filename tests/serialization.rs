@@ -0,0 +1,201 @@
+// Serialization round-trips for types that cross the Tauri IPC boundary or
+// are persisted to disk.
+use chrono::{TimeZone, Utc};
+use conductor_max::orchestrator::{
+    AgentEvent, AgentSession, AgentStatusDto, IpcMessage, MessageType, SessionState, TaskRecord,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use std::fmt::Debug;
+
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) -> T {
+    let json = serde_json::to_string(value).expect("serialize");
+    let parsed: T = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(&parsed, value, "round trip changed {}", json);
+    parsed
+}
+
+fn task_record(agent_id: &str, command: &str, v_level: Option<u8>) -> TaskRecord {
+    TaskRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        agent_id: agent_id.to_string(),
+        command: command.to_string(),
+        timestamp: Utc.with_ymd_and_hms(2025, 3, 14, 15, 9, 26).unwrap(),
+        v_level,
+        scheduled: false,
+        duration_ms: None,
+        output_line: None,
+        process_started_at: None,
+    }
+}
+
+#[test]
+fn task_record_round_trips() {
+    round_trip(&task_record("agent-1", "ls -la", None));
+    round_trip(&task_record("agent-1", "", Some(0)));
+    round_trip(&task_record("代理-🤖", "echo 'héllo wörld' && printf \"\\t\"", Some(255)));
+    round_trip(&TaskRecord { scheduled: true, ..task_record("agent-1", "cargo test", None) });
+    round_trip(&TaskRecord { duration_ms: Some(1500), ..task_record("agent-1", "cargo build", None) });
+    round_trip(&TaskRecord {
+        output_line: Some(42),
+        process_started_at: Some(Utc.with_ymd_and_hms(2025, 3, 14, 15, 0, 0).unwrap()),
+        ..task_record("agent-1", "cargo run", None)
+    });
+}
+
+#[test]
+fn agent_session_round_trips() {
+    let mut session = SessionState::new();
+    session.register_agent("agent-ü".to_string(), "plugin:hello".to_string());
+    let mut agent: AgentSession = session.agents["agent-ü"].clone();
+    // `live` describes this process, not the saved session, so it is never
+    // persisted and always reloads as false
+    assert!(agent.live);
+    let json = serde_json::to_string(&agent).unwrap();
+    let reloaded: AgentSession = serde_json::from_str(&json).unwrap();
+    assert!(!reloaded.live);
+    
+    agent.live = false;
+    assert_eq!(reloaded, agent);
+    
+    agent.history_limit = Some(3);
+    round_trip(&agent);
+}
+
+#[test]
+fn empty_session_round_trips() {
+    let mut session = SessionState::new();
+    let parsed = round_trip(&session);
+    assert!(parsed.agents.is_empty());
+    assert!(parsed.task_history.is_empty());
+    
+    session.id = String::new();
+    round_trip(&session);
+}
+
+#[test]
+fn populated_session_round_trips() {
+    let mut session = SessionState::new();
+    session.register_agent("a".to_string(), "bash".to_string());
+    session.register_agent("b".to_string(), "claude".to_string());
+    session.set_history_limit("a", 2).unwrap();
+    for command in ["one", "two", "three", "four", "ünïcödé ✓"] {
+        session.log_command("a", command);
+    }
+    session.log_scheduled_command("b", "cargo clippy");
+    session.log_command("unknown", "orphaned");
+    for agent in session.agents.values_mut() {
+        agent.live = false;
+    }
+    
+    let parsed = round_trip(&session);
+    assert_eq!(parsed.total_commands, 7);
+    assert_eq!(parsed.task_history.len(), 4);
+    assert!(parsed.task_history.iter().any(|record| record.scheduled));
+}
+
+#[test]
+fn session_import_round_trips() {
+    let mut session = SessionState::new();
+    session.register_agent("a".to_string(), "gemini".to_string());
+    session.log_command("a", "hello");
+    session.agents.get_mut("a").unwrap().live = false;
+    
+    let json = serde_json::to_string(&session.export()).unwrap();
+    let imported = SessionState::import_from_str(&json).unwrap();
+    assert_eq!(imported, session);
+}
+
+#[test]
+fn message_type_round_trips() {
+    for message_type in [
+        MessageType::Output,
+        MessageType::Input,
+        MessageType::Status,
+        MessageType::Error,
+        MessageType::SystemEvent,
+    ] {
+        round_trip(&message_type);
+    }
+}
+
+#[test]
+fn ipc_message_round_trips() {
+    let timestamp = Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 59).unwrap();
+    let message = |message_type, payload| IpcMessage {
+        agent_id: "agent-1".to_string(),
+        message_type,
+        payload,
+        timestamp,
+        batch_id: None,
+        correlation_id: None,
+    };
+    
+    round_trip(&message(MessageType::Output, json!({ "text": "✅ done — 完成\r\n" })));
+    round_trip(&message(MessageType::Error, json!({ "error": null })));
+    round_trip(&message(MessageType::SystemEvent, json!({})));
+    round_trip(&message(MessageType::Status, json!([])));
+    round_trip(&message(MessageType::Input, json!({
+        "repeat": 3,
+        "nested": { "list": [1, "two", null, false] },
+    })));
+    round_trip(&IpcMessage {
+        agent_id: String::new(),
+        ..message(MessageType::Input, serde_json::Value::Null)
+    });
+    round_trip(&IpcMessage {
+        batch_id: Some("5f0c6a8e-3b1d-4c2a-9e7f-1a2b3c4d5e6f".to_string()),
+        ..message(MessageType::Status, json!({ "stage": 2 }))
+    });
+    round_trip(&IpcMessage {
+        correlation_id: Some("0b9d2c4e-7a1f-4e3b-8c5d-6f7a8b9c0d1e".to_string()),
+        ..message(MessageType::SystemEvent, json!({ "delivered": true }))
+    });
+}
+
+#[test]
+fn agent_event_round_trips_with_type_tag() {
+    let timestamp = Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 59).unwrap();
+    let status = AgentStatusDto {
+        id: "agent-1".to_string(),
+        agent_type: "bash".to_string(),
+        running: true,
+        start_time: timestamp,
+        last_activity: timestamp,
+        commands_sent: 2,
+        workspace: None,
+        managed: true,
+        reattached: false,
+        headless: false,
+        rows: 24,
+        cols: 80,
+        has_output: true,
+        bytes_received: 4096,
+        output_rate_bps: 0,
+        response_timeout_count: 0,
+        last_error: Some("ERROR: disk full".to_string()),
+        error_count: 1,
+        signals_sent: 0,
+    };
+    let events = [
+        AgentEvent::Output { agent_id: "agent-1".to_string(), bytes: "✅\r\n".as_bytes().to_vec() },
+        AgentEvent::StatusChanged { agent_id: "agent-1".to_string(), status },
+        AgentEvent::Exited { agent_id: "agent-1".to_string(), code: 0 },
+        AgentEvent::Error { agent_id: "agent-1".to_string(), message: String::new() },
+    ];
+    for (event, tag) in events.iter().zip(["output", "status_changed", "exited", "error"]) {
+        round_trip(event);
+        let value = serde_json::to_value(event).unwrap();
+        assert_eq!(value["type"], tag);
+        assert_eq!(value["agent_id"], "agent-1");
+    }
+    
+    // Output bytes go over the wire as base64, not an array of numbers
+    assert_eq!(serde_json::to_value(&events[0]).unwrap()["bytes"], "4pyFDQo=");
+    
+    let message = events[1].clone().into_message();
+    assert_eq!(message.message_type, MessageType::Status);
+    assert_eq!(message.payload["status"]["type"], "bash");
+    assert_eq!(serde_json::from_value::<AgentEvent>(message.payload).unwrap(), events[1]);
+}
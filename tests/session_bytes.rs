@@ -0,0 +1,55 @@
+// Bytes sent to and received from agents over a session.
+use chrono::{TimeDelta, Utc};
+use conductor_max::orchestrator::{AgentOrchestrator, SessionState};
+use std::time::Duration;
+
+mod common;
+
+#[test]
+fn commands_add_to_bytes_sent() {
+    let mut session = SessionState::new();
+    session.register_agent("agent-1".to_string(), "Bash".to_string());
+    session.log_command("agent-1", "echo hi");
+    session.log_scheduled_command("agent-1", "ls");
+    session.record_bytes_received(100);
+    assert_eq!(session.total_bytes_sent, 9);
+    assert_eq!(session.total_bytes_received, 100);
+
+    let export = session.export();
+    assert_eq!(export["total_bytes_sent"], 9);
+    assert_eq!(export["total_bytes_received"], 100);
+
+    // Sessions saved before the totals existed start at 0
+    let mut older = export;
+    let fields = older.as_object_mut().unwrap();
+    fields.remove("total_bytes_sent");
+    fields.remove("total_bytes_received");
+    let restored = SessionState::import_from_str(&older.to_string()).unwrap();
+    assert_eq!((restored.total_bytes_sent, restored.total_bytes_received), (0, 0));
+}
+
+#[tokio::test]
+async fn orchestrator_counts_both_directions() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    orchestrator.send_command(&agent_id, "echo counted").await.unwrap();
+    let received = || async { orchestrator.get_session_summary().await["total_bytes_received"].as_u64().unwrap() };
+    for _ in 0..50 {
+        if received().await >= "counted\n".len() as u64 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let summary = orchestrator.get_session_summary().await;
+    assert_eq!(summary["total_commands"], 1);
+    assert_eq!(summary["total_bytes_sent"], "echo counted".len());
+    assert!(summary["total_bytes_received"].as_u64().unwrap() >= "counted\n".len() as u64);
+    assert_eq!(summary["live_agents"], 1);
+
+    let stats = orchestrator.stats_since(Utc::now() - TimeDelta::minutes(10)).await;
+    assert_eq!(stats.bytes_sent_in_window, "echo counted".len() as u64);
+    let later = orchestrator.stats_since(Utc::now() + TimeDelta::seconds(2)).await;
+    assert_eq!(later.bytes_sent_in_window, 0);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
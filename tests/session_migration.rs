@@ -0,0 +1,74 @@
+// Loading sessions saved by older builds.
+use conductor_max::orchestrator::SessionState;
+use std::path::Path;
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+#[test]
+fn loads_v1_session_with_defaults_for_new_fields() {
+    let session = SessionState::load_from(&fixture("session_v1.json")).unwrap();
+    
+    assert_eq!(session.schema_version, 1);
+    assert_eq!(session.id, "5f0c6a8e-3b1d-4c2a-9e7f-1a2b3c4d5e6f");
+    assert_eq!(session.total_commands, 2);
+    
+    let agent = &session.agents["claude-main"];
+    assert_eq!(agent.agent_type, "claude");
+    assert_eq!(agent.commands_sent, 2);
+    assert_eq!(agent.history_limit, None);
+    assert!(!agent.live);
+    assert!(session.is_history_only("claude-main"));
+    
+    let commands: Vec<&str> = session.task_history.iter()
+        .map(|record| record.command.as_str())
+        .collect();
+    assert_eq!(commands, ["explain src/main.rs", "résumé ✓"]);
+    assert_eq!(session.task_history[1].v_level, Some(3));
+    assert!(session.task_history.iter().all(|record| !record.scheduled));
+}
+
+#[test]
+fn imports_embedded_v1_session() {
+    let session = SessionState::import_from_str(include_str!("fixtures/session_v1.json")).unwrap();
+    assert_eq!(session.id, "5f0c6a8e-3b1d-4c2a-9e7f-1a2b3c4d5e6f");
+    assert_eq!(session.task_history.len(), 2);
+    assert!(session.is_history_only("claude-main"));
+}
+
+#[test]
+fn migrated_session_saves_as_current_version() {
+    let session = SessionState::load_from(&fixture("session_v1.json")).unwrap();
+    let saved = session.export();
+    let reloaded = SessionState::migrate(saved).unwrap();
+    assert_eq!(reloaded, session);
+}
+
+#[test]
+fn ignores_unknown_fields() {
+    let mut value: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(fixture("session_v1.json")).unwrap(),
+    ).unwrap();
+    value["notes"] = serde_json::json!("written by a newer build");
+    value["agents"]["claude-main"]["exit_code"] = serde_json::json!(0);
+    
+    let session = SessionState::migrate(value).unwrap();
+    assert_eq!(session.agents.len(), 1);
+}
+
+#[test]
+fn rejects_unsupported_versions() {
+    for version in [serde_json::json!(0), serde_json::json!(99), serde_json::json!("1")] {
+        let mut value = SessionState::new().export();
+        value["schema_version"] = version.clone();
+        assert!(SessionState::migrate(value).is_err(), "accepted schema_version {}", version);
+    }
+}
+
+#[test]
+fn rejects_documents_that_are_not_objects() {
+    for json in ["[]", "null", "\"x\"", "42"] {
+        assert!(SessionState::import_from_str(json).is_err(), "accepted {}", json);
+    }
+}
@@ -0,0 +1,102 @@
+// Pruning the session's task history.
+use chrono::{Duration, Utc};
+use conductor_max::orchestrator::SessionState;
+
+fn session_with_commands(commands: &[(&str, &str)]) -> SessionState {
+    let mut session = SessionState::new();
+    for (agent_id, command) in commands {
+        if !session.agents.contains_key(*agent_id) {
+            session.register_agent(agent_id.to_string(), "bash".to_string());
+        }
+        session.log_command(agent_id, command);
+    }
+    session
+}
+
+fn commands(session: &SessionState) -> Vec<&str> {
+    session.task_history.iter().map(|record| record.command.as_str()).collect()
+}
+
+#[test]
+fn prune_history_keeps_most_recent_records() {
+    let mut session = session_with_commands(&[
+        ("a", "one"), ("b", "two"), ("a", "three"), ("b", "four"), ("a", "five"),
+    ]);
+    
+    assert_eq!(session.prune_history(2), 3);
+    assert_eq!(commands(&session), ["four", "five"]);
+    assert_eq!(session.prune_history(10), 0);
+    assert_eq!(commands(&session), ["four", "five"]);
+}
+
+#[test]
+fn pruning_keeps_cumulative_counters() {
+    let mut session = session_with_commands(&[("a", "one"), ("a", "two"), ("b", "three")]);
+    
+    session.prune_history(1);
+    assert_eq!(session.total_commands, 3);
+    assert_eq!(session.agents["a"].commands_sent, 2);
+    assert_eq!(session.agents["b"].commands_sent, 1);
+    
+    session.log_command("a", "four");
+    assert_eq!(session.total_commands, 4);
+    assert_eq!(commands(&session), ["three", "four"]);
+}
+
+#[test]
+fn per_agent_limit_still_applies_after_pruning() {
+    let mut session = session_with_commands(&[("a", "one"), ("a", "two"), ("a", "three")]);
+    session.set_history_limit("a", 2).unwrap();
+    session.prune_history(1);
+    
+    for command in ["four", "five", "six", "seven"] {
+        session.log_command("a", command);
+    }
+    assert_eq!(commands(&session), ["six", "seven"]);
+}
+
+#[test]
+fn per_agent_limit_trims_in_batches() {
+    let mut session = session_with_commands(&[("a", "one"), ("b", "two")]);
+    session.set_history_limit("a", 2).unwrap();
+    
+    // Up to twice the limit is kept before the oldest go
+    for command in ["three", "four", "five"] {
+        session.log_command("a", command);
+    }
+    assert_eq!(commands(&session), ["one", "two", "three", "four", "five"]);
+    session.log_command("a", "six");
+    assert_eq!(commands(&session), ["two", "five", "six"]);
+    
+    // Setting a limit trims right away
+    session.set_history_limit("a", 1).unwrap();
+    assert_eq!(commands(&session), ["two", "six"]);
+}
+
+#[test]
+fn prune_before_removes_older_records() {
+    let mut session = session_with_commands(&[("a", "old"), ("a", "older"), ("a", "new")]);
+    let now = Utc::now();
+    session.task_history[0].timestamp = now - Duration::hours(2);
+    session.task_history[1].timestamp = now - Duration::hours(1);
+    
+    assert_eq!(session.prune_before(now - Duration::minutes(90)), 1);
+    assert_eq!(commands(&session), ["older", "new"]);
+    assert_eq!(session.prune_before(now - Duration::hours(3)), 0);
+    assert_eq!(session.total_commands, 3);
+}
+
+#[test]
+fn per_agent_limit_applies_to_imported_history() {
+    let mut session = session_with_commands(&[("a", "one"), ("a", "two"), ("a", "three")]);
+    session.set_history_limit("a", 2).unwrap();
+    let mut json = serde_json::to_value(&session).unwrap();
+    // Saved before the record count was kept
+    json["agents"]["a"].as_object_mut().unwrap().remove("history_len");
+    
+    let mut imported = SessionState::migrate(json).unwrap();
+    for command in ["four", "five", "six"] {
+        imported.log_command("a", command);
+    }
+    assert_eq!(commands(&imported), ["five", "six"]);
+}
@@ -0,0 +1,55 @@
+// Deriving reusable templates from a session and spawning them again.
+use conductor_max::orchestrator::{AgentOrchestrator, OrchestratorConfig, ShutdownStep};
+
+mod common;
+
+#[tokio::test]
+async fn template_captures_redacted_configs_and_initial_prompts() {
+    let template_dir = std::env::temp_dir().join(format!("conductor-templates-{}", uuid::Uuid::new_v4()));
+    let orchestrator = AgentOrchestrator::with_config(OrchestratorConfig {
+        template_dir: Some(template_dir.clone()),
+        ..OrchestratorConfig::default()
+    });
+    let mut config = common::pipe_bash_config();
+    config.startup_commands = vec!["export API_TOKEN=hunter2".to_string(), "cd /tmp".to_string()];
+    config.shutdown_sequence = vec![
+        ShutdownStep::Command("logout --token hunter2".to_string()),
+        ShutdownStep::Command("exit".to_string()),
+    ];
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+    orchestrator.send_command(&agent_id, "echo review the diff").await.unwrap();
+    orchestrator.send_command(&agent_id, "echo later").await.unwrap();
+
+    let template = orchestrator.session_template().await;
+    assert_eq!(template.agents.len(), 1);
+    let agent = &template.agents[0];
+    assert_eq!(agent.config.agent_id, None);
+    assert_eq!(agent.config.startup_commands, ["export API_TOKEN=<redacted>", "cd /tmp"]);
+    assert_eq!(agent.config.shutdown_sequence, [
+        ShutdownStep::Command("logout --token <redacted>".to_string()),
+        ShutdownStep::Command("exit".to_string()),
+    ]);
+    assert_eq!(agent.initial_prompt.as_deref(), Some("echo review the diff"));
+
+    assert!(orchestrator.list_templates().await.unwrap().is_empty());
+    orchestrator.save_session_template("crew").await.unwrap();
+    assert!(orchestrator.save_session_template("../escape").await.is_err());
+    assert_eq!(orchestrator.list_templates().await.unwrap(), ["crew"]);
+    let loaded = orchestrator.load_session_template("crew").await.unwrap();
+    assert_eq!(loaded.agents, template.agents);
+
+    let spawned = orchestrator.spawn_from_template(loaded).await.unwrap();
+    assert_eq!(spawned.len(), 1);
+    assert_ne!(spawned[0], agent_id);
+    let session = orchestrator.export_session().await;
+    let commands: Vec<&str> = session["task_history"].as_array().unwrap().iter()
+        .filter(|record| record["agent_id"] == spawned[0].as_str())
+        .map(|record| record["command"].as_str().unwrap())
+        .collect();
+    // The redacted startup command is not replayed
+    assert_eq!(commands, ["cd /tmp", "echo review the diff"]);
+    let respawned = orchestrator.export_agents_config().await.unwrap().pop().unwrap();
+    assert_eq!(respawned.shutdown_sequence, [ShutdownStep::Command("exit".to_string())]);
+
+    std::fs::remove_dir_all(template_dir).unwrap();
+}
@@ -0,0 +1,75 @@
+// Custom shutdown sequences run by `kill`.
+use conductor_max::orchestrator::{
+    AgentConfig, AgentManager, AgentProcess, AgentType, ExitStatus, ShutdownStep,
+};
+use std::time::{Duration, Instant};
+
+mod common;
+
+async fn bash_agent(sequence: Vec<ShutdownStep>) -> AgentProcess {
+    let mut config = common::pipe_bash_config();
+    config.shutdown_sequence = sequence;
+    AgentManager::spawn(config).await.unwrap()
+}
+
+#[tokio::test]
+async fn runs_custom_sequence_in_order() {
+    let agent = bash_agent(vec![
+        ShutdownStep::Command("echo bye".into()),
+        ShutdownStep::Command("exit 7".into()),
+        ShutdownStep::Wait(Duration::from_secs(10)),
+    ]).await;
+    let mut output = agent.subscribe_output();
+
+    let started = Instant::now();
+    agent.kill().await.unwrap();
+
+    // The wait ends as soon as bash exits
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(agent.wait().await.unwrap(), ExitStatus::Exited { code: 7 });
+    let mut printed = Vec::new();
+    while let Ok(Ok(chunk)) = tokio::time::timeout(Duration::from_secs(1), output.recv()).await {
+        printed.extend_from_slice(&chunk);
+    }
+    assert!(String::from_utf8_lossy(&printed).lines().any(|line| line == "bye"));
+}
+
+#[tokio::test]
+async fn force_kills_agent_that_outlives_its_sequence() {
+    let agent = bash_agent(vec![ShutdownStep::Command("true".into())]).await;
+
+    agent.kill().await.unwrap();
+
+    let status = tokio::time::timeout(Duration::from_secs(2), agent.wait()).await;
+    assert!(matches!(status, Ok(Ok(ExitStatus::Exited { .. }))));
+}
+
+#[tokio::test]
+async fn force_kill_step_skips_the_rest() {
+    let agent = bash_agent(vec![
+        ShutdownStep::ForceKill,
+        ShutdownStep::Command("exit 7".into()),
+    ]).await;
+
+    agent.kill().await.unwrap();
+
+    let status = tokio::time::timeout(Duration::from_secs(2), agent.wait()).await.unwrap();
+    assert_ne!(status.unwrap(), ExitStatus::Exited { code: 7 });
+}
+
+#[test]
+fn sequence_deserializes_from_config_json() {
+    let config: AgentConfig = serde_json::from_value(serde_json::json!({
+        "agent_type": "claude",
+        "shutdown_sequence": [{ "raw": [3] }, "interrupt", { "wait": 250 }, { "command": "/exit" }, "force_kill"],
+    })).unwrap();
+
+    assert_eq!(config.shutdown_sequence, [
+        ShutdownStep::Raw(vec![3]),
+        ShutdownStep::Interrupt,
+        ShutdownStep::Wait(Duration::from_millis(250)),
+        ShutdownStep::Command("/exit".into()),
+        ShutdownStep::ForceKill,
+    ]);
+    assert!(AgentConfig::new(AgentType::Claude).shutdown_sequence.is_empty());
+}
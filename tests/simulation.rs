@@ -0,0 +1,51 @@
+// Simulating a command sequence without sending it.
+#![cfg(feature = "simulation")]
+use conductor_max::orchestrator::{AgentConfig, AgentError, AgentOrchestrator, AgentType, OrchestratorConfig};
+
+mod common;
+
+#[tokio::test]
+async fn reports_syntax_errors_and_unknown_agents() {
+    let orchestrator = AgentOrchestrator::with_config(OrchestratorConfig {
+        simulation_baseline_ms: 250,
+        ..OrchestratorConfig::default()
+    });
+    let agent_id = orchestrator.spawn_agent(common::pipe_bash_config()).await.unwrap();
+
+    let result = orchestrator.simulate(vec![
+        (agent_id.clone(), "echo one".to_string()),
+        (agent_id.clone(), "if true; then".to_string()),
+        ("missing".to_string(), "echo two".to_string()),
+    ]).await.unwrap();
+    assert!(!result.is_ok());
+    assert_eq!(result.steps.len(), 2);
+    assert!(result.steps[0].preview.syntax_ok);
+    assert!(!result.steps[1].preview.syntax_ok);
+    assert_eq!(result.total_duration_estimate_ms, 500);
+
+    assert_eq!(result.errors.len(), 2);
+    assert!(matches!(&result.errors[0], (1, AgentError::InvalidCommand { .. })));
+    assert!(matches!(&result.errors[1], (2, AgentError::AgentNotFound { agent_id }) if agent_id == "missing"));
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["errors"][1]["index"], 2);
+    assert_eq!(json["steps"][0]["command"], "echo one");
+    // Nothing was sent
+    assert_eq!(orchestrator.export_session().await["total_commands"], 0);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn estimates_from_timed_responses() {
+    let orchestrator = AgentOrchestrator::new();
+    // Responses are only delimited where there's a prompt
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    orchestrator.send_command(&agent_id, "sleep 0.2; echo done").await.unwrap();
+    orchestrator.await_agent_response(&agent_id, 5000).await.unwrap();
+
+    let result = orchestrator.simulate(vec![(agent_id.clone(), "echo again".to_string())]).await.unwrap();
+    assert!(result.is_ok());
+    let estimate = result.steps[0].estimated_duration_ms;
+    assert!((150..5000).contains(&estimate), "{}", estimate);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
@@ -0,0 +1,50 @@
+// Time-windowed orchestrator stats and the session timeline.
+use chrono::{TimeDelta, Utc};
+use conductor_max::orchestrator::AgentOrchestrator;
+use std::time::Duration;
+
+mod common;
+
+#[tokio::test]
+async fn counts_only_activity_inside_the_window() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    orchestrator.send_command(&agent_id, "echo windowed").await.unwrap();
+    let since = Utc::now() - TimeDelta::minutes(10);
+    // Until the forwarder has counted the output
+    for _ in 0..50 {
+        if orchestrator.stats_since(since).await.bytes_received_in_window > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+
+    let stats = orchestrator.stats_since(since).await;
+    assert_eq!(stats.commands_in_window, 1);
+    assert_eq!(stats.agents_spawned_in_window, 1);
+    assert_eq!(stats.agents_killed_in_window, 1);
+    assert_eq!(stats.errors_in_window, 0);
+    assert!(stats.bytes_received_in_window > 0);
+
+    let later = orchestrator.stats_since(Utc::now() + TimeDelta::seconds(2)).await;
+    assert_eq!(later.commands_in_window, 0);
+    assert_eq!(later.agents_spawned_in_window, 0);
+    assert_eq!(later.bytes_received_in_window, 0);
+}
+
+#[tokio::test]
+async fn timeline_lists_session_events_in_order() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = common::spawn_pipe_bash(&orchestrator).await;
+    orchestrator.send_command(&agent_id, "true").await.unwrap();
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+
+    let timeline = orchestrator.get_session_timeline().await;
+    let types: Vec<String> = timeline.iter()
+        .map(|event| serde_json::to_value(event).unwrap()["type"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(types, ["agent_spawned", "command_sent", "agent_killed"]);
+    assert!(timeline.iter().all(|event| event.agent_id() == agent_id));
+    assert!(timeline.windows(2).all(|pair| pair[0].timestamp() <= pair[1].timestamp()));
+}
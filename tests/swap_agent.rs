@@ -0,0 +1,49 @@
+// Spawning agents under an explicit id and swapping one for another.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator};
+
+mod common;
+
+fn bash_config(agent_id: Option<&str>) -> AgentConfig {
+    let mut config = common::pipe_bash_config();
+    config.agent_id = agent_id.map(str::to_string);
+    config
+}
+
+#[tokio::test]
+async fn concurrent_spawns_take_an_id_once() {
+    let orchestrator = AgentOrchestrator::new();
+    let (first, second) = tokio::join!(
+        orchestrator.spawn_agent(bash_config(Some("twin"))),
+        orchestrator.spawn_agent(bash_config(Some("twin"))),
+    );
+    assert!(first.is_ok() != second.is_ok(), "{:?} {:?}", first, second);
+    let error = first.err().or(second.err()).unwrap();
+    assert!(error.to_string().contains("already exists"), "{}", error);
+
+    assert_eq!(orchestrator.get_agent_status("twin").await.unwrap()["running"], true);
+    orchestrator.kill_agent("twin").await.unwrap();
+    assert!(orchestrator.get_agent_status("twin").await.is_err());
+}
+
+#[tokio::test]
+async fn failed_swaps_leave_the_old_agent_in_place() {
+    let orchestrator = AgentOrchestrator::new();
+    let old_id = orchestrator.spawn_agent(bash_config(None)).await.unwrap();
+    orchestrator.add_to_group("team", &old_id).unwrap();
+    orchestrator.set_alias("lead", &old_id).unwrap();
+
+    let mut broken = bash_config(None);
+    broken.workspace_path = Some("/nonexistent/conductor-swap".to_string());
+    assert!(orchestrator.swap_agent(&old_id, broken).await.is_err());
+
+    let status = orchestrator.get_agent_status(&old_id).await.unwrap();
+    assert_eq!(status["running"], true);
+    assert_eq!(orchestrator.list_groups()["team"], [old_id.as_str()]);
+    assert_eq!(orchestrator.resolve_agent_id("lead"), old_id);
+
+    let new_id = orchestrator.swap_agent(&old_id, bash_config(None)).await.unwrap();
+    assert_eq!(orchestrator.list_groups()["team"], [new_id.as_str()]);
+    assert_eq!(orchestrator.resolve_agent_id("lead"), new_id);
+    assert!(orchestrator.get_agent_status(&old_id).await.is_err());
+    orchestrator.kill_agent(&new_id).await.unwrap();
+}
@@ -0,0 +1,46 @@
+// `tail`-style streams of an agent's scrollback.
+use conductor_max::orchestrator::AgentOrchestrator;
+use futures::StreamExt;
+use std::time::Duration;
+
+mod common;
+
+/// A pipe bash agent whose startup output is already in the scrollback.
+async fn settled_agent(orchestrator: &AgentOrchestrator) -> String {
+    let agent_id = common::spawn_pipe_bash(orchestrator).await;
+    // Let anything bash prints on startup reach the scrollback first
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    agent_id
+}
+
+#[tokio::test]
+async fn without_follow_yields_the_last_lines_and_ends() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = settled_agent(&orchestrator).await;
+    orchestrator.send_command(&agent_id, "printf 'one\\ntwo\\nthree\\n'").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let lines: Vec<String> = orchestrator.tail(&agent_id, 2, false).await.unwrap().collect().await;
+    assert_eq!(lines, ["two", "three"]);
+    let none: Vec<String> = orchestrator.tail(&agent_id, 0, false).await.unwrap().collect().await;
+    assert!(none.is_empty());
+}
+
+#[tokio::test]
+async fn follow_yields_new_lines_until_the_agent_exits() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = settled_agent(&orchestrator).await;
+    orchestrator.send_command(&agent_id, "echo before").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let mut lines = orchestrator.tail(&agent_id, 1, true).await.unwrap();
+    let wait = Duration::from_secs(5);
+    assert_eq!(tokio::time::timeout(wait, lines.next()).await.unwrap().unwrap(), "before");
+    orchestrator.send_command(&agent_id, "echo after; echo again").await.unwrap();
+    assert_eq!(tokio::time::timeout(wait, lines.next()).await.unwrap().unwrap(), "after");
+    assert_eq!(tokio::time::timeout(wait, lines.next()).await.unwrap().unwrap(), "again");
+
+    orchestrator.send_command(&agent_id, "echo last; exit").await.unwrap();
+    let rest: Vec<String> = tokio::time::timeout(wait, lines.collect()).await.unwrap();
+    assert_eq!(rest, ["last"]);
+}
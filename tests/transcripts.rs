@@ -0,0 +1,62 @@
+// Transcripts of one agent's commands and output.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, TranscriptFormat};
+use std::time::Duration;
+
+#[tokio::test]
+async fn echoes_are_suppressed_after_older_output_is_dropped() {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.scrollback_lines = Some(6);
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+
+    for n in 0..10 {
+        orchestrator.send_command(&agent_id, &format!("echo line{}", n)).await.unwrap();
+    }
+    let mut done = false;
+    for _ in 0..30 {
+        let scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+        if scrollback.iter().any(|line| line.trim_end() == "line9") {
+            done = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(done);
+
+    // Only the last few commands' echoes are still in the scrollback
+    let transcript = orchestrator.export_agent_transcript(&agent_id, TranscriptFormat::Txt, None, true).await.unwrap();
+    let output = transcript.split("== Output ==\n").nth(1).unwrap();
+    assert!(output.lines().any(|line| line == "line9"), "{}", output);
+    assert!(!output.lines().any(|line| line.contains("echo line")), "{}", output);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn output_ending_like_a_command_is_kept() {
+    let workspace = std::env::temp_dir().join(format!("conductor-transcript-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&workspace).unwrap();
+    std::fs::write(workspace.join("utils"), "").unwrap();
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.workspace_path = Some(workspace.display().to_string());
+    let agent_id = orchestrator.spawn_agent(config).await.unwrap();
+
+    orchestrator.send_command(&agent_id, "ls").await.unwrap();
+    let mut listed = false;
+    for _ in 0..30 {
+        let scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+        if scrollback.iter().any(|line| line.trim_end() == "utils") {
+            listed = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(listed);
+
+    let transcript = orchestrator.export_agent_transcript(&agent_id, TranscriptFormat::Txt, None, true).await.unwrap();
+    let output = transcript.split("== Output ==\n").nth(1).unwrap();
+    assert!(output.lines().any(|line| line == "utils"), "{}", output);
+    assert!(!output.lines().any(|line| line.ends_with("# ls") || line.ends_with("$ ls")), "{}", output);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+    std::fs::remove_dir_all(workspace).unwrap();
+}
@@ -0,0 +1,56 @@
+// Watermarks marking a point in an agent's output.
+use conductor_max::orchestrator::{AgentConfig, AgentManager, AgentOrchestrator, AgentType, TtyType};
+use std::time::Duration;
+
+fn bash_config(tty_type: TtyType) -> AgentConfig {
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.tty_type = tty_type;
+    config
+}
+
+#[tokio::test]
+async fn watermark_resolves_after_earlier_output() {
+    for tty_type in [TtyType::Pipe, TtyType::Pty] {
+        let orchestrator = AgentOrchestrator::new();
+        let agent_id = orchestrator.spawn_agent(bash_config(tty_type)).await.unwrap();
+        orchestrator.send_command(&agent_id, "sleep 0.3; echo before").await.unwrap();
+
+        let first = orchestrator.create_watermark(&agent_id).await.unwrap();
+        let second = orchestrator.create_watermark(&agent_id).await.unwrap();
+        assert_ne!(first, second);
+        orchestrator.await_watermark(&second, Duration::from_secs(5)).await.unwrap();
+        orchestrator.await_watermark(&first, Duration::from_secs(5)).await.unwrap();
+
+        let scrollback = orchestrator.get_scrollback(&agent_id, None).await.unwrap();
+        assert!(scrollback.iter().any(|line| line.trim_end() == "before"), "{:?}", scrollback);
+        // Neither the sentinel nor, on a terminal, its echo is kept
+        let sentinel = format!("__WATERMARK_{}__", first);
+        assert!(!scrollback.iter().any(|line| line.contains(&sentinel)), "{:?}", scrollback);
+        // Each watermark is awaited once
+        assert!(orchestrator.await_watermark(&first, Duration::from_secs(1)).await.is_err());
+        orchestrator.kill_agent(&agent_id).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn unreached_watermarks_time_out() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(bash_config(TtyType::Pipe)).await.unwrap();
+    orchestrator.send_command(&agent_id, "sleep 1").await.unwrap();
+    let watermark = orchestrator.create_watermark(&agent_id).await.unwrap();
+    let error = orchestrator.await_watermark(&watermark, Duration::from_millis(200)).await.unwrap_err();
+    assert!(error.to_string().contains("not reached"), "{}", error);
+    assert!(orchestrator.await_watermark("missing", Duration::from_millis(200)).await.is_err());
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn markers_must_be_shell_safe() {
+    let agent = AgentManager::spawn(bash_config(TtyType::Pipe)).await.unwrap();
+    assert!(agent.output_watermark("two words".to_string()).await.is_err());
+    assert!(agent.output_watermark(String::new()).await.is_err());
+    let handle = agent.output_watermark("step-1".to_string()).await.unwrap();
+    assert_eq!(handle.id(), "step-1");
+    handle.wait(Duration::from_secs(5)).await.unwrap();
+    agent.kill().await.unwrap();
+}
@@ -0,0 +1,70 @@
+// Window labels the orchestrator keeps for agent windows.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+
+fn bash_config(agent_id: &str) -> AgentConfig {
+    let mut config = common::pipe_bash_config();
+    config.agent_id = Some(agent_id.to_string());
+    config
+}
+
+#[test]
+fn labels_are_valid_and_unique() {
+    let orchestrator = AgentOrchestrator::new();
+    assert_eq!(orchestrator.assign_window_label("abc-123", None), "agent_abc-123");
+    // Characters a label can't hold become `_`, which may collide
+    assert_eq!(orchestrator.assign_window_label("my agent", None), "agent_my_agent");
+    assert_eq!(orchestrator.assign_window_label("my.agent", None), "agent_my_agent-2");
+    // The same agent keeps its label
+    assert_eq!(orchestrator.assign_window_label("my agent", None), "agent_my_agent");
+
+    assert_eq!(orchestrator.window_label_for("my.agent").as_deref(), Some("agent_my_agent-2"));
+    assert_eq!(orchestrator.window_label_for("missing"), None);
+    assert_eq!(orchestrator.window_labels(), HashMap::from([
+        ("abc-123".to_string(), "agent_abc-123".to_string()),
+        ("my agent".to_string(), "agent_my_agent".to_string()),
+        ("my.agent".to_string(), "agent_my_agent-2".to_string()),
+    ]));
+
+    assert_eq!(orchestrator.deregister_window("agent_my_agent").as_deref(), Some("my agent"));
+    assert_eq!(orchestrator.deregister_window("agent_my_agent"), None);
+    assert_eq!(orchestrator.window_label_for("my agent"), None);
+}
+
+#[tokio::test]
+async fn labels_follow_resets_and_swaps() {
+    let orchestrator = Arc::new(AgentOrchestrator::new());
+    orchestrator.spawn_agent(bash_config("window-agent")).await.unwrap();
+    let label = orchestrator.assign_window_label("window-agent", Some("team".to_string()));
+
+    orchestrator.reset_agent("window-agent").await.unwrap();
+    assert_eq!(orchestrator.window_label_for("window-agent"), Some(label.clone()));
+
+    let new_id = orchestrator.swap_agent("window-agent", bash_config("swapped-agent")).await.unwrap();
+    assert_eq!(orchestrator.window_label_for(&new_id), Some(label.clone()));
+    assert_eq!(orchestrator.window_label_for("window-agent"), None);
+    assert_eq!(orchestrator.open_windows(), [(label, Some("team".to_string()))]);
+
+    orchestrator.kill_agent(&new_id).await.unwrap();
+    assert_eq!(orchestrator.window_label_for(&new_id), None);
+    assert!(orchestrator.open_windows().is_empty());
+}
+
+#[test]
+fn open_windows_keep_their_opening_order() {
+    let orchestrator = AgentOrchestrator::new();
+    for agent_id in ["c", "a", "b"] {
+        orchestrator.assign_window_label(agent_id, Some(agent_id.to_uppercase()));
+    }
+    // Assigning again opens a new window with the same label
+    orchestrator.assign_window_label("c", None);
+    let windows: Vec<(String, Option<String>)> = orchestrator.open_windows();
+    assert_eq!(windows, [
+        ("agent_a".to_string(), Some("A".to_string())),
+        ("agent_b".to_string(), Some("B".to_string())),
+        ("agent_c".to_string(), None),
+    ]);
+}
@@ -0,0 +1,142 @@
+// Planning and running workflows of commands across agents.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType, StepStatus, WorkflowSpec, WorkflowStatus};
+use serde_json::json;
+
+fn spec(steps: serde_json::Value) -> WorkflowSpec {
+    serde_json::from_value(json!({ "name": "test", "steps": steps })).unwrap()
+}
+
+fn plan_error(steps: serde_json::Value) -> String {
+    spec(steps).plan().unwrap_err().to_string()
+}
+
+#[test]
+fn plan_orders_steps_after_their_dependencies() {
+    let workflow = spec(json!([
+        { "id": "deploy", "agent_id_or_alias": "a", "command_template": "d", "depends_on": ["test", "build"] },
+        { "id": "test", "agent_id_or_alias": "a", "command_template": "t", "depends_on": ["build"] },
+        { "id": "lint", "agent_id_or_alias": "a", "command_template": "l" },
+        { "id": "build", "agent_id_or_alias": "a", "command_template": "b" },
+    ]));
+    let order: Vec<&str> = workflow.plan().unwrap().iter().map(|step| step.id.as_str()).collect();
+    assert_eq!(order, ["lint", "build", "test", "deploy"]);
+}
+
+#[test]
+fn plan_rejects_invalid_specs() {
+    assert!(plan_error(json!([])).contains("no steps"));
+
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "1" },
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "2" },
+    ]));
+    assert!(error.contains("Duplicate step id a"), "{}", error);
+
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "1", "depends_on": ["missing"] },
+    ]));
+    assert!(error.contains("unknown step missing"), "{}", error);
+
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "1", "depends_on": ["a"] },
+    ]));
+    assert!(error.contains("depends on itself"), "{}", error);
+
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "1", "depends_on": ["c"] },
+        { "id": "b", "agent_id_or_alias": "x", "command_template": "2", "depends_on": ["a"] },
+        { "id": "c", "agent_id_or_alias": "x", "command_template": "3", "depends_on": ["b"] },
+        { "id": "d", "agent_id_or_alias": "x", "command_template": "4" },
+    ]));
+    assert!(error.contains("Steps a, b, c form a dependency cycle"), "{}", error);
+
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "1", "output_var": "v" },
+        { "id": "b", "agent_id_or_alias": "x", "command_template": "2", "output_var": "v" },
+    ]));
+    assert!(error.contains("set by more than one step"), "{}", error);
+
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "echo {{nobody}}" },
+    ]));
+    assert!(error.contains("which no step sets"), "{}", error);
+
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "echo {{open" },
+    ]));
+    assert!(error.contains("Unterminated placeholder"), "{}", error);
+}
+
+#[test]
+fn plan_requires_variables_from_ancestors() {
+    // A sibling's variable would not be set in time
+    let error = plan_error(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "1", "output_var": "v" },
+        { "id": "b", "agent_id_or_alias": "x", "command_template": "echo {{v}}" },
+    ]));
+    assert!(error.contains("does not depend on step a"), "{}", error);
+
+    // Indirect dependencies count
+    let workflow = spec(json!([
+        { "id": "a", "agent_id_or_alias": "x", "command_template": "1", "output_var": "v" },
+        { "id": "b", "agent_id_or_alias": "x", "command_template": "2", "depends_on": ["a"] },
+        { "id": "c", "agent_id_or_alias": "x", "command_template": "echo {{ v }}", "depends_on": ["b"] },
+    ]));
+    assert_eq!(workflow.plan().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn run_passes_outputs_into_later_commands() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    orchestrator.set_alias("shell", &agent_id).unwrap();
+    let workflow = spec(json!([
+        { "id": "reply", "agent_id_or_alias": "shell", "command_template": "echo \"{{greeting}} world\"", "depends_on": ["greet"] },
+        { "id": "greet", "agent_id_or_alias": "shell", "command_template": "echo hello", "output_var": "greeting" },
+    ]));
+
+    let operation = orchestrator.begin_operation(None).unwrap();
+    let run = orchestrator.run_workflow(workflow, &operation).await.unwrap();
+
+    assert_eq!(run.status, WorkflowStatus::Succeeded);
+    assert_eq!(run.name.as_deref(), Some("test"));
+    assert_eq!(run.order, ["greet", "reply"]);
+    assert_eq!(run.step_results["greet"].output.as_deref(), Some("hello"));
+    assert_eq!(run.step_results["reply"].output.as_deref(), Some("hello world"));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn run_skips_the_dependents_of_a_failed_step() {
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = orchestrator.spawn_agent(AgentConfig::new(AgentType::Bash)).await.unwrap();
+    let workflow = spec(json!([
+        { "id": "broken", "agent_id_or_alias": "missing", "command_template": "true", "output_var": "v" },
+        { "id": "after", "agent_id_or_alias": agent_id, "command_template": "echo {{v}}", "depends_on": ["broken"] },
+        { "id": "last", "agent_id_or_alias": agent_id, "command_template": "echo still", "depends_on": ["after"] },
+        { "id": "other", "agent_id_or_alias": agent_id, "command_template": "echo independent" },
+    ]));
+
+    let operation = orchestrator.begin_operation(None).unwrap();
+    let run = orchestrator.run_workflow(workflow, &operation).await.unwrap();
+
+    assert_eq!(run.status, WorkflowStatus::Failed);
+    let broken = &run.step_results["broken"];
+    assert_eq!(broken.status, StepStatus::Failed);
+    assert!(broken.error.as_deref().unwrap().contains("not found"), "{:?}", broken.error);
+    assert_eq!(run.step_results["after"].status, StepStatus::Skipped);
+    assert_eq!(run.step_results["last"].status, StepStatus::Skipped);
+    assert_eq!(run.step_results["other"].status, StepStatus::Succeeded);
+    assert_eq!(run.step_results["other"].output.as_deref(), Some("independent"));
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn run_rejects_an_invalid_spec_before_sending_anything() {
+    let orchestrator = AgentOrchestrator::new();
+    let operation = orchestrator.begin_operation(None).unwrap();
+    let workflow = spec(json!([
+        { "id": "a", "agent_id_or_alias": "missing", "command_template": "1", "depends_on": ["a"] },
+    ]));
+    assert!(orchestrator.run_workflow(workflow, &operation).await.is_err());
+}
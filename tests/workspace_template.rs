@@ -0,0 +1,82 @@
+// `workspace_path` templates expanded before an agent spawns.
+use conductor_max::orchestrator::{AgentConfig, AgentOrchestrator, AgentType};
+use std::path::PathBuf;
+
+mod common;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("conductor-workspace-{}", uuid::Uuid::new_v4()));
+    let dir = dir.join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.canonicalize().unwrap()
+}
+
+/// Spawns a bash agent in `template` and returns the workspace it got.
+async fn spawned_workspace(template: &str, agent_id: &str) -> anyhow::Result<String> {
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = common::pipe_bash_config();
+    config.agent_id = Some(agent_id.to_string());
+    config.workspace_path = Some(template.to_string());
+    orchestrator.spawn_agent(config).await?;
+    let status = orchestrator.get_agent_status(agent_id).await?;
+    orchestrator.kill_agent(agent_id).await?;
+    Ok(status["workspace"].as_str().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn expands_home() {
+    let home = PathBuf::from(std::env::var("HOME").unwrap()).canonicalize().unwrap();
+    assert_eq!(spawned_workspace("~", "home-agent").await.unwrap(), home.to_str().unwrap());
+    assert_eq!(spawned_workspace("~/", "home-slash-agent").await.unwrap(), home.to_str().unwrap());
+}
+
+#[tokio::test]
+async fn expands_environment_variables() {
+    let dir = scratch_dir("env");
+    std::env::set_var("CONDUCTOR_TEST_WORKSPACE_ROOT", dir.parent().unwrap());
+    let expected = dir.to_str().unwrap();
+    assert_eq!(spawned_workspace("$CONDUCTOR_TEST_WORKSPACE_ROOT/env", "env-agent").await.unwrap(), expected);
+    assert_eq!(spawned_workspace("${CONDUCTOR_TEST_WORKSPACE_ROOT}/env", "braced-agent").await.unwrap(), expected);
+
+    let error = spawned_workspace("$CONDUCTOR_TEST_WORKSPACE_UNSET/env", "unset-agent").await.unwrap_err();
+    assert!(error.to_string().contains("CONDUCTOR_TEST_WORKSPACE_UNSET"), "{}", error);
+}
+
+#[tokio::test]
+async fn expands_agent_id() {
+    let dir = scratch_dir("id-agent");
+    let template = format!("{}/{{agent_id}}", dir.parent().unwrap().display());
+    assert_eq!(spawned_workspace(&template, "id-agent").await.unwrap(), dir.to_str().unwrap());
+}
+
+#[tokio::test]
+async fn expands_agent_type() {
+    let dir = scratch_dir("bash");
+    let template = format!("{}/{{agent_type}}", dir.parent().unwrap().display());
+    assert_eq!(spawned_workspace(&template, "type-agent").await.unwrap(), dir.to_str().unwrap());
+}
+
+#[tokio::test]
+async fn expands_date() {
+    let dir = scratch_dir(&chrono::Local::now().format("%Y-%m-%d").to_string());
+    let template = format!("{}/{{date}}", dir.parent().unwrap().display());
+    assert_eq!(spawned_workspace(&template, "date-agent").await.unwrap(), dir.to_str().unwrap());
+}
+
+#[tokio::test]
+async fn rejects_missing_paths_and_unknown_placeholders() {
+    let dir = scratch_dir("present");
+    let missing = format!("{}/{{agent_id}}", dir.display());
+    let error = spawned_workspace(&missing, "missing-agent").await.unwrap_err();
+    assert!(error.to_string().contains("does not exist"), "{}", error);
+    assert!(error.to_string().contains("missing-agent"), "{}", error);
+
+    let unknown = format!("{}/{{branch}}", dir.display());
+    let error = spawned_workspace(&unknown, "unknown-agent").await.unwrap_err();
+    assert!(error.to_string().contains("{branch}"), "{}", error);
+
+    let orchestrator = AgentOrchestrator::new();
+    let mut config = AgentConfig::new(AgentType::Bash);
+    config.workspace_path = Some(missing);
+    assert!(orchestrator.validate_agent(&config).is_err());
+}
@@ -0,0 +1,109 @@
+// Listing the files in an agent's workspace.
+use conductor_max::orchestrator::{AgentError, AgentOrchestrator, OrchestratorConfig, MAX_TREE_ENTRIES};
+use std::path::PathBuf;
+
+mod common;
+
+/// A workspace with files three directories deep.
+fn workspace() -> PathBuf {
+    let root = std::env::temp_dir().join(format!("conductor-tree-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(root.join("src/nested/deeper")).unwrap();
+    std::fs::write(root.join("README.md"), "hello").unwrap();
+    std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+    std::fs::write(root.join("src/nested/deeper/far.txt"), "far").unwrap();
+    root
+}
+
+async fn agent_in(orchestrator: &AgentOrchestrator, workspace: &std::path::Path) -> String {
+    let mut config = common::pipe_bash_config();
+    config.workspace_path = Some(workspace.display().to_string());
+    orchestrator.spawn_agent(config).await.unwrap()
+}
+
+#[tokio::test]
+async fn lists_files_down_to_the_configured_depth() {
+    let root = workspace();
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = agent_in(&orchestrator, &root).await;
+
+    let tree = orchestrator.get_workspace_file_tree(&agent_id).await.unwrap().entries;
+    let paths: Vec<&str> = tree.iter().map(|entry| entry.path.as_str()).collect();
+    assert_eq!(paths, ["README.md", "src", "src/main.rs", "src/nested", "src/nested/deeper"]);
+    let readme = &tree[0];
+    assert_eq!((readme.size_bytes, readme.is_dir), (5, false));
+    assert!(tree[1].is_dir);
+
+    let nested = orchestrator.list_agent_workspace(&agent_id, "src/nested").await.unwrap().entries;
+    let paths: Vec<&str> = nested.iter().map(|entry| entry.path.as_str()).collect();
+    assert_eq!(paths, ["src/nested/deeper", "src/nested/deeper/far.txt"]);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+
+    let shallow = AgentOrchestrator::with_config(OrchestratorConfig {
+        workspace_tree_depth: 1,
+        ..OrchestratorConfig::default()
+    });
+    let agent_id = agent_in(&shallow, &root).await;
+    let tree = shallow.get_workspace_file_tree(&agent_id).await.unwrap().entries;
+    assert_eq!(tree.iter().map(|entry| entry.path.as_str()).collect::<Vec<_>>(), ["README.md", "src"]);
+    shallow.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_paths_outside_the_workspace() {
+    let root = workspace();
+    let outside = workspace();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = agent_in(&orchestrator, &root).await;
+
+    let mut escapes = vec!["..".to_string(), "src/../..".to_string(), outside.display().to_string()];
+    if cfg!(unix) {
+        escapes.push("escape".to_string());
+        // Listed, but not followed
+        let tree = orchestrator.get_workspace_file_tree(&agent_id).await.unwrap().entries;
+        assert!(tree.iter().any(|entry| entry.path == "escape" && !entry.is_dir));
+        assert!(!tree.iter().any(|entry| entry.path.starts_with("escape/")));
+    }
+    for path in escapes {
+        let error = orchestrator.list_agent_workspace(&agent_id, &path).await.unwrap_err();
+        assert!(
+            matches!(error.downcast_ref(), Some(AgentError::WorkspaceAccessDenied { .. })),
+            "{}: {}", path, error
+        );
+    }
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn skips_unreadable_directories_and_reports_truncation() {
+    let root = workspace();
+    let orchestrator = AgentOrchestrator::new();
+    let agent_id = agent_in(&orchestrator, &root).await;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let locked = root.join("src/nested");
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        let tree = orchestrator.get_workspace_file_tree(&agent_id).await.unwrap();
+        assert!(tree.entries.iter().any(|entry| entry.path == "src/main.rs"));
+        assert!(tree.entries.iter().any(|entry| entry.path == "src/nested"));
+        // Root reads it regardless
+        if std::fs::read_dir(&locked).is_err() {
+            assert!(!tree.entries.iter().any(|entry| entry.path.starts_with("src/nested/")));
+        }
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    assert!(!orchestrator.get_workspace_file_tree(&agent_id).await.unwrap().truncated);
+
+    std::fs::create_dir(root.join("many")).unwrap();
+    for i in 0..MAX_TREE_ENTRIES {
+        std::fs::write(root.join("many").join(i.to_string()), "").unwrap();
+    }
+    let tree = orchestrator.get_workspace_file_tree(&agent_id).await.unwrap();
+    assert!(tree.truncated);
+    assert_eq!(tree.entries.len(), MAX_TREE_ENTRIES);
+    orchestrator.kill_agent(&agent_id).await.unwrap();
+    std::fs::remove_dir_all(root).unwrap();
+}